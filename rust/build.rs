@@ -1,24 +1,59 @@
-// build.rs - Compile CUDA kernel to PTX when CUDA Toolkit is available
+// build.rs - Compile the CUDA kernels to PTX when the CUDA Toolkit is available
 use std::env;
-use std::path::PathBuf;
-use std::process::Command;
-
-// Helper function to find nvcc in common CUDA installation paths
-fn find_nvcc() -> Option<String> {
-    // Common CUDA installation paths on Windows
-    let cuda_paths = vec![
-        r"C:\Program Files\NVIDIA GPU Computing Toolkit\CUDA\v13.0\bin\nvcc.exe",
-        r"C:\Program Files\NVIDIA GPU Computing Toolkit\CUDA\v12.0\bin\nvcc.exe",
-        r"C:\Program Files\NVIDIA GPU Computing Toolkit\CUDA\v11.8\bin\nvcc.exe",
-    ];
-    
-    for path in cuda_paths {
-        if std::path::Path::new(path).exists() {
-            return Some(path.to_string());
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+// Kernel source, relative to the crate root
+const KERNEL_SOURCE: &str = "kernels/gpuseed.cu";
+
+// Compute capabilities built when GPUSEED_CUDA_ARCHS is not set (Pascal to Hopper). The driver
+// compiles PTX for the device it loads on, so newer GPUs run the newest image that is not
+// newer than they are
+const DEFAULT_ARCHS: &[u32] = &[60, 70, 75, 80, 86, 90];
+
+// Helper function to find nvcc: CUDA_PATH and CUDA_HOME first, then PATH, then the default
+// installation paths
+fn find_nvcc() -> Option<PathBuf> {
+    let exe = if cfg!(windows) { "nvcc.exe" } else { "nvcc" };
+    let mut candidates = Vec::new();
+    for var in ["CUDA_PATH", "CUDA_HOME"] {
+        if let Some(root) = env::var_os(var) {
+            candidates.push(PathBuf::from(root).join("bin").join(exe));
         }
     }
-    
-    None
+    candidates.push(PathBuf::from(exe));
+    if cfg!(windows) {
+        candidates.extend(windows_toolkits().into_iter().map(|root| root.join("bin").join(exe)));
+    } else {
+        candidates.push(PathBuf::from("/usr/local/cuda/bin/nvcc"));
+        candidates.push(PathBuf::from("/opt/cuda/bin/nvcc"));
+    }
+
+    candidates.into_iter().find(|nvcc| {
+        Command::new(nvcc)
+            .arg("--version")
+            .output()
+            .is_ok_and(|output| output.status.success())
+    })
+}
+
+// CUDA Toolkit installations on Windows, newest version first
+fn windows_toolkits() -> Vec<PathBuf> {
+    let base = Path::new(r"C:\Program Files\NVIDIA GPU Computing Toolkit\CUDA");
+    let Ok(entries) = std::fs::read_dir(base) else {
+        return Vec::new();
+    };
+    let mut toolkits: Vec<(Vec<u32>, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            // Directories are named after the version, e.g. v12.4
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let version = name.strip_prefix('v')?.split('.').map(|part| part.parse().ok()).collect::<Option<Vec<u32>>>()?;
+            Some((version, entry.path()))
+        })
+        .collect();
+    toolkits.sort_by(|a, b| b.0.cmp(&a.0));
+    toolkits.into_iter().map(|(_, path)| path).collect()
 }
 
 // Helper function to find Visual Studio vcvars64.bat
@@ -35,194 +70,169 @@ fn find_vcvars64() -> Option<String> {
         r"C:\Program Files (x86)\Microsoft Visual Studio\2019\Professional\VC\Auxiliary\Build\vcvars64.bat",
         r"C:\Program Files (x86)\Microsoft Visual Studio\2019\Enterprise\VC\Auxiliary\Build\vcvars64.bat",
     ];
-    
+
     for path in vs_paths {
         if std::path::Path::new(path).exists() {
             return Some(path.to_string());
         }
     }
-    
+
     None
 }
 
-fn main() {
-    let out_dir = env::var("OUT_DIR").unwrap();
-    let module_path = PathBuf::from(&out_dir).join("kernel_ptx.rs");
-    
-    // Try to find nvcc in common CUDA installation paths
-    let nvcc_path = find_nvcc();
-    
-    // Check if CUDA Toolkit is installed
-    let nvcc_output = if let Some(ref path) = nvcc_path {
-        Command::new(path)
-            .arg("--version")
-            .output()
-    } else {
-        Command::new("nvcc")
-            .arg("--version")
-            .output()
+// Target compute capabilities from GPUSEED_CUDA_ARCHS ("75,86", "sm_75,sm_86" or "7.5,8.6"),
+// oldest first
+fn target_archs() -> Vec<u32> {
+    let Ok(value) = env::var("GPUSEED_CUDA_ARCHS") else {
+        return DEFAULT_ARCHS.to_vec();
     };
-    
-    let ptx_available = if nvcc_output.is_ok() {
-        println!("cargo:warning=CUDA Toolkit detected. Kernel compilation will be attempted.");
-        
-        // CUDA kernel source using cuRAND for true GPU random number generation
-        let kernel_source = r#"
-#include <curand_kernel.h>
-
-// Kernel to initialize cuRAND states
-extern "C" __global__ void init_curand_states(
-    curandState* states,
-    unsigned long long seed,
-    int num_states
-) {
-    int idx = blockIdx.x * blockDim.x + threadIdx.x;
-    if (idx < num_states) {
-        curand_init(seed, idx, 0, &states[idx]);
+    let mut archs = Vec::new();
+    for arch in value.split(',').map(str::trim).filter(|arch| !arch.is_empty()) {
+        let digits = arch.trim_start_matches("sm_").trim_start_matches("compute_").replace('.', "");
+        match digits.parse::<u32>() {
+            Ok(parsed) if parsed >= 10 => {
+                if !archs.contains(&parsed) {
+                    archs.push(parsed);
+                }
+            }
+            _ => println!("cargo:warning=Ignoring invalid CUDA architecture \"{}\" in GPUSEED_CUDA_ARCHS", arch),
+        }
     }
+    if archs.is_empty() {
+        println!("cargo:warning=GPUSEED_CUDA_ARCHS has no valid architecture. Using the defaults.");
+        return DEFAULT_ARCHS.to_vec();
+    }
+    archs.sort();
+    archs
 }
 
-// Kernel to generate random bytes using cuRAND
-extern "C" __global__ void generate_random_bytes(
-    curandState* states,
-    unsigned char* output,
-    int num_elements
-) {
-    int idx = blockIdx.x * blockDim.x + threadIdx.x;
-    if (idx < num_elements) {
-        curandState local_state = states[idx];
-        
-        // Generate 16 bytes per element using cuRAND
-        for (int i = 0; i < 16; i++) {
-            // Generate random unsigned int and take lower 8 bits
-            unsigned int rand_val = curand(&local_state);
-            output[idx * 16 + i] = (unsigned char)(rand_val & 0xFF);
-        }
-        
-        // Save state back
-        states[idx] = local_state;
+// Run nvcc. On Windows, nvcc requires cl.exe (Visual Studio C++ compiler) to be in PATH, so it
+// runs from a batch script that calls vcvars64.bat first
+fn run_nvcc(nvcc: &Path, args: &[String], out_dir: &Path, vcvars: Option<&str>) -> std::io::Result<Output> {
+    if let Some(vcvars_path) = vcvars {
+        let batch_script_path = out_dir.join("compile_kernel.bat");
+        let quoted: Vec<String> = args.iter().map(|arg| format!("\"{}\"", arg)).collect();
+        let batch_content = format!(
+            "@echo off\n\
+            call \"{}\" >nul 2>&1\n\
+            \"{}\" {}\n",
+            vcvars_path,
+            nvcc.display(),
+            quoted.join(" ")
+        );
+        std::fs::write(&batch_script_path, batch_content)?;
+        return Command::new("cmd.exe").arg("/c").arg(&batch_script_path).output();
     }
+    Command::new(nvcc).args(args).output()
 }
-"#;
-        
-        // Write kernel to temporary file
-        let kernel_path = PathBuf::from(&out_dir).join("kernel.cu");
-        std::fs::write(&kernel_path, kernel_source).unwrap();
-        
-        // Compile kernel to PTX
-        // On Windows, nvcc requires cl.exe (Visual Studio C++ compiler) to be in PATH
-        let ptx_path = PathBuf::from(&out_dir).join("kernel.ptx");
-        let nvcc_cmd = nvcc_path.as_ref().map(|s| s.as_str()).unwrap_or("nvcc");
-        
-        // Find Visual Studio vcvars64.bat to set up the environment
-        let vcvars64_path = find_vcvars64();
-        
-        let compile_result = if let Some(ref vcvars_path) = vcvars64_path {
-            // On Windows, we need to run nvcc through cmd.exe with vcvars64.bat
-            // Create a batch script that sets up the environment and runs nvcc
-            let batch_script_path = PathBuf::from(&out_dir).join("compile_kernel.bat");
-            let kernel_path_str = kernel_path.to_string_lossy().replace('/', "\\");
-            let ptx_path_str = ptx_path.to_string_lossy().replace('/', "\\");
-            let nvcc_cmd_str = nvcc_cmd.replace('/', "\\");
-            let vcvars_path_str = vcvars_path.replace('/', "\\");
-            
-            // Create batch script that calls vcvars64.bat and then nvcc
-            let batch_content = format!(
-                "@echo off\n\
-                call \"{}\" >nul 2>&1\n\
-                \"{}\" -ptx -arch=sm_86 -o \"{}\" \"{}\"\n",
-                vcvars_path_str, nvcc_cmd_str, ptx_path_str, kernel_path_str
-            );
-            
-            // Write batch script, if it fails, try direct compilation
-            match std::fs::write(&batch_script_path, batch_content) {
-                Ok(_) => {
-                    // Execute the batch script
-                    Command::new("cmd.exe")
-                        .arg("/c")
-                        .arg(&batch_script_path)
-                        .output()
-                }
-                Err(e) => {
-                    println!("cargo:warning=Failed to create batch script: {}", e);
-                    println!("cargo:warning=Attempting direct compilation without Visual Studio environment.");
-                    // Fallback to direct compilation
-                    Command::new(nvcc_cmd)
-                        .arg("-ptx")
-                        .arg("-arch=sm_86")
-                        .arg("-o")
-                        .arg(&ptx_path)
-                        .arg(&kernel_path)
-                        .output()
-                }
-            }
-        } else {
-            // Try without Visual Studio environment (might work if cl.exe is already in PATH)
+
+// Compile one PTX image per target architecture; returns (architecture, file name in OUT_DIR)
+fn compile_kernels(out_dir: &Path) -> Vec<(u32, String)> {
+    let Some(nvcc) = find_nvcc() else {
+        println!("cargo:warning=CUDA Toolkit (nvcc) not found in CUDA_PATH, CUDA_HOME, PATH or /usr/local/cuda. GPU support will be unavailable.");
+        println!("cargo:warning=Install CUDA Toolkit from https://developer.nvidia.com/cuda-downloads");
+        return Vec::new();
+    };
+    println!("cargo:warning=CUDA Toolkit detected ({}). Kernel compilation will be attempted.", nvcc.display());
+
+    let vcvars = if cfg!(windows) {
+        let vcvars = find_vcvars64();
+        if vcvars.is_none() {
+            // Might work if cl.exe is already in PATH
             println!("cargo:warning=Visual Studio vcvars64.bat not found. Attempting compilation without it.");
-            Command::new(nvcc_cmd)
-                .arg("-ptx")
-                .arg("-arch=sm_86") // RTX 3080 Ti has compute capability 8.6
-                .arg("-o")
-                .arg(&ptx_path)
-                .arg(&kernel_path)
-                .output()
-        };
-        
-        match compile_result {
+        }
+        vcvars
+    } else {
+        None
+    };
+
+    let source = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join(KERNEL_SOURCE);
+    let mut images = Vec::new();
+    for arch in target_archs() {
+        let file = format!("kernel_compute_{}.ptx", arch);
+        let ptx_path = out_dir.join(&file);
+        // An image left over from an earlier build must not pass for this one
+        let _ = std::fs::remove_file(&ptx_path);
+        let args = vec![
+            "-ptx".to_string(),
+            format!("-arch=compute_{}", arch),
+            "-o".to_string(),
+            ptx_path.display().to_string(),
+            source.display().to_string(),
+        ];
+
+        match run_nvcc(&nvcc, &args, out_dir, vcvars.as_deref()) {
+            Ok(output) if output.status.success() && ptx_path.exists() => images.push((arch, file)),
             Ok(output) => {
-                if output.status.success() && ptx_path.exists() {
-                    println!("cargo:warning=CUDA kernel compiled successfully to {:?}", ptx_path);
-                    true
-                } else {
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    println!("cargo:warning=Failed to compile CUDA kernel:");
-                    if !stderr.is_empty() {
-                        println!("cargo:warning=STDERR: {}", stderr);
-                    }
-                    if !stdout.is_empty() {
-                        println!("cargo:warning=STDOUT: {}", stdout);
-                    }
-                    println!("cargo:warning=Exit code: {:?}", output.status.code());
-                    println!("cargo:warning=GPU support will be limited.");
-                    false
+                // Usually an architecture this nvcc does not support; the others are still built
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                println!("cargo:warning=Failed to compile CUDA kernel for compute_{}:", arch);
+                if !stderr.is_empty() {
+                    println!("cargo:warning=STDERR: {}", stderr);
+                }
+                if !stdout.is_empty() {
+                    println!("cargo:warning=STDOUT: {}", stdout);
                 }
+                println!("cargo:warning=Exit code: {:?}", output.status.code());
             }
             Err(e) => {
                 println!("cargo:warning=Failed to run nvcc: {}", e);
-                println!("cargo:warning=GPU support will be limited.");
-                false
+                break;
             }
         }
+    }
+
+    if images.is_empty() {
+        println!("cargo:warning=No CUDA kernel image was compiled. GPU support will be unavailable.");
     } else {
-        println!("cargo:warning=CUDA Toolkit (nvcc) not found. GPU support will be limited.");
-        println!("cargo:warning=Install CUDA Toolkit from https://developer.nvidia.com/cuda-downloads");
-        false
-    };
-    
-    // Always generate the module file, even if PTX is not available
-    // This ensures the Rust code compiles regardless of CUDA availability
-    let module_content = if ptx_available {
-        format!(
-            "// Auto-generated module with embedded CUDA kernel PTX\n\
-            // This file is generated by build.rs\n\
-            \n\
-            pub const KERNEL_PTX: &str = include_str!(concat!(env!(\"OUT_DIR\"), \"/kernel.ptx\"));\n"
-        )
+        let archs: Vec<String> = images.iter().map(|(arch, _)| format!("compute_{}", arch)).collect();
+        println!("cargo:warning=CUDA kernels compiled to PTX for {}", archs.join(", "));
+    }
+    images
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={}", KERNEL_SOURCE);
+    for var in ["CUDA_PATH", "CUDA_HOME", "GPUSEED_CUDA_ARCHS"] {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let module_path = out_dir.join("kernel_ptx.rs");
+
+    // The kernels are only loaded with the gpu feature
+    let images = if env::var_os("CARGO_FEATURE_GPU").is_some() {
+        compile_kernels(&out_dir)
     } else {
-        format!(
-            "// Auto-generated module with embedded CUDA kernel PTX\n\
-            // This file is generated by build.rs\n\
-            // NOTE: CUDA kernel PTX is not available. GPU support will not work.\n\
-            \n\
-            pub const KERNEL_PTX: &str = \"\";\n"
-        )
+        Vec::new()
     };
-    
+
+    // Always generate the module file, even if no PTX is available
+    // This ensures the Rust code compiles regardless of CUDA availability (the GPU then reports
+    // itself unavailable at runtime)
+    let mut module_content = String::from(
+        "// Auto-generated module with embedded CUDA kernel PTX\n\
+        // This file is generated by build.rs\n",
+    );
+    if images.is_empty() {
+        module_content.push_str("// NOTE: CUDA kernel PTX is not available. GPU support will not work.\n");
+    }
+    module_content.push_str(
+        "\n\
+        // Compute capability (major * 10 + minor) the PTX was compiled for, oldest first\n\
+        pub const KERNEL_IMAGES: &[(u32, &str)] = &[\n",
+    );
+    for (arch, file) in &images {
+        module_content.push_str(&format!(
+            "    ({}, include_str!(concat!(env!(\"OUT_DIR\"), \"/{}\"))),\n",
+            arch, file
+        ));
+    }
+    module_content.push_str("];\n");
+
     if let Err(e) = std::fs::write(&module_path, module_content) {
         println!("cargo:warning=Failed to write PTX module: {}", e);
-    } else {
-        println!("cargo:warning=PTX module generated at {:?}", module_path);
     }
 }
-
@@ -0,0 +1,276 @@
+// CUDA kernels of gpuseed-rust, compiled to PTX by build.rs (one image per target compute
+// capability, see GPUSEED_CUDA_ARCHS) and loaded by src/gpu.rs.
+#include <curand_kernel.h>
+
+// Generators: XORWOW (the cuRAND default, curandState) or the counter-based Philox4x32-10
+// (curandStatePhilox4_32_10_t). Every candidate takes 128 bits of generator output, written as
+// four little-endian 32-bit words (host implementation of the Philox output: src/philox.rs).
+
+// Kernel to initialize cuRAND states
+extern "C" __global__ void init_curand_states(
+    curandState* states,
+    unsigned long long seed,
+    int num_states
+) {
+    int idx = blockIdx.x * blockDim.x + threadIdx.x;
+    if (idx < num_states) {
+        curand_init(seed, idx, 0, &states[idx]);
+    }
+}
+
+// Philox: `seed` is the key and every thread gets its own subsequence, counted from a random
+// `subsequence` chosen by the host (128 bits of seed material in total)
+extern "C" __global__ void init_philox_states(
+    curandStatePhilox4_32_10_t* states,
+    unsigned long long seed,
+    unsigned long long subsequence,
+    int num_states
+) {
+    int idx = blockIdx.x * blockDim.x + threadIdx.x;
+    if (idx < num_states) {
+        curand_init(seed, subsequence + idx, 0, &states[idx]);
+    }
+}
+
+__device__ void store_words(unsigned int x, unsigned int y, unsigned int z, unsigned int w, unsigned char* entropy) {
+    unsigned int words[4] = {x, y, z, w};
+    for (int i = 0; i < 4; i++) {
+        for (int j = 0; j < 4; j++) {
+            entropy[i * 4 + j] = (unsigned char)(words[i] >> (8 * j));
+        }
+    }
+}
+
+// 16 entropy bytes from four full 32-bit draws
+__device__ void draw_entropy(curandState* state, unsigned char* entropy) {
+    unsigned int x = curand(state);
+    unsigned int y = curand(state);
+    unsigned int z = curand(state);
+    unsigned int w = curand(state);
+    store_words(x, y, z, w, entropy);
+}
+
+// Philox produces 128 bits per call
+__device__ void draw_entropy(curandStatePhilox4_32_10_t* state, unsigned char* entropy) {
+    uint4 block = curand4(state);
+    store_words(block.x, block.y, block.z, block.w, entropy);
+}
+
+template <typename State>
+__device__ void generate_entropy(State* states, unsigned char* output, int num_elements) {
+    int idx = blockIdx.x * blockDim.x + threadIdx.x;
+    if (idx < num_elements) {
+        State local_state = states[idx];
+        draw_entropy(&local_state, output + idx * 16);
+        
+        // Save state back
+        states[idx] = local_state;
+    }
+}
+
+// Kernels to generate random bytes using cuRAND
+extern "C" __global__ void generate_random_bytes(
+    curandState* states,
+    unsigned char* output,
+    int num_elements
+) {
+    generate_entropy(states, output, num_elements);
+}
+
+extern "C" __global__ void generate_random_bytes_philox(
+    curandStatePhilox4_32_10_t* states,
+    unsigned char* output,
+    int num_elements
+) {
+    generate_entropy(states, output, num_elements);
+}
+
+// Scoring on the device (CPU reference: src/scoring.rs)
+// Only candidates under the threshold are written to the output buffer; the host also gets
+// the score histogram and the best candidate, so nothing else crosses the PCIe bus.
+#define MAX_SCORE 96
+#define HIT_RECORD_SIZE 24
+
+__constant__ unsigned int SHA256_K[64] = {
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2
+};
+
+__device__ __forceinline__ unsigned int rotr(unsigned int x, int n) {
+    return (x >> n) | (x << (32 - n));
+}
+
+// First word of SHA-256 of a 16-byte message (one padded block): holds the checksum bits
+__device__ unsigned int sha256_first_word(const unsigned char* m) {
+    unsigned int w[64];
+    for (int i = 0; i < 4; i++) {
+        w[i] = ((unsigned int)m[4 * i] << 24) | ((unsigned int)m[4 * i + 1] << 16)
+            | ((unsigned int)m[4 * i + 2] << 8) | (unsigned int)m[4 * i + 3];
+    }
+    w[4] = 0x80000000u;
+    for (int i = 5; i < 15; i++) {
+        w[i] = 0;
+    }
+    w[15] = 128;
+    for (int i = 16; i < 64; i++) {
+        unsigned int s0 = rotr(w[i - 15], 7) ^ rotr(w[i - 15], 18) ^ (w[i - 15] >> 3);
+        unsigned int s1 = rotr(w[i - 2], 17) ^ rotr(w[i - 2], 19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16] + s0 + w[i - 7] + s1;
+    }
+    unsigned int a = 0x6a09e667, b = 0xbb67ae85, c = 0x3c6ef372, d = 0xa54ff53a;
+    unsigned int e = 0x510e527f, f = 0x9b05688c, g = 0x1f83d9ab, h = 0x5be0cd19;
+    for (int i = 0; i < 64; i++) {
+        unsigned int s1 = rotr(e, 6) ^ rotr(e, 11) ^ rotr(e, 25);
+        unsigned int ch = (e & f) ^ (~e & g);
+        unsigned int t1 = h + s1 + ch + SHA256_K[i] + w[i];
+        unsigned int s0 = rotr(a, 2) ^ rotr(a, 13) ^ rotr(a, 22);
+        unsigned int maj = (a & b) ^ (a & c) ^ (b & c);
+        unsigned int t2 = s0 + maj;
+        h = g; g = f; f = e; e = d + t1; d = c; c = b; b = a; a = t1 + t2;
+    }
+    return a + 0x6a09e667;
+}
+
+// Total characters of the 12-word mnemonic of the entropy
+__device__ int score_entropy(const unsigned char* entropy, const unsigned char* word_lengths) {
+    // 132 bits: the entropy followed by the 4 checksum bits (one padding byte for the last window)
+    unsigned char bits[18];
+    for (int i = 0; i < 16; i++) {
+        bits[i] = entropy[i];
+    }
+    bits[16] = (unsigned char)((sha256_first_word(entropy) >> 28) << 4);
+    bits[17] = 0;
+    int chars = 0;
+    for (int word = 0; word < 12; word++) {
+        int bit = word * 11;
+        // The 11 bits of a word lie within 3 bytes
+        unsigned int window = ((unsigned int)bits[bit / 8] << 16) | ((unsigned int)bits[bit / 8 + 1] << 8)
+            | (unsigned int)bits[bit / 8 + 2];
+        chars += word_lengths[(window >> (13 - bit % 8)) & 0x7ff];
+    }
+    return chars;
+}
+
+// Histogram, best candidate (atomicMin of chars << 32 | index keeps the first on ties) and
+// compaction of hits: index and chars (u32 each) followed by the 16 entropy bytes
+__device__ void record_score(
+    const unsigned char* entropy, int chars, int idx, int threshold,
+    unsigned int* block_histogram, unsigned long long* best,
+    unsigned char* hits, unsigned int* hit_count
+) {
+    atomicAdd(&block_histogram[chars], 1u);
+    atomicMin(best, ((unsigned long long)chars << 32) | (unsigned int)idx);
+    if (chars < threshold) {
+        unsigned int slot = atomicAdd(hit_count, 1u);
+        unsigned char* record = hits + (size_t)slot * HIT_RECORD_SIZE;
+        ((unsigned int*)record)[0] = (unsigned int)idx;
+        ((unsigned int*)record)[1] = (unsigned int)chars;
+        for (int i = 0; i < 16; i++) {
+            record[8 + i] = entropy[i];
+        }
+    }
+}
+
+__device__ void flush_histogram(unsigned int* block_histogram, unsigned int* histogram) {
+    __syncthreads();
+    for (int i = threadIdx.x; i <= MAX_SCORE; i += blockDim.x) {
+        if (block_histogram[i] > 0) {
+            atomicAdd(&histogram[i], block_histogram[i]);
+        }
+    }
+}
+
+// Generate candidates with cuRAND (same bytes as generate_random_bytes) and score them.
+// The entropy stays in device memory; the host only copies the best candidate's.
+template <typename State>
+__device__ void score_generated(
+    State* states,
+    const unsigned char* word_lengths,
+    int threshold,
+    unsigned char* entropy_out,
+    unsigned char* hits,
+    unsigned int* hit_count,
+    unsigned int* histogram,
+    unsigned long long* best,
+    int num_elements
+) {
+    __shared__ unsigned int block_histogram[MAX_SCORE + 1];
+    for (int i = threadIdx.x; i <= MAX_SCORE; i += blockDim.x) {
+        block_histogram[i] = 0;
+    }
+    __syncthreads();
+
+    int idx = blockIdx.x * blockDim.x + threadIdx.x;
+    if (idx < num_elements) {
+        State local_state = states[idx];
+        unsigned char entropy[16];
+        draw_entropy(&local_state, entropy);
+        states[idx] = local_state;
+        for (int i = 0; i < 16; i++) {
+            entropy_out[idx * 16 + i] = entropy[i];
+        }
+        int chars = score_entropy(entropy, word_lengths);
+        record_score(entropy, chars, idx, threshold, block_histogram, best, hits, hit_count);
+    }
+    flush_histogram(block_histogram, histogram);
+}
+
+extern "C" __global__ void score_random(
+    curandState* states,
+    const unsigned char* word_lengths,
+    int threshold,
+    unsigned char* entropy_out,
+    unsigned char* hits,
+    unsigned int* hit_count,
+    unsigned int* histogram,
+    unsigned long long* best,
+    int num_elements
+) {
+    score_generated(states, word_lengths, threshold, entropy_out, hits, hit_count, histogram, best, num_elements);
+}
+
+extern "C" __global__ void score_random_philox(
+    curandStatePhilox4_32_10_t* states,
+    const unsigned char* word_lengths,
+    int threshold,
+    unsigned char* entropy_out,
+    unsigned char* hits,
+    unsigned int* hit_count,
+    unsigned int* histogram,
+    unsigned long long* best,
+    int num_elements
+) {
+    score_generated(states, word_lengths, threshold, entropy_out, hits, hit_count, histogram, best, num_elements);
+}
+
+// Score candidates supplied by the host (gpu-check compares this with the CPU reference)
+extern "C" __global__ void score_entropies(
+    const unsigned char* entropy_in,
+    const unsigned char* word_lengths,
+    int threshold,
+    unsigned char* hits,
+    unsigned int* hit_count,
+    unsigned int* histogram,
+    unsigned long long* best,
+    int num_elements
+) {
+    __shared__ unsigned int block_histogram[MAX_SCORE + 1];
+    for (int i = threadIdx.x; i <= MAX_SCORE; i += blockDim.x) {
+        block_histogram[i] = 0;
+    }
+    __syncthreads();
+
+    int idx = blockIdx.x * blockDim.x + threadIdx.x;
+    if (idx < num_elements) {
+        const unsigned char* entropy = entropy_in + idx * 16;
+        int chars = score_entropy(entropy, word_lengths);
+        record_score(entropy, chars, idx, threshold, block_histogram, best, hits, hit_count);
+    }
+    flush_histogram(block_histogram, histogram);
+}
@@ -0,0 +1,212 @@
+// BIP85 deterministic child seeds
+// https://github.com/bitcoin/bips/blob/master/bip-0085.mediawiki
+//
+// Children are 12-word BIP39 mnemonics at m/83696968'/39'/0'/12'/index'. The path up to the
+// index is derived once; each index then costs one hardened derivation (HMAC-SHA512 and a
+// scalar addition, no EC multiplication) plus the BIP85 HMAC.
+use bip39::{Language, Mnemonic};
+use bitcoin::bip32::{DerivationPath, Xpriv};
+use bitcoin::secp256k1::{Scalar, Secp256k1, SecretKey};
+use bitcoin::NetworkKind;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::secure::EntropyBatch;
+
+// m/83696968'/39'/{language: English}'/{words: 12}'
+const PARENT_PATH: &str = "m/83696968'/39'/0'/12'";
+const ENTROPY_KEY: &[u8] = b"bip-entropy-from-k";
+const HARDENED: u32 = 0x8000_0000;
+pub const MAX_INDEX: u32 = HARDENED - 1;
+
+/// Full derivation path of the child at `index`
+pub fn child_path(index: u32) -> String {
+    format!("{}/{}'", PARENT_PATH, index)
+}
+
+/// Parse a master given as an xprv or as a BIP39 mnemonic (with its optional passphrase)
+pub fn parse_master(input: &str, passphrase: &str) -> Result<Xpriv, String> {
+    let input = input.trim();
+    if input.starts_with("xprv") || input.starts_with("tprv") {
+        return Xpriv::from_str(input).map_err(|e| format!("Invalid xprv: {}", e));
+    }
+
+    let phrase = Zeroizing::new(
+        input
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    let mnemonic = Mnemonic::parse_in_normalized(Language::English, &phrase)
+        .map_err(|e| format!("Invalid master mnemonic: {}", e))?;
+    let seed = Zeroizing::new(mnemonic.to_seed(passphrase));
+    Xpriv::new_master(NetworkKind::Main, seed.as_slice())
+        .map_err(|e| format!("Failed to derive master key: {}", e))
+}
+
+/// Entropy source handing out BIP85 children at consecutive indices
+pub struct Bip85Source {
+    parent_key: SecretKey,
+    chain_code: Zeroizing<[u8; 32]>,
+    next_index: AtomicU64,
+}
+
+impl Bip85Source {
+    pub fn new(master: &Xpriv, start_index: u32) -> Result<Self, String> {
+        let secp = Secp256k1::signing_only();
+        let path = DerivationPath::from_str(PARENT_PATH).expect("valid BIP85 path");
+        let parent = master
+            .derive_priv(&secp, &path)
+            .map_err(|e| format!("Failed to derive {}: {}", PARENT_PATH, e))?;
+        Ok(Self {
+            parent_key: parent.private_key,
+            chain_code: Zeroizing::new(parent.chain_code.to_bytes()),
+            next_index: AtomicU64::new(start_index as u64),
+        })
+    }
+
+    /// Claim the next `count` indices; returns the first index and the children's entropy,
+    /// or None once the index space is exhausted
+    pub fn next_batch(&self, count: usize) -> Option<(u32, EntropyBatch)> {
+        let start = self.next_index.fetch_add(count as u64, Ordering::Relaxed);
+        if start > MAX_INDEX as u64 {
+            return None;
+        }
+        let end = (start + count as u64).min(MAX_INDEX as u64 + 1);
+
+        let mut batch = Zeroizing::new(Vec::with_capacity((end - start) as usize));
+        for index in start..end {
+            // An invalid child key (probability below 2^-127) leaves all-zero entropy
+            let mut entropy = self.child_entropy(index as u32).unwrap_or([0u8; 16]);
+            batch.push(entropy);
+            entropy.zeroize();
+        }
+        Some((start as u32, batch))
+    }
+
+    /// 128 bits of BIP85 entropy (12-word mnemonic) for one index
+    pub fn child_entropy(&self, index: u32) -> Option<[u8; 16]> {
+        // BIP32 hardened private derivation: I = HMAC-SHA512(c, 0x00 || k || index)
+        let mut mac = Hmac::<Sha512>::new_from_slice(self.chain_code.as_slice())
+            .expect("HMAC accepts any key length");
+        mac.update(&[0]);
+        mac.update(Zeroizing::new(self.parent_key.secret_bytes()).as_slice());
+        mac.update(&(index | HARDENED).to_be_bytes());
+        let i = Zeroizing::new(<[u8; 64]>::from(mac.finalize().into_bytes()));
+
+        let mut tweak_bytes = Zeroizing::new([0u8; 32]);
+        tweak_bytes.copy_from_slice(&i[..32]);
+        let tweak = Scalar::from_be_bytes(*tweak_bytes).ok()?;
+        let mut child = self.parent_key.add_tweak(&tweak).ok()?;
+
+        // BIP85: entropy = HMAC-SHA512(key = "bip-entropy-from-k", msg = k)
+        let mut mac = Hmac::<Sha512>::new_from_slice(ENTROPY_KEY).expect("HMAC accepts any key length");
+        mac.update(Zeroizing::new(child.secret_bytes()).as_slice());
+        child.non_secure_erase();
+        let full = Zeroizing::new(<[u8; 64]>::from(mac.finalize().into_bytes()));
+
+        let mut entropy = [0u8; 16];
+        entropy.copy_from_slice(&full[..16]);
+        Some(entropy)
+    }
+}
+
+impl Drop for Bip85Source {
+    fn drop(&mut self) {
+        self.parent_key.non_secure_erase();
+    }
+}
+
+/// Prompt for the master (hidden input) and, optionally, its BIP39 passphrase
+pub fn prompt_master(with_passphrase: bool) -> Result<Xpriv, String> {
+    let input = Zeroizing::new(
+        rpassword::prompt_password("BIP85 master (mnemonic or xprv): ")
+            .map_err(|e| format!("Could not read master: {}", e))?,
+    );
+    let passphrase = if with_passphrase {
+        Zeroizing::new(
+            rpassword::prompt_password("Master BIP39 passphrase: ")
+                .map_err(|e| format!("Could not read passphrase: {}", e))?,
+        )
+    } else {
+        Zeroizing::new(String::new())
+    };
+    parse_master(&input, &passphrase)
+}
+
+/// `bip85-derive` subcommand: re-derive the child mnemonic at one index
+pub fn derive(index: u32, with_passphrase: bool) -> Result<(), String> {
+    if index > MAX_INDEX {
+        return Err(format!("Index must be at most {}", MAX_INDEX));
+    }
+    let master = prompt_master(with_passphrase)?;
+    let source = Bip85Source::new(&master, index)?;
+    let entropy = Zeroizing::new(
+        source
+            .child_entropy(index)
+            .ok_or_else(|| format!("Index {} has no valid child key", index))?,
+    );
+    let mnemonic = Mnemonic::from_entropy_in(Language::English, entropy.as_slice())
+        .map_err(|e| format!("Invalid entropy: {}", e))?;
+    // Word by word, so the phrase is never formatted into an intermediate string
+    let mut out = io::stdout().lock();
+    let written = (|| -> io::Result<()> {
+        writeln!(out, "Path: {}", child_path(index))?;
+        write!(out, "Mnemonic:")?;
+        for word in mnemonic.words() {
+            write!(out, " {}", word)?;
+        }
+        writeln!(out)?;
+        writeln!(out, "Total characters: {}", mnemonic.words().map(str::len).sum::<usize>())
+    })();
+    written.map_err(|e| format!("Could not write the mnemonic: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hex::FromHex;
+
+    // BIP85 test vector of the BIP39 application (English, 12 words, index 0)
+    const MASTER: &str =
+        "xprv9s21ZrQH143K2LBWUUQRFXhucrQqBpKdRRxNVq2zBqsx8HVqFk2uYo8kmbaLLHRdqtQpUm98uKfu3vca1LqdGhUtyoFnCNkfmXRyPXLjbKb";
+
+    #[test]
+    fn derives_the_bip39_test_vector() {
+        let master = parse_master(MASTER, "").unwrap();
+        let source = Bip85Source::new(&master, 0).unwrap();
+        let entropy = source.child_entropy(0).unwrap();
+        assert_eq!(entropy, <[u8; 16]>::from_hex("6250b68daf746d12a24d58b4787a714b").unwrap());
+        let mnemonic = Mnemonic::from_entropy_in(Language::English, &entropy).unwrap();
+        assert_eq!(
+            mnemonic.to_string(),
+            "girl mad pet galaxy egg matter matrix prison refuse sense ordinary nose"
+        );
+
+        // Batches hand out consecutive indices
+        let (first, batch) = source.next_batch(2).unwrap();
+        assert_eq!(first, 0);
+        assert_eq!(batch[0], entropy);
+        assert_eq!(batch[1], source.child_entropy(1).unwrap());
+    }
+
+    #[test]
+    fn formats_hardened_child_paths() {
+        assert_eq!(child_path(0), "m/83696968'/39'/0'/12'/0'");
+        assert_eq!(child_path(MAX_INDEX), "m/83696968'/39'/0'/12'/2147483647'");
+        // The path derives the same child as the source
+        let master = parse_master(MASTER, "").unwrap();
+        let secp = Secp256k1::signing_only();
+        let child = master.derive_priv(&secp, &DerivationPath::from_str(&child_path(7)).unwrap()).unwrap();
+        let mut mac = Hmac::<Sha512>::new_from_slice(ENTROPY_KEY).unwrap();
+        mac.update(&child.private_key.secret_bytes());
+        let expected = mac.finalize().into_bytes();
+        let source = Bip85Source::new(&master, 0).unwrap();
+        assert_eq!(source.child_entropy(7).unwrap(), expected[..16]);
+    }
+}
@@ -0,0 +1,947 @@
+// Distributed search (--coordinator / --join)
+// The coordinator hands out work units over TCP; worker nodes search them and report progress
+// and finds. A unit is a fixed range of a ChaCha20 stream keyed by the shared cluster secret
+// and a per-run id, so every node derives the candidates itself and only unit numbers and
+// offsets travel over the network - never seeds. The coordinator re-derives each reported seed
+// and applies the global collection rules, dedupe and run limits to it.
+use bitcoin::hex::{DisplayHex, FromHex};
+use hmac::{Hmac, Mac};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+
+use crate::limits::RunLimits;
+use crate::partition::PartitionRun;
+use crate::reporter::Reporter;
+use crate::secure::{EntropyBatch, SecretVec};
+use crate::stats::{RunStats, MAX_SCORE};
+use crate::worker::{self, CollectionRules, FoundSeed};
+
+const PROTOCOL_VERSION: u64 = 1;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// Worker nodes report progress this often; the reports double as their heartbeat
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+// A node that sends nothing for this long is dropped and its units are reassigned
+const NODE_TIMEOUT: Duration = Duration::from_secs(30);
+// How long the coordinator keeps connections open for final reports when stopping
+const STOP_GRACE: Duration = Duration::from_secs(3);
+const MAX_MESSAGE: u64 = 64 * 1024;
+const MIN_SECRET_LEN: usize = 16;
+
+/// Shared secret of the cluster, read from a file on every node
+pub struct ClusterSecret {
+    key: Zeroizing<[u8; 32]>,
+}
+
+impl ClusterSecret {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = Zeroizing::new(
+            fs::read_to_string(path).map_err(|e| format!("Could not read cluster secret {}: {}", path, e))?,
+        );
+        let secret = content.trim();
+        if secret.len() < MIN_SECRET_LEN {
+            return Err(format!(
+                "Cluster secret in {} must be at least {} characters",
+                path, MIN_SECRET_LEN
+            ));
+        }
+        Ok(Self {
+            key: Zeroizing::new(Sha256::digest(secret.as_bytes()).into()),
+        })
+    }
+
+    fn mac(&self, label: &[u8], data: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.key.as_slice()).expect("HMAC accepts any key length");
+        mac.update(label);
+        mac.update(data);
+        mac
+    }
+
+    // Key of the candidate stream of one run
+    fn unit_key(&self, run_id: &[u8; 16]) -> UnitKey {
+        UnitKey(Zeroizing::new(self.mac(b"gpuseed units", run_id).finalize().into_bytes().into()))
+    }
+
+    /// Key of the candidate stream shared by the runs of a partitioned search
+    pub fn partition_key(&self) -> UnitKey {
+        UnitKey(Zeroizing::new(self.mac(b"gpuseed partition", &[]).finalize().into_bytes().into()))
+    }
+
+    // Proves knowledge of the secret without revealing it
+    fn proof(&self, challenge: &[u8; 16]) -> [u8; 32] {
+        self.mac(b"gpuseed auth", challenge).finalize().into_bytes().into()
+    }
+
+    fn verify(&self, challenge: &[u8; 16], proof: &[u8]) -> bool {
+        self.mac(b"gpuseed auth", challenge).verify_slice(proof).is_ok()
+    }
+}
+
+// Unit u is ChaCha20 stream u; candidate i of a unit is 16 bytes at position 16 * i
+pub struct UnitKey(Zeroizing<[u8; 32]>);
+
+impl UnitKey {
+    /// Short public id of the stream (runs with the same id search the same candidates)
+    pub fn fingerprint(&self) -> String {
+        Sha256::digest(self.0.as_slice())[..8].to_lower_hex_string()
+    }
+
+    pub fn entropy(&self, unit: u64, start: u64, count: u64) -> EntropyBatch {
+        let mut rng = ChaCha20Rng::from_seed(*self.0);
+        rng.set_stream(unit);
+        // Positions count 32-bit words
+        rng.set_word_pos(start as u128 * 4);
+        let mut batch = Zeroizing::new(vec![[0u8; 16]; count as usize]);
+        for entropy in batch.iter_mut() {
+            rng.fill_bytes(entropy);
+        }
+        batch
+    }
+}
+
+// One newline-delimited JSON message per line
+struct Lines {
+    reader: BufReader<TcpStream>,
+    buf: Vec<u8>,
+}
+
+impl Lines {
+    fn new(stream: &TcpStream) -> io::Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            buf: Vec::new(),
+        })
+    }
+
+    /// Next message, or None if the read timed out (a partial line is kept for the next call)
+    fn next(&mut self) -> io::Result<Option<Value>> {
+        let limit = MAX_MESSAGE.saturating_sub(self.buf.len() as u64);
+        match (&mut self.reader).take(limit).read_until(b'\n', &mut self.buf) {
+            Ok(_) if self.buf.ends_with(b"\n") => {
+                let message = serde_json::from_slice(&self.buf);
+                self.buf.clear();
+                message.map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+            Ok(_) if self.buf.len() as u64 >= MAX_MESSAGE => {
+                Err(io::Error::new(io::ErrorKind::InvalidData, "message too long"))
+            }
+            Ok(_) => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed")),
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+fn send(stream: &TcpStream, message: &Value) -> io::Result<()> {
+    let mut line = message.to_string();
+    line.push('\n');
+    (&*stream).write_all(line.as_bytes())
+}
+
+fn field(message: &Value, name: &str) -> Result<u64, String> {
+    message[name]
+        .as_u64()
+        .ok_or_else(|| format!("Invalid message from the other side (no \"{}\")", name))
+}
+
+fn hex16(message: &Value, name: &str) -> Result<[u8; 16], String> {
+    message[name]
+        .as_str()
+        .and_then(|hex| <[u8; 16]>::from_hex(hex).ok())
+        .ok_or_else(|| format!("Invalid message from the other side (no \"{}\")", name))
+}
+
+// Work unit bookkeeping of the coordinator
+#[derive(Default)]
+struct Ledger {
+    next_unit: u64,
+    // Units of nodes that left before finishing them, handed out again first
+    queued: VecDeque<u64>,
+    completed: u64,
+    reassigned: u64,
+}
+
+pub struct Coordinator {
+    listener: TcpListener,
+    secret: ClusterSecret,
+    run_id: [u8; 16],
+    key: UnitKey,
+    unit_size: u64,
+    ledger: Mutex<Ledger>,
+    nodes: AtomicUsize,
+}
+
+impl Coordinator {
+    pub fn bind(addr: &str, secret: ClusterSecret, unit_size: u64) -> Result<Self, String> {
+        let listener = TcpListener::bind(addr).map_err(|e| format!("Could not listen on {}: {}", addr, e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Could not configure coordinator socket: {}", e))?;
+        // A fresh stream of candidates for every run
+        let mut run_id = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut run_id);
+        Ok(Self {
+            listener,
+            key: secret.unit_key(&run_id),
+            secret,
+            run_id,
+            unit_size,
+            ledger: Mutex::new(Ledger::default()),
+            nodes: AtomicUsize::new(0),
+        })
+    }
+
+    fn grant(&self) -> u64 {
+        let mut ledger = self.ledger.lock().unwrap();
+        ledger.queued.pop_front().unwrap_or_else(|| {
+            ledger.next_unit += 1;
+            ledger.next_unit - 1
+        })
+    }
+
+    fn complete(&self) {
+        self.ledger.lock().unwrap().completed += 1;
+    }
+
+    fn requeue(&self, units: HashSet<u64>) -> usize {
+        let mut ledger = self.ledger.lock().unwrap();
+        ledger.reassigned += units.len() as u64;
+        let count = units.len();
+        ledger.queued.extend(units);
+        count
+    }
+
+    /// Unit counters for the end of the run
+    pub fn summary(&self) -> String {
+        let ledger = self.ledger.lock().unwrap();
+        format!(
+            "{} units of {} candidates completed, {} reassigned",
+            ledger.completed, self.unit_size, ledger.reassigned
+        )
+    }
+
+    /// Accept worker nodes on a background thread until the stop flag is set
+    #[allow(clippy::too_many_arguments)]
+    pub fn serve(
+        self: &Arc<Self>,
+        results: Arc<Mutex<SecretVec<FoundSeed>>>,
+        stats: Arc<RunStats>,
+        rules: Arc<CollectionRules>,
+        reporter: Arc<Reporter>,
+        limits: Arc<RunLimits>,
+        stop_flag: Arc<AtomicBool>,
+    ) -> JoinHandle<()> {
+        let server = Arc::new(Server {
+            coordinator: self.clone(),
+            results,
+            stats,
+            rules,
+            reporter,
+            limits,
+            stop_flag,
+        });
+        std::thread::spawn(move || {
+            let mut connections = Vec::new();
+            while !server.stop_flag.load(Ordering::Relaxed) {
+                match server.coordinator.listener.accept() {
+                    Ok((stream, addr)) => {
+                        let server = server.clone();
+                        connections.push(std::thread::spawn(move || server.handle(stream, addr)));
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(POLL_INTERVAL),
+                    Err(e) => {
+                        server.reporter.notice(&format!("[Cluster] Accept failed: {}", e));
+                        std::thread::sleep(POLL_INTERVAL);
+                    }
+                }
+                connections.retain(|connection| !connection.is_finished());
+            }
+            for connection in connections {
+                connection.join().ok();
+            }
+        })
+    }
+}
+
+// Coordinator side of the node connections
+struct Server {
+    coordinator: Arc<Coordinator>,
+    results: Arc<Mutex<SecretVec<FoundSeed>>>,
+    stats: Arc<RunStats>,
+    rules: Arc<CollectionRules>,
+    reporter: Arc<Reporter>,
+    limits: Arc<RunLimits>,
+    stop_flag: Arc<AtomicBool>,
+}
+
+impl Server {
+    fn handle(&self, stream: TcpStream, addr: SocketAddr) {
+        let setup = stream
+            .set_nonblocking(false)
+            .and_then(|_| stream.set_nodelay(true))
+            .and_then(|_| stream.set_read_timeout(Some(POLL_INTERVAL * 10)))
+            .and_then(|_| stream.set_write_timeout(Some(NODE_TIMEOUT)))
+            .and_then(|_| Lines::new(&stream));
+        let mut lines = match setup {
+            Ok(lines) => lines,
+            Err(e) => {
+                self.reporter.notice(&format!("[Cluster] Connection from {} failed: {}", addr, e));
+                return;
+            }
+        };
+        let name = match self.handshake(&stream, &mut lines) {
+            Ok((name, threads)) => format!("{} ({}, {} threads)", name, addr, threads),
+            Err(e) => {
+                send(&stream, &json!({ "type": "error", "message": e })).ok();
+                self.reporter.notice(&format!("[Cluster] Rejected {}: {}", addr, e));
+                return;
+            }
+        };
+
+        let nodes = self.coordinator.nodes.fetch_add(1, Ordering::Relaxed) + 1;
+        self.reporter.notice(&format!("[Cluster] Node joined: {}, {} connected", name, nodes));
+        self.reporter.event("node_joined", json!({ "node": name }));
+
+        let mut assigned = HashSet::new();
+        let reason = match self.serve_node(&stream, &mut lines, &name, &mut assigned) {
+            Ok(()) => "disconnected".to_string(),
+            Err(e) => e,
+        };
+        stream.shutdown(Shutdown::Both).ok();
+
+        // Unfinished units go to the next node that asks (no one will once the run stopped)
+        let reassigned = if self.stop_flag.load(Ordering::Relaxed) {
+            0
+        } else {
+            self.coordinator.requeue(assigned)
+        };
+        let nodes = self.coordinator.nodes.fetch_sub(1, Ordering::Relaxed) - 1;
+        self.reporter.notice(&format!(
+            "[Cluster] Node left: {}: {}, {} units reassigned, {} connected",
+            name, reason, reassigned, nodes
+        ));
+        self.reporter.event(
+            "node_left",
+            json!({ "node": name, "reason": reason, "reassigned": reassigned }),
+        );
+    }
+
+    // Challenge-response on the cluster secret; returns the node name and thread count
+    fn handshake(&self, stream: &TcpStream, lines: &mut Lines) -> Result<(String, u64), String> {
+        let mut challenge = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut challenge);
+        send(stream, &json!({
+            "type": "welcome",
+            "version": PROTOCOL_VERSION,
+            "run_id": self.coordinator.run_id.to_lower_hex_string(),
+            "challenge": challenge.to_lower_hex_string(),
+        }))
+        .map_err(|e| e.to_string())?;
+
+        let deadline = Instant::now() + NODE_TIMEOUT;
+        let hello = loop {
+            match lines.next().map_err(|e| e.to_string())? {
+                Some(message) => break message,
+                None if Instant::now() >= deadline => return Err("no hello".to_string()),
+                None => {}
+            }
+        };
+        if hello["type"] != "hello" || field(&hello, "version")? != PROTOCOL_VERSION {
+            return Err("unsupported protocol version".to_string());
+        }
+        let proof = hello["proof"].as_str().and_then(|hex| Vec::<u8>::from_hex(hex).ok()).unwrap_or_default();
+        if !self.coordinator.secret.verify(&challenge, &proof) {
+            return Err("wrong cluster secret".to_string());
+        }
+
+        let (threshold, count) = self.rules.get();
+        send(stream, &json!({
+            "type": "ready",
+            "unit_size": self.coordinator.unit_size,
+            "threshold": threshold,
+            "count": count,
+        }))
+        .map_err(|e| e.to_string())?;
+        let name = hello["name"].as_str().unwrap_or("node").chars().take(64).collect();
+        Ok((name, hello["threads"].as_u64().unwrap_or(0)))
+    }
+
+    fn serve_node(
+        &self,
+        stream: &TcpStream,
+        lines: &mut Lines,
+        name: &str,
+        assigned: &mut HashSet<u64>,
+    ) -> Result<(), String> {
+        let mut last_message = Instant::now();
+        let mut stopping_since = None;
+        loop {
+            if self.stop_flag.load(Ordering::Relaxed) {
+                // Give the node a moment to pick up the stop and send its last report
+                let since = *stopping_since.get_or_insert_with(Instant::now);
+                if since.elapsed() >= STOP_GRACE {
+                    return Ok(());
+                }
+            }
+            let message = match lines.next() {
+                Ok(Some(message)) => message,
+                Ok(None) if last_message.elapsed() >= NODE_TIMEOUT => return Err("timed out".to_string()),
+                Ok(None) => continue,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e.to_string()),
+            };
+            last_message = Instant::now();
+
+            let (threshold, count) = self.rules.get();
+            let stop = self.stop_flag.load(Ordering::Relaxed);
+            match message["type"].as_str().unwrap_or("") {
+                "request" => {
+                    let reply = if stop {
+                        json!({ "type": "stop" })
+                    } else {
+                        let unit = self.coordinator.grant();
+                        assigned.insert(unit);
+                        json!({ "type": "unit", "unit": unit, "threshold": threshold, "count": count })
+                    };
+                    send(stream, &reply).map_err(|e| e.to_string())?;
+                }
+                "progress" => {
+                    // Node candidates count towards --max-iterations like local ones; reaching
+                    // it stops the whole cluster
+                    let max_iterations = self.limits.max_iterations();
+                    self.stats.claim_iterations(field(&message, "iterations")?, max_iterations);
+                    let reached =
+                        max_iterations.is_some_and(|max| self.stats.iterations.load(Ordering::Relaxed) >= max);
+                    if reached {
+                        self.limits.stop("max_iterations", &self.stop_flag);
+                    }
+                    if let Some(scores) = message["scores"].as_object() {
+                        let mut local = [0u64; MAX_SCORE + 1];
+                        for (chars, seen) in scores {
+                            if let (Ok(chars), Some(seen)) = (chars.parse::<usize>(), seen.as_u64()) {
+                                if chars <= MAX_SCORE {
+                                    local[chars] += seen;
+                                }
+                            }
+                        }
+                        self.stats.merge_scores(&mut local);
+                    }
+                    let reply = if stop || reached {
+                        json!({ "type": "stop" })
+                    } else {
+                        json!({ "type": "ack", "threshold": threshold, "count": count })
+                    };
+                    send(stream, &reply).map_err(|e| e.to_string())?;
+                }
+                "done" => {
+                    if assigned.remove(&field(&message, "unit")?) {
+                        self.coordinator.complete();
+                    }
+                }
+                kind @ ("found" | "best") => {
+                    let (unit, offset) = (field(&message, "unit")?, field(&message, "offset")?);
+                    if offset >= self.coordinator.unit_size {
+                        return Err(format!("offset {} outside unit {}", offset, unit));
+                    }
+                    // Only the node searching a unit may report from it (finished, reassigned or
+                    // never granted units are not its to report)
+                    if !assigned.contains(&unit) {
+                        self.reporter.notice(&format!(
+                            "[Cluster] Ignored \"{}\" report from {} for unit {}, which is not assigned to it",
+                            kind, name, unit
+                        ));
+                        continue;
+                    }
+                    let batch = self.coordinator.key.entropy(unit, offset, 1);
+                    let entropy = &batch[0];
+                    let chars = FoundSeed::from_entropy(*entropy).chars;
+                    if kind == "best" {
+                        if self.stats.best_chars().is_none_or(|best| chars < best) {
+                            self.stats.offer_best(FoundSeed::from_entropy(*entropy));
+                        }
+                    } else if chars < threshold {
+                        worker::collect(
+                            entropy,
+                            chars,
+                            count,
+                            || FoundSeed {
+                                unit: Some((unit, offset)),
+                                ..FoundSeed::from_entropy(*entropy)
+                            },
+                            &self.results,
+                            &self.stats,
+                            &self.reporter,
+                            &self.limits,
+                            &self.stop_flag,
+                        );
+                    }
+                }
+                other => return Err(format!("unexpected message \"{}\"", other)),
+            }
+        }
+    }
+}
+
+/// Connection of a worker node to the coordinator
+pub struct ClusterClient {
+    stream: TcpStream,
+    lines: Mutex<Lines>,
+    key: UnitKey,
+    unit_size: u64,
+    // Iterations and score histogram as of the last progress report
+    reported: Mutex<(u64, Vec<u64>)>,
+}
+
+/// What the coordinator answered
+enum Reply {
+    Unit(u64, (usize, usize)),
+    Rules(usize, usize),
+    Stop,
+}
+
+impl ClusterClient {
+    /// Connect and authenticate; returns the client and the coordinator's collection rules
+    pub fn connect(
+        addr: &str,
+        secret: &ClusterSecret,
+        name: &str,
+        threads: usize,
+    ) -> Result<(Self, (usize, usize)), String> {
+        let failed = |e: io::Error| format!("Could not connect to coordinator {}: {}", addr, e);
+        let stream = TcpStream::connect(addr).map_err(failed)?;
+        stream.set_nodelay(true).map_err(failed)?;
+        stream.set_read_timeout(Some(NODE_TIMEOUT)).map_err(failed)?;
+        stream.set_write_timeout(Some(NODE_TIMEOUT)).map_err(failed)?;
+        let mut lines = Lines::new(&stream).map_err(failed)?;
+
+        let welcome = Self::read(&mut lines)?;
+        if welcome["type"] != "welcome" || field(&welcome, "version")? != PROTOCOL_VERSION {
+            return Err(format!("{} is not a compatible coordinator", addr));
+        }
+        let run_id = hex16(&welcome, "run_id")?;
+        let challenge = hex16(&welcome, "challenge")?;
+        send(&stream, &json!({
+            "type": "hello",
+            "version": PROTOCOL_VERSION,
+            "name": name,
+            "threads": threads,
+            "proof": secret.proof(&challenge).to_lower_hex_string(),
+        }))
+        .map_err(failed)?;
+
+        let ready = Self::read(&mut lines)?;
+        if ready["type"] == "error" {
+            return Err(format!("Coordinator refused to connect: {}", ready["message"].as_str().unwrap_or("")));
+        }
+        let rules = (field(&ready, "threshold")? as usize, field(&ready, "count")? as usize);
+        let client = Self {
+            stream,
+            lines: Mutex::new(lines),
+            key: secret.unit_key(&run_id),
+            unit_size: field(&ready, "unit_size")?.max(1),
+            reported: Mutex::new((0, vec![0; MAX_SCORE + 1])),
+        };
+        Ok((client, rules))
+    }
+
+    fn read(lines: &mut Lines) -> Result<Value, String> {
+        match lines.next() {
+            Ok(Some(message)) => Ok(message),
+            Ok(None) => Err("Coordinator did not answer".to_string()),
+            Err(e) => Err(format!("Connection to the coordinator failed: {}", e)),
+        }
+    }
+
+    // Send a message that gets a reply
+    fn call(&self, message: Value) -> Result<Reply, String> {
+        // Holding the reader keeps request and reply together
+        let mut lines = self.lines.lock().unwrap();
+        send(&self.stream, &message).map_err(|e| format!("Connection to the coordinator failed: {}", e))?;
+        let reply = Self::read(&mut lines)?;
+        let rules = || -> Result<(usize, usize), String> {
+            Ok((field(&reply, "threshold")? as usize, field(&reply, "count")? as usize))
+        };
+        match reply["type"].as_str().unwrap_or("") {
+            "unit" => Ok(Reply::Unit(field(&reply, "unit")?, rules()?)),
+            "ack" => rules().map(|(threshold, count)| Reply::Rules(threshold, count)),
+            "stop" => Ok(Reply::Stop),
+            _ => Err("Unexpected reply from the coordinator".to_string()),
+        }
+    }
+
+    // Send a report without a reply; a broken connection shows up at the next call
+    fn notify(&self, message: Value) {
+        let _lines = self.lines.lock().unwrap();
+        send(&self.stream, &message).ok();
+    }
+
+    // Report the candidates searched since the last report
+    fn progress(&self, stats: &RunStats) -> Result<Reply, String> {
+        let message = {
+            let mut reported = self.reported.lock().unwrap();
+            let iterations = stats.iterations.load(Ordering::Relaxed);
+            let scores = stats.score_snapshot();
+            let delta: serde_json::Map<String, Value> = scores
+                .iter()
+                .zip(reported.1.iter())
+                .enumerate()
+                .filter(|(_, (now, before))| now > before)
+                .map(|(chars, (now, before))| (chars.to_string(), json!(now - before)))
+                .collect();
+            let message = json!({
+                "type": "progress",
+                "iterations": iterations.saturating_sub(reported.0),
+                "scores": delta,
+            });
+            *reported = (iterations, scores);
+            message
+        };
+        self.call(message)
+    }
+
+    /// Report progress every second until the stop flag is set; the coordinator answers with
+    /// its current collection rules or a stop
+    pub fn spawn_progress(
+        self: &Arc<Self>,
+        stats: Arc<RunStats>,
+        rules: Arc<CollectionRules>,
+        reporter: Arc<Reporter>,
+        stop_flag: Arc<AtomicBool>,
+    ) -> JoinHandle<()> {
+        let client = self.clone();
+        std::thread::spawn(move || {
+            let mut last_report = Instant::now();
+            while !stop_flag.load(Ordering::Relaxed) {
+                std::thread::sleep(POLL_INTERVAL);
+                if last_report.elapsed() < PROGRESS_INTERVAL {
+                    continue;
+                }
+                last_report = Instant::now();
+                match client.progress(&stats) {
+                    Ok(Reply::Rules(threshold, count)) => apply_rules(&rules, &reporter, threshold, count),
+                    Ok(_) => stop(&reporter, &stop_flag, "[Cluster] Coordinator stopped the run."),
+                    Err(e) => stop(&reporter, &stop_flag, &format!("[Cluster] {}.", e)),
+                }
+            }
+        })
+    }
+
+    /// Send the final progress report and disconnect (after the workers finished)
+    pub fn close(&self, stats: &RunStats) {
+        self.progress(stats).ok();
+        self.stream.shutdown(Shutdown::Both).ok();
+    }
+}
+
+fn apply_rules(rules: &CollectionRules, reporter: &Reporter, threshold: usize, count: usize) {
+    if rules.get() != (threshold, count) && rules.set(threshold, count).is_ok() {
+        reporter.notice(&format!(
+            "[Cluster] Collection rules from the coordinator: threshold {}, count {}",
+            threshold, count
+        ));
+    }
+}
+
+fn stop(reporter: &Reporter, stop_flag: &AtomicBool, message: &str) {
+    if !stop_flag.swap(true, Ordering::Relaxed) {
+        reporter.notice(&format!("\n\n{} Shutting down gracefully...", message));
+    }
+}
+
+enum Origin {
+    // Workers of the coordinator itself
+    Local(Arc<Coordinator>),
+    Remote(Arc<ClusterClient>),
+    // This run's range of a partitioned search
+    Partition(Arc<PartitionRun>),
+}
+
+/// Part of a work unit handed to one worker batch
+#[derive(Clone, Copy)]
+pub struct UnitSlice {
+    unit: u64,
+    start: u64,
+}
+
+impl UnitSlice {
+    /// Work unit and offset in the unit of the candidate at `offset` in the batch
+    pub fn position(&self, offset: u64) -> (u64, u64) {
+        (self.unit, self.start + offset)
+    }
+}
+
+#[derive(Default)]
+struct Cursor {
+    // Unit being handed out and its next offset
+    current: Option<(u64, u64)>,
+    // Candidates of each unit not processed yet
+    remaining: HashMap<u64, u64>,
+}
+
+/// Entropy source of the worker pool in a distributed or partitioned search
+pub struct UnitSource {
+    origin: Origin,
+    rules: Arc<CollectionRules>,
+    reporter: Arc<Reporter>,
+    stop_flag: Arc<AtomicBool>,
+    cursor: Mutex<Cursor>,
+}
+
+impl UnitSource {
+    pub fn local(
+        coordinator: Arc<Coordinator>,
+        rules: Arc<CollectionRules>,
+        reporter: Arc<Reporter>,
+        stop_flag: Arc<AtomicBool>,
+    ) -> Self {
+        Self::new(Origin::Local(coordinator), rules, reporter, stop_flag)
+    }
+
+    pub fn remote(
+        client: Arc<ClusterClient>,
+        rules: Arc<CollectionRules>,
+        reporter: Arc<Reporter>,
+        stop_flag: Arc<AtomicBool>,
+    ) -> Self {
+        Self::new(Origin::Remote(client), rules, reporter, stop_flag)
+    }
+
+    pub fn partition(
+        run: Arc<PartitionRun>,
+        rules: Arc<CollectionRules>,
+        reporter: Arc<Reporter>,
+        stop_flag: Arc<AtomicBool>,
+    ) -> Self {
+        Self::new(Origin::Partition(run), rules, reporter, stop_flag)
+    }
+
+    fn new(origin: Origin, rules: Arc<CollectionRules>, reporter: Arc<Reporter>, stop_flag: Arc<AtomicBool>) -> Self {
+        Self {
+            origin,
+            rules,
+            reporter,
+            stop_flag,
+            cursor: Mutex::new(Cursor::default()),
+        }
+    }
+
+    fn key(&self) -> &UnitKey {
+        match self.origin {
+            Origin::Local(ref coordinator) => &coordinator.key,
+            Origin::Remote(ref client) => &client.key,
+            Origin::Partition(ref run) => &run.key,
+        }
+    }
+
+    fn unit_size(&self) -> u64 {
+        match self.origin {
+            Origin::Local(ref coordinator) => coordinator.unit_size,
+            Origin::Remote(ref client) => client.unit_size,
+            Origin::Partition(ref run) => run.unit_size,
+        }
+    }
+
+    /// Claim up to `count` candidates of the current unit, fetching a new unit when it is
+    /// used up; None once the coordinator stopped or cannot be reached (or the partition is done)
+    pub fn next_batch(&self, count: usize) -> Option<(UnitSlice, EntropyBatch)> {
+        let unit_size = self.unit_size();
+        let (unit, start, count) = {
+            let mut cursor = self.cursor.lock().unwrap();
+            let (unit, start) = match cursor.current {
+                Some(current) => current,
+                None => {
+                    let unit = self.claim()?;
+                    cursor.remaining.insert(unit, unit_size);
+                    (unit, 0)
+                }
+            };
+            let count = (count as u64).min(unit_size - start);
+            cursor.current = (start + count < unit_size).then_some((unit, start + count));
+            (unit, start, count)
+        };
+        Some((UnitSlice { unit, start }, self.key().entropy(unit, start, count)))
+    }
+
+    fn claim(&self) -> Option<u64> {
+        match self.origin {
+            Origin::Local(ref coordinator) => Some(coordinator.grant()),
+            Origin::Remote(ref client) => match client.call(json!({ "type": "request" })) {
+                Ok(Reply::Unit(unit, (threshold, count))) => {
+                    apply_rules(&self.rules, &self.reporter, threshold, count);
+                    Some(unit)
+                }
+                Ok(_) => {
+                    stop(&self.reporter, &self.stop_flag, "[Cluster] Coordinator stopped the run.");
+                    None
+                }
+                Err(e) => {
+                    stop(&self.reporter, &self.stop_flag, &format!("[Cluster] {}.", e));
+                    None
+                }
+            },
+            Origin::Partition(ref run) => run.claim().or_else(|| {
+                let message = format!("[Partition] Every unit of partition {} was searched.", run.partition);
+                stop(&self.reporter, &self.stop_flag, &message);
+                None
+            }),
+        }
+    }
+
+    /// A batch was searched; the unit is done once all its candidates were
+    pub fn complete(&self, slice: UnitSlice, processed: u64) {
+        {
+            let mut cursor = self.cursor.lock().unwrap();
+            let Some(remaining) = cursor.remaining.get_mut(&slice.unit) else {
+                return;
+            };
+            *remaining = remaining.saturating_sub(processed);
+            if *remaining > 0 {
+                return;
+            }
+            cursor.remaining.remove(&slice.unit);
+        }
+        match self.origin {
+            Origin::Local(ref coordinator) => coordinator.complete(),
+            Origin::Remote(ref client) => client.notify(json!({ "type": "done", "unit": slice.unit })),
+            Origin::Partition(ref run) => run.complete(slice.unit),
+        }
+    }
+
+    /// A candidate was collected (the coordinator's own workers collect into the shared results)
+    pub fn found(&self, slice: UnitSlice, offset: u64) {
+        self.report("found", slice, offset);
+    }
+
+    /// A candidate is the shortest this node has seen
+    pub fn best(&self, slice: UnitSlice, offset: u64) {
+        self.report("best", slice, offset);
+    }
+
+    fn report(&self, kind: &str, slice: UnitSlice, offset: u64) {
+        if let Origin::Remote(ref client) = self.origin {
+            client.notify(json!({ "type": kind, "unit": slice.unit, "offset": slice.start + offset }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reporter::ConsoleMode;
+
+    fn secret(text: &str) -> ClusterSecret {
+        ClusterSecret {
+            key: Zeroizing::new(Sha256::digest(text.as_bytes()).into()),
+        }
+    }
+
+    fn unit(client: &ClusterClient) -> u64 {
+        match client.call(json!({ "type": "request" })) {
+            Ok(Reply::Unit(unit, _)) => unit,
+            _ => panic!("no unit granted"),
+        }
+    }
+
+    // Block until the coordinator handled the reports sent before
+    fn sync(client: &ClusterClient) {
+        assert!(matches!(client.progress(&RunStats::new(1, &[])), Ok(Reply::Rules(..))));
+    }
+
+    #[test]
+    fn serves_units_and_finds_to_nodes() {
+        let coordinator = Arc::new(Coordinator::bind("127.0.0.1:0", secret("cluster test secret"), 1000).unwrap());
+        let addr = coordinator.listener.local_addr().unwrap().to_string();
+        let results = Arc::new(Mutex::new(SecretVec::new(false)));
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let handle = coordinator.serve(
+            results.clone(),
+            Arc::new(RunStats::new(1, &[])),
+            // Every candidate qualifies
+            Arc::new(CollectionRules::new(200, 10).unwrap()),
+            Arc::new(Reporter::new(ConsoleMode::Redacted, Zeroizing::default())),
+            Arc::new(RunLimits::new(None, None, None, Vec::new()).unwrap()),
+            stop_flag.clone(),
+        );
+
+        let refused = ClusterClient::connect(&addr, &secret("some other secret"), "intruder", 1);
+        assert!(refused.err().unwrap().contains("wrong cluster secret"));
+
+        let (a, rules) = ClusterClient::connect(&addr, &secret("cluster test secret"), "a", 1).unwrap();
+        assert_eq!(rules, (200, 10));
+        let (b, _) = ClusterClient::connect(&addr, &secret("cluster test secret"), "b", 1).unwrap();
+        assert_eq!(unit(&a), 0);
+        assert_eq!(unit(&b), 1);
+
+        // A repeated find is collected once; a find from a unit of another node is ignored
+        a.notify(json!({ "type": "found", "unit": 0, "offset": 5 }));
+        a.notify(json!({ "type": "found", "unit": 0, "offset": 5 }));
+        a.notify(json!({ "type": "found", "unit": 1, "offset": 7 }));
+        sync(&a);
+        {
+            let results = results.lock().unwrap();
+            assert_eq!(results.len(), 1);
+            let found = results.iter().next().unwrap();
+            assert_eq!(found.unit, Some((0, 5)));
+            assert_eq!(found.entropy, a.key.entropy(0, 5, 1)[0]);
+        }
+
+        // The unit of a node that disconnects goes to the next node that asks
+        b.stream.shutdown(Shutdown::Both).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while coordinator.ledger.lock().unwrap().reassigned == 0 {
+            assert!(Instant::now() < deadline, "unit of the disconnected node not requeued");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(unit(&a), 1);
+        assert_eq!(unit(&a), 2);
+
+        // Stopped first, so the units of A are not requeued when it disconnects
+        stop_flag.store(true, Ordering::Relaxed);
+        a.stream.shutdown(Shutdown::Both).unwrap();
+        handle.join().unwrap();
+        assert_eq!(coordinator.summary(), "0 units of 1000 candidates completed, 1 reassigned");
+    }
+
+    #[test]
+    fn node_progress_counts_towards_max_iterations() {
+        let coordinator = Arc::new(Coordinator::bind("127.0.0.1:0", secret("cluster test secret"), 1000).unwrap());
+        let addr = coordinator.listener.local_addr().unwrap().to_string();
+        let stats = Arc::new(RunStats::new(1, &[]));
+        let limits = Arc::new(RunLimits::new(None, None, Some(1500), Vec::new()).unwrap());
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let handle = coordinator.serve(
+            Arc::new(Mutex::new(SecretVec::new(false))),
+            stats.clone(),
+            Arc::new(CollectionRules::new(200, 10).unwrap()),
+            Arc::new(Reporter::new(ConsoleMode::Redacted, Zeroizing::default())),
+            limits.clone(),
+            stop_flag.clone(),
+        );
+
+        let (a, _) = ClusterClient::connect(&addr, &secret("cluster test secret"), "a", 1).unwrap();
+        let node = RunStats::new(1, &[]);
+        node.iterations.store(1000, Ordering::Relaxed);
+        assert!(matches!(a.progress(&node), Ok(Reply::Rules(..))));
+        assert!(!stop_flag.load(Ordering::Relaxed));
+
+        // Counted up to the limit only, and the coordinator stops
+        node.iterations.store(2000, Ordering::Relaxed);
+        assert!(matches!(a.progress(&node), Ok(Reply::Stop)));
+        assert_eq!(stats.iterations.load(Ordering::Relaxed), 1500);
+        assert!(stop_flag.load(Ordering::Relaxed));
+        assert_eq!(limits.reason(), Some("max_iterations"));
+
+        a.stream.shutdown(Shutdown::Both).unwrap();
+        handle.join().unwrap();
+    }
+}
@@ -4,9 +4,17 @@ use std::io;
 
 const CONFIG_FILE: &str = "../gpuseed_config.json";
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Config {
     pub gpu_type: Option<String>,
+    // Collection rules and resource limit. Used when not given on the command line,
+    // and re-read on SIGHUP.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_usage_percent: Option<f64>,
 }
 
 impl Config {
@@ -24,9 +32,18 @@ impl Config {
         }
     }
     
+    /// Read the config file again, reporting why it could not be used
+    pub fn reload() -> Result<Self, String> {
+        let content = fs::read_to_string(CONFIG_FILE)
+            .map_err(|e| format!("Could not read config file {}: {}", CONFIG_FILE, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Invalid config file {}: {}", CONFIG_FILE, e))
+    }
+    
     pub fn save_gpu_type(gpu_type: &str) {
+        // Keep the other settings
         let config = Config {
             gpu_type: Some(gpu_type.to_string()),
+            ..Self::load_or_create().unwrap_or_default()
         };
         if let Ok(json) = serde_json::to_string_pretty(&config) {
             if let Err(e) = fs::write(CONFIG_FILE, json) {
@@ -35,4 +52,3 @@ impl Config {
         }
     }
 }
-
@@ -0,0 +1,406 @@
+// Control socket (`--control-socket`) and the `ctl` client
+// A Unix socket for managing a running search without its terminal. Each connection sends one
+// command line and gets "ok" or "error" plus output back. Commands act on the same shared state
+// as the status line, dashboard and signals (RunStats, throttle data, collection rules, limits).
+use clap::Args;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::limits::{self, RunLimits};
+use crate::monitor;
+use crate::reporter::Reporter;
+use crate::stats::{PauseReason, RunStats};
+use crate::worker::CollectionRules;
+
+const DEFAULT_SOCKET: &str = "gpuseed.sock";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+// flush-results writes every collected seed before replying
+const REPLY_TIMEOUT: Duration = Duration::from_secs(60);
+// A command line is short; anything longer is not a client of ours
+const MAX_REQUEST: u64 = 4096;
+
+const HELP: &str = "\
+status                    status snapshot and run limits
+pause | resume            pause or resume the workers
+set-rules THRESHOLD COUNT change the collection rules
+set-limits KEY=VALUE...   max-usage=PERCENT, duration=8h, deadline=07:00,
+                          max-iterations=N, until-found=<=41:1 (\"none\" clears a limit)
+flush-results             write the seeds collected so far to the log and output files
+stop                      stop gracefully, like Ctrl+C";
+
+/// Writes the collected seeds to the results files, returning a summary
+pub type FlushResults = Box<dyn Fn() -> Result<String, String> + Send>;
+
+#[derive(Args, Debug)]
+pub struct CtlArgs {
+    /// Control socket of the running search
+    #[arg(long, default_value = DEFAULT_SOCKET)]
+    socket: PathBuf,
+
+    /// Command and arguments (status, pause, resume, set-rules, set-limits, flush-results, stop, help)
+    #[arg(required = true, trailing_var_arg = true)]
+    command: Vec<String>,
+}
+
+/// `ctl` subcommand: send one command and print the reply
+pub fn ctl(args: &CtlArgs) -> Result<(), String> {
+    let mut stream = UnixStream::connect(&args.socket)
+        .map_err(|e| format!("Could not connect to {}: {}", args.socket.display(), e))?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT)).ok();
+    // Tabs keep arguments with spaces ("deadline=2026-10-19 07:00") in one piece
+    writeln!(stream, "{}", args.command.join("\t")).map_err(|e| format!("Could not send command: {}", e))?;
+    stream.shutdown(std::net::Shutdown::Write).ok();
+
+    let mut reply = String::new();
+    stream
+        .read_to_string(&mut reply)
+        .map_err(|e| format!("Could not read reply: {}", e))?;
+    let (status, output) = reply.split_once('\n').unwrap_or((reply.as_str(), ""));
+    match status {
+        "ok" => {
+            print!("{}", output);
+            Ok(())
+        }
+        "error" => Err(output.trim_end().to_string()),
+        _ => Err("Unexpected reply from the control socket".to_string()),
+    }
+}
+
+pub struct ControlServer {
+    listener: UnixListener,
+    path: PathBuf,
+    stats: Arc<RunStats>,
+    throttle_data: Arc<Mutex<HashMap<String, f64>>>,
+    rules: Arc<CollectionRules>,
+    limits: Arc<RunLimits>,
+    reporter: Arc<Reporter>,
+    stop_flag: Arc<AtomicBool>,
+    flush: FlushResults,
+}
+
+impl ControlServer {
+    #[allow(clippy::too_many_arguments)]
+    pub fn bind(
+        path: &str,
+        stats: Arc<RunStats>,
+        throttle_data: Arc<Mutex<HashMap<String, f64>>>,
+        rules: Arc<CollectionRules>,
+        limits: Arc<RunLimits>,
+        reporter: Arc<Reporter>,
+        stop_flag: Arc<AtomicBool>,
+        flush: FlushResults,
+    ) -> Result<Self, String> {
+        let path = PathBuf::from(path);
+        remove_stale(&path)?;
+
+        // Only the owner may connect: the socket is created without group/other permissions
+        // (umask), so there is no window in which it is open to everyone
+        // SAFETY: umask only swaps the process file mode mask
+        let umask = unsafe { libc::umask(0o177) };
+        let listener = UnixListener::bind(&path);
+        // SAFETY: as above, restoring the previous mask
+        unsafe { libc::umask(umask) };
+        let listener = listener.map_err(|e| format!("Could not create control socket {}: {}", path.display(), e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Could not configure control socket: {}", e))?;
+
+        Ok(Self {
+            listener,
+            path,
+            stats,
+            throttle_data,
+            rules,
+            limits,
+            reporter,
+            stop_flag,
+            flush,
+        })
+    }
+
+    pub fn spawn(self) -> JoinHandle<()> {
+        std::thread::spawn(move || {
+            while !self.stop_flag.load(Ordering::Relaxed) {
+                match self.listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Err(e) = self.serve(stream) {
+                            self.reporter.notice(&format!("[Control] Connection failed: {}", e));
+                        }
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(POLL_INTERVAL),
+                    Err(e) => {
+                        self.reporter.notice(&format!("[Control] Accept failed: {}", e));
+                        std::thread::sleep(POLL_INTERVAL);
+                    }
+                }
+            }
+            fs::remove_file(&self.path).ok();
+        })
+    }
+
+    fn serve(&self, stream: UnixStream) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+        let mut line = String::new();
+        // Nothing sent: a probe (e.g. another run checking whether the socket is live)
+        if BufReader::new((&stream).take(MAX_REQUEST)).read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        let words: Vec<&str> = if line.contains('\t') {
+            line.split('\t').collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+
+        let reply = match self.execute(&words) {
+            Ok(output) => format!("ok\n{}", output),
+            Err(e) => format!("error\n{}\n", e),
+        };
+        (&stream).write_all(reply.as_bytes())
+    }
+
+    fn execute(&self, words: &[&str]) -> Result<String, String> {
+        let (command, args) = words.split_first().ok_or("Empty command")?;
+        let no_args = || {
+            if args.is_empty() {
+                Ok(())
+            } else {
+                Err(format!("{} takes no arguments", command))
+            }
+        };
+        match *command {
+            "status" => {
+                no_args()?;
+                let limits = self.limits.describe();
+                Ok(format!(
+                    "{}Limits: {}\n",
+                    self.stats.snapshot(&self.throttle_data, &self.rules),
+                    if limits.is_empty() { "none".to_string() } else { limits.join(", ") }
+                ))
+            }
+            "pause" | "resume" => {
+                no_args()?;
+                let paused = *command == "pause";
+                if self.stats.set_pause(PauseReason::Manual, paused) != paused {
+                    self.reporter.notice(if paused { "[Control] Paused" } else { "[Control] Resumed" });
+                    self.reporter.event(
+                        if paused { "paused" } else { "resumed" },
+                        serde_json::json!({ "reason": PauseReason::Manual.name() }),
+                    );
+                }
+                // Other pause reasons (run windows, idle-only) still apply
+                let reasons: Vec<&str> = self.stats.pause_reasons().map(PauseReason::name).collect();
+                Ok(if reasons.is_empty() {
+                    "running\n".to_string()
+                } else {
+                    format!("paused ({})\n", reasons.join(", "))
+                })
+            }
+            "set-rules" => {
+                let [threshold, count] = args else {
+                    return Err("Usage: set-rules THRESHOLD COUNT".to_string());
+                };
+                let threshold = threshold.parse().map_err(|_| format!("Invalid threshold '{}'", threshold))?;
+                let count = count.parse().map_err(|_| format!("Invalid count '{}'", count))?;
+                self.rules.set(threshold, count)?;
+                let message = format!("threshold {}, count {}", threshold, count);
+                self.reporter.notice(&format!("[Control] Collection rules: {}", message));
+                Ok(format!("{}\n", message))
+            }
+            "set-limits" => {
+                if args.is_empty() {
+                    return Err("Usage: set-limits KEY=VALUE...".to_string());
+                }
+                // Parse everything first, so an invalid value changes nothing
+                let changes = args.iter().map(|arg| LimitChange::parse(arg)).collect::<Result<Vec<_>, _>>()?;
+                for change in &changes {
+                    self.apply(change)?;
+                }
+                let limits = self.limits.describe();
+                let message = format!(
+                    "resource limit {:.0}%, {}",
+                    monitor::max_usage(&self.throttle_data) * 100.0,
+                    if limits.is_empty() { "no run limits".to_string() } else { limits.join(", ") }
+                );
+                self.reporter.notice(&format!("[Control] Limits: {}", message));
+                Ok(format!("{}\n", message))
+            }
+            "flush-results" => {
+                no_args()?;
+                (self.flush)().map(|summary| format!("{}\n", summary))
+            }
+            "stop" => {
+                no_args()?;
+                self.reporter.notice("\n\n[Control] Stop requested. Shutting down gracefully...");
+                self.stop_flag.store(true, Ordering::Relaxed);
+                Ok("stopping\n".to_string())
+            }
+            "help" => Ok(format!("{}\n", HELP)),
+            _ => Err(format!("Unknown command '{}' (try \"help\")", command)),
+        }
+    }
+
+    fn apply(&self, change: &LimitChange) -> Result<(), String> {
+        match change {
+            LimitChange::MaxUsage(percent) => monitor::set_max_usage(&self.throttle_data, *percent),
+            LimitChange::Duration(duration) => {
+                self.limits.set_duration(*duration);
+                Ok(())
+            }
+            LimitChange::Deadline(deadline) => self.limits.set_deadline(*deadline),
+            LimitChange::MaxIterations(max) => {
+                self.limits.set_max_iterations(*max);
+                Ok(())
+            }
+            LimitChange::UntilFound(goals) => {
+                self.limits.set_goals(goals.clone());
+                Ok(())
+            }
+        }
+    }
+}
+
+enum LimitChange {
+    MaxUsage(f64),
+    Duration(Option<Duration>),
+    Deadline(Option<chrono::DateTime<chrono::Local>>),
+    MaxIterations(Option<u64>),
+    UntilFound(Vec<limits::Goal>),
+}
+
+impl LimitChange {
+    fn parse(arg: &str) -> Result<Self, String> {
+        let (key, value) = arg
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid limit '{}' (expected KEY=VALUE)", arg))?;
+        let value = value.trim();
+        let none = value == "none";
+        match key.trim() {
+            "max-usage" => {
+                let percent = value
+                    .trim_end_matches('%')
+                    .parse()
+                    .map_err(|_| format!("Invalid resource limit '{}'", value))?;
+                if !(1.0..=100.0).contains(&percent) {
+                    return Err("Resource limit must be between 1 and 100 percent".to_string());
+                }
+                Ok(Self::MaxUsage(percent))
+            }
+            "duration" if none => Ok(Self::Duration(None)),
+            "duration" => limits::parse_duration(value).map(|duration| Self::Duration(Some(duration))),
+            "deadline" if none => Ok(Self::Deadline(None)),
+            "deadline" => {
+                let deadline = limits::parse_deadline(value)?;
+                if deadline <= chrono::Local::now() {
+                    return Err(format!("Deadline {} is in the past", deadline.format("%Y-%m-%d %H:%M:%S")));
+                }
+                Ok(Self::Deadline(Some(deadline)))
+            }
+            "max-iterations" if none => Ok(Self::MaxIterations(None)),
+            "max-iterations" => value
+                .parse()
+                .map(|max| Self::MaxIterations(Some(max)))
+                .map_err(|_| format!("Invalid iteration count '{}'", value)),
+            "until-found" if none => Ok(Self::UntilFound(Vec::new())),
+            "until-found" => value.split(',').map(str::parse).collect::<Result<_, _>>().map(Self::UntilFound),
+            other => Err(format!(
+                "Unknown limit '{}' (max-usage, duration, deadline, max-iterations, until-found)",
+                other
+            )),
+        }
+    }
+}
+
+// A socket left behind by a crashed run is replaced; a live one or any other file is not
+fn remove_stale(path: &Path) -> Result<(), String> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Could not check {}: {}", path.display(), e)),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(format!("{} exists and is not a socket", path.display()));
+    }
+    if UnixStream::connect(path).is_ok() {
+        return Err(format!("{} is in use by another running search", path.display()));
+    }
+    fs::remove_file(path).map_err(|e| format!("Could not remove stale socket {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reporter::ConsoleMode;
+    use zeroize::Zeroizing;
+
+    // One command line over the socket, as `ctl` sends it; returns the raw reply
+    fn request(path: &Path, line: &str) -> String {
+        let mut stream = UnixStream::connect(path).unwrap();
+        writeln!(stream, "{}", line).unwrap();
+        stream.shutdown(std::net::Shutdown::Write).unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).unwrap();
+        reply
+    }
+
+    #[test]
+    fn round_trips_commands_over_the_socket() {
+        let dir = std::env::temp_dir().join(format!("gpuseed-control-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("control.sock");
+
+        let throttle_data = Arc::new(Mutex::new(HashMap::new()));
+        let rules = Arc::new(CollectionRules::new(46, 5).unwrap());
+        let limits = Arc::new(RunLimits::new(None, None, Some(1000), Vec::new()).unwrap());
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let server = ControlServer::bind(
+            path.to_str().unwrap(),
+            Arc::new(RunStats::new(1, &[])),
+            throttle_data.clone(),
+            rules.clone(),
+            limits.clone(),
+            Arc::new(Reporter::new(ConsoleMode::Redacted, Zeroizing::default())),
+            stop_flag.clone(),
+            Box::new(|| Ok("0 seeds".to_string())),
+        )
+        .unwrap();
+        // Owner-only socket
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o077, 0);
+        let handle = server.spawn();
+
+        let reply = request(&path, "status");
+        assert!(reply.starts_with("ok\n=== Status snapshot ==="), "{}", reply);
+        assert!(reply.ends_with("Limits: max 1000 iterations\n"), "{}", reply);
+
+        assert_eq!(request(&path, "set-rules 44 3"), "ok\nthreshold 44, count 3\n");
+        assert_eq!(rules.get(), (44, 3));
+        assert!(request(&path, "status").contains("Rules: fewer than 44 characters, at most 3 per count"));
+
+        // The valid first change is not applied either
+        let reply = request(&path, "set-limits max-usage=50 max-iterations=many");
+        assert_eq!(reply, "error\nInvalid iteration count 'many'\n");
+        assert_eq!(monitor::max_usage(&throttle_data), monitor::MAX_USAGE_PERCENT);
+        assert_eq!(limits.max_iterations(), Some(1000));
+
+        assert!(request(&path, "frobnicate").starts_with("error\nUnknown command"));
+        assert_eq!(request(&path, "stop"), "ok\nstopping\n");
+        handle.join().unwrap();
+        assert!(stop_flag.load(Ordering::Relaxed));
+        assert!(!path.exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
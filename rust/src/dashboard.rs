@@ -0,0 +1,406 @@
+// Full-screen terminal dashboard
+// Replaces the single status line when stdout is a terminal. Workers keep running in the
+// background; the Reporter captures found seeds and notices into the event log meanwhile.
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{BarChart, Block, Borders, Gauge, List, ListItem, Paragraph, Row, Sparkline, Table};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::format_number;
+use crate::monitor::device_key;
+use crate::reporter::Reporter;
+use crate::worker::CollectionRules;
+use crate::stats::{self, Backend, PauseReason, RunStats};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+// Seconds of throughput history kept for the sparkline
+const HISTORY_LEN: usize = 600;
+
+pub struct Dashboard {
+    stats: Arc<RunStats>,
+    throttle_data: Arc<Mutex<HashMap<String, f64>>>,
+    reporter: Arc<Reporter>,
+    stop_flag: Arc<AtomicBool>,
+    rules: Arc<CollectionRules>,
+    // Probability of each character count for a random candidate
+    distribution: Vec<f64>,
+    // Rates over the last sample interval
+    history: VecDeque<u64>,
+    rate: f64,
+    worker_rates: Vec<f64>,
+    backend_rates: [f64; 2],
+    device_rates: Vec<f64>,
+    last_sample: Instant,
+    last_total: u64,
+    last_workers: Vec<u64>,
+    last_backends: [u64; 2],
+    last_devices: Vec<u64>,
+}
+
+impl Dashboard {
+    pub fn new(
+        stats: Arc<RunStats>,
+        throttle_data: Arc<Mutex<HashMap<String, f64>>>,
+        reporter: Arc<Reporter>,
+        stop_flag: Arc<AtomicBool>,
+        rules: Arc<CollectionRules>,
+    ) -> Self {
+        let workers = stats.worker_iterations.len();
+        let devices = stats.devices.len();
+        Self {
+            stats,
+            throttle_data,
+            reporter,
+            stop_flag,
+            rules,
+            distribution: stats::score_distribution(),
+            history: VecDeque::with_capacity(HISTORY_LEN),
+            rate: 0.0,
+            worker_rates: vec![0.0; workers],
+            backend_rates: [0.0; 2],
+            device_rates: vec![0.0; devices],
+            last_sample: Instant::now(),
+            last_total: 0,
+            last_workers: vec![0; workers],
+            last_backends: [0; 2],
+            last_devices: vec![0; devices],
+        }
+    }
+
+    /// Run until the user quits, the stop flag is set or the workers finish
+    pub fn run(&mut self, workers: &JoinHandle<()>) -> io::Result<()> {
+        self.reporter.capture(true);
+        let mut terminal = ratatui::try_init()?;
+        let result = self.event_loop(&mut terminal, workers);
+        ratatui::restore();
+        self.reporter.capture(false);
+        result
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal, workers: &JoinHandle<()>) -> io::Result<()> {
+        while !self.stop_flag.load(Ordering::Relaxed) && !workers.is_finished() {
+            if self.last_sample.elapsed() >= SAMPLE_INTERVAL {
+                self.sample();
+            }
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if !event::poll(POLL_INTERVAL)? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => self.stop_flag.store(true, Ordering::Relaxed),
+                // Raw mode swallows SIGINT, so Ctrl+C arrives as a key
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.stop_flag.store(true, Ordering::Relaxed)
+                }
+                // Only the manual pause; run windows and idle-only keep their own
+                KeyCode::Char('p') | KeyCode::Char(' ') => {
+                    let paused = !self.stats.is_paused_by(PauseReason::Manual);
+                    self.stats.set_pause(PauseReason::Manual, paused);
+                    self.reporter.notice(if paused { "Paused" } else { "Resumed" });
+                    self.reporter.event(
+                        if paused { "paused" } else { "resumed" },
+                        serde_json::json!({ "reason": PauseReason::Manual.name() }),
+                    );
+                }
+                KeyCode::Char('r') if self.stats.set_pause(PauseReason::Manual, false) => {
+                    self.reporter.notice("Resumed");
+                    self.reporter.event("resumed", serde_json::json!({ "reason": PauseReason::Manual.name() }));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    // Turn counter deltas into rates
+    fn sample(&mut self) {
+        let seconds = self.last_sample.elapsed().as_secs_f64();
+        self.last_sample = Instant::now();
+
+        let total = self.stats.iterations.load(Ordering::Relaxed);
+        self.rate = total.saturating_sub(self.last_total) as f64 / seconds;
+        self.last_total = total;
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(self.rate as u64);
+
+        for (i, counter) in self.stats.worker_iterations.iter().enumerate() {
+            let count = counter.load(Ordering::Relaxed);
+            self.worker_rates[i] = (count - self.last_workers[i]) as f64 / seconds;
+            self.last_workers[i] = count;
+        }
+        for backend in Backend::ALL {
+            let count = self.stats.backend_iterations(backend);
+            let i = backend as usize;
+            self.backend_rates[i] = (count - self.last_backends[i]) as f64 / seconds;
+            self.last_backends[i] = count;
+        }
+        for (i, device) in self.stats.devices.iter().enumerate() {
+            let count = device.candidates.load(Ordering::Relaxed);
+            self.device_rates[i] = (count - self.last_devices[i]) as f64 / seconds;
+            self.last_devices[i] = count;
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, top, middle, bottom] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(7),
+            Constraint::Min(8),
+            Constraint::Min(8),
+        ])
+        .areas(frame.area());
+        let [throughput, resources] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(top);
+        let [rates, histogram] =
+            Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)]).areas(middle);
+        let [buckets, events] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(bottom);
+
+        self.draw_header(frame, header);
+        self.draw_throughput(frame, throughput);
+        self.draw_resources(frame, resources);
+        self.draw_rates(frame, rates);
+        self.draw_histogram(frame, histogram);
+        self.draw_buckets(frame, buckets);
+        self.draw_events(frame, events);
+    }
+
+    fn draw_header(&self, frame: &mut Frame, area: Rect) {
+        let reasons: Vec<&str> = self.stats.pause_reasons().map(PauseReason::name).collect();
+        let state = if !reasons.is_empty() {
+            Span::styled(
+                format!(" PAUSED ({}) ", reasons.join(", ")),
+                Style::default().fg(Color::Black).bg(Color::Yellow),
+            )
+        } else {
+            Span::styled(" RUNNING ", Style::default().fg(Color::Black).bg(Color::Green))
+        };
+        let line = Line::from(vec![
+            state,
+            Span::raw(format!(
+                "  Elapsed {}  |  Processed {}  |  {}/s (avg {}/s)  |  Found {}  |  Best {}",
+                format_elapsed(self.stats.start.elapsed()),
+                format_number(self.stats.iterations.load(Ordering::Relaxed)),
+                format_number(self.rate as u64),
+                format_number(self.stats.average_rate() as u64),
+                self.stats.found_snapshot().values().sum::<usize>(),
+                self.stats.best_chars().map_or("-".to_string(), |chars| chars.to_string()),
+            )),
+            Span::styled("   [p] pause  [r] resume  [q] quit", Style::default().fg(Color::DarkGray)),
+        ]);
+        let block = Block::default().borders(Borders::ALL).title(" gpuseed-rust ");
+        frame.render_widget(Paragraph::new(line).block(block), area);
+    }
+
+    fn draw_throughput(&self, frame: &mut Frame, area: Rect) {
+        // Most recent samples that fit the width
+        let width = area.width.saturating_sub(2) as usize;
+        let data: Vec<u64> = self
+            .history
+            .iter()
+            .skip(self.history.len().saturating_sub(width))
+            .copied()
+            .collect();
+        let peak = data.iter().copied().max().unwrap_or(0);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" Throughput (candidates/s, peak {}) ", format_number(peak)));
+        frame.render_widget(
+            Sparkline::default()
+                .block(block)
+                .data(&data)
+                .style(Style::default().fg(Color::Cyan)),
+            area,
+        );
+    }
+
+    fn draw_resources(&self, frame: &mut Frame, area: Rect) {
+        let (cpu_usage, gpu_usage, cpu_throttle, gpu_throttle) = {
+            let data = self.throttle_data.lock().unwrap();
+            (
+                data.get("cpu_usage").copied().unwrap_or(0.0),
+                data.get("gpu_usage").copied().unwrap_or(0.0),
+                data.get("cpu_throttle").copied().unwrap_or(1.0),
+                data.get("gpu_throttle").copied().unwrap_or(1.0),
+            )
+        };
+        let block = Block::default().borders(Borders::ALL).title(" Resources ");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let rows = Layout::vertical([Constraint::Length(1); 4]).split(inner);
+        let gauges = [
+            ("CPU usage", cpu_usage, Color::Green),
+            ("GPU usage", gpu_usage, Color::Green),
+            ("CPU throttle", cpu_throttle, Color::Yellow),
+            ("GPU throttle", gpu_throttle, Color::Yellow),
+        ];
+        for (row, (label, value, color)) in rows.iter().zip(gauges) {
+            let gauge = Gauge::default()
+                .gauge_style(Style::default().fg(color))
+                .ratio(value.clamp(0.0, 1.0))
+                .label(format!("{} {:.0}%", label, value * 100.0));
+            frame.render_widget(gauge, *row);
+        }
+    }
+
+    fn draw_rates(&self, frame: &mut Frame, area: Rect) {
+        let mut rows: Vec<Row> = Backend::ALL
+            .iter()
+            .map(|&backend| {
+                Row::new(vec![
+                    format!("backend {}", backend.name()),
+                    format!("{}/s", format_number(self.backend_rates[backend as usize] as u64)),
+                ])
+            })
+            .collect();
+        rows.push(Row::new(vec![
+            "gpu failures".to_string(),
+            format_number(self.stats.gpu_failures.load(Ordering::Relaxed)),
+        ]));
+        {
+            let data = self.throttle_data.lock().unwrap();
+            rows.extend(self.stats.devices.iter().zip(&self.device_rates).map(|(device, rate)| {
+                let usage = data.get(&device_key("gpu_usage", device.index)).copied().unwrap_or(0.0);
+                Row::new(vec![
+                    format!("gpu {}", device.index),
+                    format!("{}/s ({:.0}%)", format_number(*rate as u64), usage * 100.0),
+                ])
+            }));
+        }
+        rows.extend(self.worker_rates.iter().enumerate().map(|(i, rate)| {
+            Row::new(vec![format!("worker {}", i), format!("{}/s", format_number(*rate as u64))])
+        }));
+
+        let table = Table::new(rows, [Constraint::Length(16), Constraint::Min(10)])
+            .block(Block::default().borders(Borders::ALL).title(" Rates "));
+        frame.render_widget(table, area);
+    }
+
+    fn draw_histogram(&self, frame: &mut Frame, area: Rect) {
+        let found = self.stats.found_snapshot();
+        let (threshold, _) = self.rules.get();
+        // The 12 counts below the threshold (more if shorter seeds were found), as many as fit
+        let lowest = found.keys().copied().min().unwrap_or(threshold);
+        let fitting = (area.width.saturating_sub(2) / 5) as usize;
+        let first = lowest
+            .min(threshold.saturating_sub(12))
+            .max(threshold.saturating_sub(fitting));
+        let labels: Vec<String> = (first..threshold).map(|chars| chars.to_string()).collect();
+        let bars: Vec<(&str, u64)> = labels
+            .iter()
+            .zip(first..threshold)
+            .map(|(label, chars)| (label.as_str(), found.get(&chars).copied().unwrap_or(0) as u64))
+            .collect();
+
+        let chart = BarChart::default()
+            .block(Block::default().borders(Borders::ALL).title(" Found by character count "))
+            .data(bars.as_slice())
+            .bar_width(4)
+            .bar_gap(1)
+            .bar_style(Style::default().fg(Color::Magenta))
+            .value_style(Style::default().fg(Color::Black).bg(Color::Magenta));
+        frame.render_widget(chart, area);
+    }
+
+    fn draw_buckets(&self, frame: &mut Frame, area: Rect) {
+        let found = self.stats.found_snapshot();
+        let (threshold, count_per_threshold) = self.rules.get();
+        // Every candidate with this count, collected or not
+        let seen = self.stats.score_snapshot();
+        let header = Row::new(vec!["Chars", "Seen", "Found", "Limit", "Odds", "ETA"])
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        let visible = area.height.saturating_sub(3) as usize;
+
+        let rows: Vec<Row> = (0..threshold)
+            .rev()
+            .filter(|&chars| self.distribution.get(chars).copied().unwrap_or(0.0) > 0.0)
+            .take(visible)
+            .map(|chars| {
+                let probability = self.distribution[chars];
+                let count = found.get(&chars).copied().unwrap_or(0);
+                // Same collection rule as the workers: 42 or less is unlimited
+                let limited = chars > 42;
+                let eta = if limited && count >= count_per_threshold {
+                    "full".to_string()
+                } else {
+                    let remaining = if limited { count_per_threshold - count } else { 1 };
+                    let rate = if self.rate > 0.0 { self.rate } else { self.stats.average_rate() };
+                    format_eta(remaining as f64 / (probability * rate))
+                };
+                Row::new(vec![
+                    chars.to_string(),
+                    format_number(seen[chars]),
+                    count.to_string(),
+                    if limited { count_per_threshold.to_string() } else { "-".to_string() },
+                    format!("1 in {:.3e}", 1.0 / probability),
+                    eta,
+                ])
+            })
+            .collect();
+
+        let widths = [
+            Constraint::Length(6),
+            Constraint::Length(10),
+            Constraint::Length(6),
+            Constraint::Length(6),
+            Constraint::Length(14),
+            Constraint::Min(8),
+        ];
+        let table = Table::new(rows, widths).header(header).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Buckets (ETA: fill limit, or next find) "),
+        );
+        frame.render_widget(table, area);
+    }
+
+    fn draw_events(&self, frame: &mut Frame, area: Rect) {
+        let visible = area.height.saturating_sub(2) as usize;
+        let events = self.reporter.recent_events(visible);
+        let items: Vec<ListItem> = events
+            .iter()
+            .map(|line| ListItem::new(line.as_str()))
+            .collect();
+        let list = List::new(items).block(Block::default().borders(Borders::ALL).title(" Events "));
+        frame.render_widget(list, area);
+    }
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+fn format_eta(seconds: f64) -> String {
+    if !seconds.is_finite() {
+        return "-".to_string();
+    }
+    match seconds {
+        s if s < 1.0 => "<1s".to_string(),
+        s if s < 60.0 => format!("{:.0}s", s),
+        s if s < 3600.0 => format!("{:.0}m", s / 60.0),
+        s if s < 86_400.0 => format!("{:.1}h", s / 3600.0),
+        s if s < 31_557_600.0 => format!("{:.1}d", s / 86_400.0),
+        s if s < 3_155_760_000.0 => format!("{:.1}y", s / 31_557_600.0),
+        _ => ">100y".to_string(),
+    }
+}
@@ -0,0 +1,164 @@
+// `export` subcommand: render stored mnemonics for signing devices and metal backups
+use bitcoin::hex::FromHex;
+use clap::{Args, ValueEnum};
+use std::fs;
+
+use crate::plate::{self, PlateLayout};
+use crate::results;
+use crate::seedqr::{self, SeedQrFormat};
+use crate::worker::FoundSeed;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Standard SeedQR (digit-encoded word indices)
+    Seedqr,
+    /// CompactSeedQR (raw entropy bytes)
+    CompactSeedqr,
+    /// Steel plate stamping template (4-letter abbreviations)
+    Plate,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Render {
+    /// PNG image
+    Png,
+    /// SVG image
+    Svg,
+    /// Terminal output (ANSI QR blocks or plate text)
+    Ansi,
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    /// Results file to read (output or log file)
+    #[arg(long, default_value = "seeds_output.txt")]
+    input: String,
+
+    /// Which seed to export (1-based, in file order); all seeds if omitted
+    #[arg(long)]
+    index: Option<usize>,
+
+    /// Export format
+    #[arg(long, value_enum, default_value_t = ExportFormat::Seedqr)]
+    format: ExportFormat,
+
+    /// Word grid for plate templates
+    #[arg(long, value_enum, default_value_t = PlateLayout::Double)]
+    layout: PlateLayout,
+
+    /// Output rendering
+    #[arg(long, value_enum, default_value_t = Render::Ansi)]
+    render: Render,
+
+    /// Output file for PNG/SVG (a -<n> suffix is added when exporting several seeds)
+    #[arg(long, default_value = "seed")]
+    out: String,
+
+    /// Pixels per QR module for PNG output
+    #[arg(long, default_value_t = 10)]
+    scale: usize,
+}
+
+pub fn run(args: &ExportArgs) -> Result<(), String> {
+    if args.scale == 0 || args.scale > 100 {
+        return Err("Scale must be between 1 and 100".to_string());
+    }
+
+    let results = results::load_results(&args.input)?;
+    let selected: Vec<(usize, &FoundSeed)> = match args.index {
+        Some(index) => {
+            let found = results
+                .iter()
+                .nth(index.wrapping_sub(1))
+                .ok_or_else(|| format!("Index {} out of range (1-{})", index, results.len()))?;
+            vec![(index, found)]
+        }
+        None => results.iter().enumerate().map(|(i, f)| (i + 1, f)).collect(),
+    };
+    let several = selected.len() > 1;
+
+    for (number, found) in selected {
+        match args.format {
+            ExportFormat::Seedqr => export_seedqr(args, SeedQrFormat::Standard, number, found, several)?,
+            ExportFormat::CompactSeedqr => export_seedqr(args, SeedQrFormat::Compact, number, found, several)?,
+            ExportFormat::Plate => export_plate(args, number, found, several)?,
+        }
+    }
+    Ok(())
+}
+
+fn export_seedqr(
+    args: &ExportArgs,
+    format: SeedQrFormat,
+    number: usize,
+    found: &FoundSeed,
+    several: bool,
+) -> Result<(), String> {
+    // Round-trip check: the payload must decode back to the same seed
+    let payload = seedqr::encode_payload(&found.entropy, format)?;
+    let decoded = seedqr::decode_payload(&payload)?;
+    if decoded.to_entropy() != found.entropy {
+        return Err(format!("Seed #{}: SeedQR payload did not round-trip", number));
+    }
+
+    let code = seedqr::build_code(&found.entropy, format)?;
+    match args.render {
+        Render::Ansi => {
+            println!("Seed #{} ({} characters)", number, found.chars);
+            print!("{}", seedqr::render_ansi(&code));
+        }
+        Render::Png | Render::Svg => {
+            let extension = if args.render == Render::Png { "png" } else { "svg" };
+            let path = output_path(&args.out, extension, several.then_some(number));
+            let bytes = match args.render {
+                Render::Png => seedqr::render_png(&code, args.scale)?,
+                _ => seedqr::render_svg(&code).into_bytes(),
+            };
+            fs::write(&path, bytes).map_err(|e| format!("Could not write {}: {}", path, e))?;
+            println!("Seed #{} ({} characters) -> {}", number, found.chars, path);
+        }
+    }
+    Ok(())
+}
+
+fn export_plate(args: &ExportArgs, number: usize, found: &FoundSeed, several: bool) -> Result<(), String> {
+    match args.render {
+        Render::Ansi => {
+            println!("Seed #{} ({} characters)", number, found.chars);
+            println!("{}", *plate::render_text(found, args.layout));
+        }
+        Render::Svg => {
+            let path = output_path(&args.out, "svg", several.then_some(number));
+            let svg = plate::render_svg(found, args.layout, &format!("Seed #{}", number));
+            fs::write(&path, svg).map_err(|e| format!("Could not write {}: {}", path, e))?;
+            println!("Seed #{} ({} characters) -> {}", number, found.chars, path);
+        }
+        Render::Png => return Err("Plate templates support --render ansi or svg".to_string()),
+    }
+    Ok(())
+}
+
+/// Decode a SeedQR payload (digits) or CompactSeedQR payload (hex) into a phrase
+pub fn decode(payload: &str) -> Result<(), String> {
+    let payload = payload.trim();
+    let is_seedqr = (payload.len() == 48 || payload.len() == 96)
+        && payload.bytes().all(|b| b.is_ascii_digit());
+    let bytes = if is_seedqr {
+        payload.as_bytes().to_vec()
+    } else {
+        Vec::<u8>::from_hex(payload).map_err(|e| format!("Invalid hex payload: {}", e))?
+    };
+    let mnemonic = seedqr::decode_payload(&bytes)?;
+    println!("{}", mnemonic);
+    Ok(())
+}
+
+/// `out` with the extension added (unless present) and an optional -<n> suffix
+pub fn output_path(out: &str, extension: &str, number: Option<usize>) -> String {
+    let dotted = format!(".{}", extension);
+    let stem = out.strip_suffix(&dotted).unwrap_or(out);
+    match number {
+        Some(n) => format!("{}-{}{}", stem, n, dotted),
+        None => format!("{}{}", stem, dotted),
+    }
+}
@@ -0,0 +1,369 @@
+// GPU feeders: per device, one thread owns the GPU session (and its CUDA context) and keeps a
+// bounded queue of batches filled for the CPU workers. A full queue makes the feeder wait
+// (backpressure); a worker that finds every queue empty generates its batch on the CPU instead
+// of waiting, so a slow or failing GPU never holds the workers up.
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use crate::gpu::GpuContext;
+use crate::limits::RunLimits;
+use crate::monitor::device_key;
+use crate::reporter::Reporter;
+use crate::session::{GpuBatch, Job};
+use crate::stats::RunStats;
+use crate::worker::CollectionRules;
+
+// How long the feeder waits before retrying after a GPU error
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+pub struct GpuFeeder {
+    context: GpuContext,
+    // Scored on the device, unless user entropy is mixed in on the CPU
+    scoring: bool,
+    batch_size: usize,
+    depth: usize,
+    // Batches with the job and candidate count they were launched with
+    queue: Mutex<VecDeque<(Job, usize, GpuBatch)>>,
+    // Signalled when a worker takes a batch
+    not_full: Condvar,
+    rules: Arc<CollectionRules>,
+    limits: Arc<RunLimits>,
+    throttle_data: Arc<Mutex<HashMap<String, f64>>>,
+    stats: Arc<RunStats>,
+    reporter: Arc<Reporter>,
+    produced: AtomicU64,
+    taken: AtomicU64,
+    // Queued batches scored for a threshold that has changed since
+    stale: AtomicU64,
+}
+
+impl GpuFeeder {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        context: GpuContext,
+        scoring: bool,
+        batch_size: usize,
+        depth: usize,
+        rules: Arc<CollectionRules>,
+        limits: Arc<RunLimits>,
+        throttle_data: Arc<Mutex<HashMap<String, f64>>>,
+        stats: Arc<RunStats>,
+        reporter: Arc<Reporter>,
+    ) -> Self {
+        Self {
+            context,
+            scoring,
+            batch_size,
+            depth: depth.max(1),
+            queue: Mutex::new(VecDeque::new()),
+            not_full: Condvar::new(),
+            rules,
+            limits,
+            throttle_data,
+            stats,
+            reporter,
+            produced: AtomicU64::new(0),
+            taken: AtomicU64::new(0),
+            stale: AtomicU64::new(0),
+        }
+    }
+
+    pub fn device(&self) -> u32 {
+        self.context.device()
+    }
+
+    /// Feeder thread: fill the queue until the run stops
+    pub fn run(&self, stop_flag: &AtomicBool) {
+        let device = self.device();
+        let task = if self.scoring { "score candidates" } else { "generate entropy" };
+        self.reporter.notice(&format!(
+            "[GPU {}] Feeder started: {} on the GPU, up to {} batches queued ahead of the workers",
+            device, task, self.depth
+        ));
+        let mut logged_success = false;
+        let mut logged_error = false;
+
+        while !stop_flag.load(Ordering::Relaxed) {
+            if self.stats.is_paused() {
+                std::thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+
+            // Backpressure: wait until a worker takes a batch
+            {
+                let queue = self.queue.lock().unwrap();
+                if queue.len() >= self.depth {
+                    let _ = self.not_full.wait_timeout(queue, Duration::from_millis(100)).unwrap();
+                    continue;
+                }
+            }
+
+            let (job, size) = self.next_job();
+            match self.context.next_batch(job, size) {
+                Ok(batch) => {
+                    if !logged_success {
+                        logged_success = true;
+                        self.reporter.notice(&format!(
+                            "[GPU {}] Successfully running on GPU: {} (batch size: {})",
+                            device, task, size
+                        ));
+                    }
+                    self.queue.lock().unwrap().push_back((job, size, batch));
+                    self.produced.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(e) => {
+                    // Workers keep going on the CPU while the queue is empty
+                    // Only log once to avoid spam
+                    if !logged_error {
+                        logged_error = true;
+                        self.reporter.notice(&format!(
+                            "[GPU {}] ERROR: GPU generation failed: {}. Using CPU fallback.",
+                            device, e
+                        ));
+                        self.reporter.event("backend_fallback", serde_json::json!({
+                            "from": "gpu", "to": "cpu", "device": device, "error": e.to_string(),
+                        }));
+                    }
+                    self.stats.gpu_failures.fetch_add(1, Ordering::Relaxed);
+                    std::thread::sleep(RETRY_DELAY);
+                }
+            }
+        }
+
+        // Queued batches are zeroizing; drop them with the session
+        self.queue.lock().unwrap().clear();
+        self.context.cleanup();
+    }
+
+    // Job and size of the next batch: current threshold, batch size under this device's
+    // throttling, and never more than is left of --max-iterations
+    fn next_job(&self) -> (Job, usize) {
+        let gpu_throttle = self
+            .throttle_data
+            .lock()
+            .unwrap()
+            .get(&device_key("gpu_throttle", self.device()))
+            .copied()
+            .unwrap_or(1.0);
+        let size = std::cmp::max(1, (self.batch_size as f64 * gpu_throttle) as usize);
+        if !self.scoring {
+            return (Job::Entropy, size);
+        }
+        let (threshold, _) = self.rules.get();
+        let remaining = self
+            .limits
+            .max_iterations()
+            .map_or(u64::MAX, |max| max.saturating_sub(self.stats.iterations.load(Ordering::Relaxed)));
+        let size = size.min(remaining.try_into().unwrap_or(usize::MAX)).max(1);
+        (Job::Scored { threshold }, size)
+    }
+
+    /// A queued batch for a worker, or None when the queue is empty. Batches scored for
+    /// another threshold are dropped. Only batches handed out count toward the device's
+    /// throughput (not stale ones or those cleared at shutdown).
+    pub fn take(&self, threshold: usize) -> Option<GpuBatch> {
+        let mut queue = self.queue.lock().unwrap();
+        while let Some((job, size, batch)) = queue.pop_front() {
+            self.not_full.notify_one();
+            match job {
+                Job::Scored { threshold: scored } if scored != threshold => {
+                    self.stale.fetch_add(1, Ordering::Relaxed);
+                }
+                _ => {
+                    self.taken.fetch_add(1, Ordering::Relaxed);
+                    self.stats.add_device_batch(self.device(), size as u64);
+                    return Some(batch);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} batches produced, {} taken by workers, {} dropped for a changed threshold; {}",
+            self.produced.load(Ordering::Relaxed),
+            self.taken.load(Ordering::Relaxed),
+            self.stale.load(Ordering::Relaxed),
+            self.context.counters().summary(),
+        )
+    }
+}
+
+/// The feeders of all devices of the run
+pub struct GpuFeeders {
+    feeders: Vec<Arc<GpuFeeder>>,
+    // Worker batches generated on the CPU because every queue was empty
+    dry: AtomicU64,
+}
+
+impl GpuFeeders {
+    pub fn new(feeders: Vec<Arc<GpuFeeder>>) -> Self {
+        Self { feeders, dry: AtomicU64::new(0) }
+    }
+
+    pub fn feeders(&self) -> &[Arc<GpuFeeder>] {
+        &self.feeders
+    }
+
+    /// A queued batch for `worker`, or None when every queue is empty (the worker then
+    /// generates its batch on the CPU). Workers start at different devices, so the queues
+    /// drain evenly.
+    pub fn take(&self, worker: usize, threshold: usize) -> Option<GpuBatch> {
+        let count = self.feeders.len();
+        let batch = if count == 0 {
+            None
+        } else {
+            (0..count).find_map(|i| self.feeders[(worker + i) % count].take(threshold))
+        };
+        if batch.is_none() {
+            self.dry.fetch_add(1, Ordering::Relaxed);
+        }
+        batch
+    }
+
+    /// One line per device, then the batches the workers had to generate themselves
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .feeders
+            .iter()
+            .map(|feeder| format!("GPU {}: {}", feeder.device(), feeder.summary()))
+            .collect();
+        lines.push(format!(
+            "GPU: {} worker batches on the CPU (queues empty)",
+            self.dry.load(Ordering::Relaxed)
+        ));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu::{DeviceSelection, GpuRng};
+    use crate::reporter::ConsoleMode;
+    use std::time::Instant;
+    use zeroize::Zeroizing;
+
+    fn feeder(device: u32, depth: usize, rules: &Arc<CollectionRules>) -> Arc<GpuFeeder> {
+        let reporter = Arc::new(Reporter::new(ConsoleMode::Redacted, Zeroizing::default()));
+        let context = GpuContext::simulated(device, 16, GpuRng::Philox, Duration::ZERO, reporter.clone());
+        Arc::new(GpuFeeder::new(
+            context,
+            true,
+            16,
+            depth,
+            rules.clone(),
+            Arc::new(RunLimits::new(None, None, None, Vec::new()).unwrap()),
+            Arc::new(Mutex::new(HashMap::new())),
+            Arc::new(RunStats::new(1, &DeviceSelection::List(vec![device]).simulated())),
+            reporter,
+        ))
+    }
+
+    fn spawn(feeder: &Arc<GpuFeeder>, stop_flag: &Arc<AtomicBool>) -> std::thread::JoinHandle<()> {
+        let (feeder, stop_flag) = (feeder.clone(), stop_flag.clone());
+        std::thread::spawn(move || feeder.run(&stop_flag))
+    }
+
+    fn wait_for(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn queued(feeder: &GpuFeeder) -> usize {
+        feeder.queue.lock().unwrap().len()
+    }
+
+    #[test]
+    fn stops_producing_at_the_queue_depth() {
+        let rules = Arc::new(CollectionRules::new(46, 5).unwrap());
+        let feeder = feeder(0, 2, &rules);
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let handle = spawn(&feeder, &stop_flag);
+
+        wait_for(|| queued(&feeder) == 2);
+        std::thread::sleep(Duration::from_millis(300));
+        assert_eq!(queued(&feeder), 2);
+        assert_eq!(feeder.produced.load(Ordering::Relaxed), 2);
+
+        // Taking one makes room for exactly one more
+        assert!(feeder.take(46).is_some());
+        wait_for(|| feeder.produced.load(Ordering::Relaxed) == 3);
+        std::thread::sleep(Duration::from_millis(300));
+        assert_eq!(queued(&feeder), 2);
+        assert_eq!(feeder.produced.load(Ordering::Relaxed), 3);
+
+        stop_flag.store(true, Ordering::Relaxed);
+        handle.join().unwrap();
+        assert_eq!(queued(&feeder), 0);
+    }
+
+    #[test]
+    fn drops_batches_scored_for_an_old_threshold() {
+        let rules = Arc::new(CollectionRules::new(46, 5).unwrap());
+        let feeder = feeder(0, 2, &rules);
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let handle = spawn(&feeder, &stop_flag);
+        wait_for(|| queued(&feeder) == 2);
+
+        rules.set(44, 5).unwrap();
+        // Both queued batches were scored for 46; none is handed out for 44
+        assert!(feeder.take(44).is_none());
+        assert_eq!(feeder.stale.load(Ordering::Relaxed), 2);
+        assert_eq!(feeder.taken.load(Ordering::Relaxed), 0);
+        assert_eq!(feeder.stats.devices[0].batches.load(Ordering::Relaxed), 0);
+
+        // The feeder refills the queue for the new threshold
+        wait_for(|| queued(&feeder) > 0);
+        match feeder.take(44) {
+            Some(GpuBatch::Scored(scored)) => assert!(scored.hits.iter().all(|hit| hit.chars < 44)),
+            _ => panic!("no batch scored for the new threshold"),
+        }
+        assert_eq!(feeder.taken.load(Ordering::Relaxed), 1);
+
+        // Only the batch handed out counts for the device, not those cleared at shutdown
+        stop_flag.store(true, Ordering::Relaxed);
+        handle.join().unwrap();
+        let device = &feeder.stats.devices[0];
+        assert_eq!(device.batches.load(Ordering::Relaxed), 1);
+        assert_eq!(device.candidates.load(Ordering::Relaxed), 16);
+    }
+
+    #[test]
+    fn counts_worker_batches_when_every_queue_is_empty() {
+        let rules = Arc::new(CollectionRules::new(46, 5).unwrap());
+        let feeders = GpuFeeders::new(vec![feeder(0, 2, &rules), feeder(1, 2, &rules)]);
+        assert!(feeders.take(0, 46).is_none());
+        assert!(feeders.take(1, 46).is_none());
+        assert_eq!(feeders.dry.load(Ordering::Relaxed), 2);
+
+        // A batch on either device is found, whichever device the worker starts at
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let second = feeders.feeders()[1].clone();
+        let handle = spawn(&second, &stop_flag);
+        wait_for(|| queued(&second) == 2);
+        assert!(feeders.take(0, 46).is_some());
+        assert!(feeders.take(1, 46).is_some());
+        assert_eq!(feeders.dry.load(Ordering::Relaxed), 2);
+        stop_flag.store(true, Ordering::Relaxed);
+        handle.join().unwrap();
+        assert_eq!(
+            feeders.summary().last().unwrap(),
+            "GPU: 2 worker batches on the CPU (queues empty)"
+        );
+    }
+
+    #[test]
+    fn takes_nothing_without_devices() {
+        let feeders = GpuFeeders::new(Vec::new());
+        assert!(feeders.take(3, 46).is_none());
+        assert_eq!(feeders.dry.load(Ordering::Relaxed), 1);
+        assert_eq!(feeders.summary(), ["GPU: 1 worker batches on the CPU (queues empty)"]);
+    }
+}
@@ -3,339 +3,931 @@
 #[cfg(feature = "gpu")]
 use rustacuda::prelude::*;
 #[cfg(feature = "gpu")]
-use rustacuda::memory::DeviceBuffer;
+use rustacuda::error::CudaResult;
+#[cfg(feature = "gpu")]
+use rustacuda::memory::{AsyncCopyDestination, DeviceBuffer, LockedBuffer};
 #[cfg(feature = "gpu")]
 use rustacuda::function::Function;
 #[cfg(feature = "gpu")]
 use std::ffi::CString;
-#[cfg(feature = "gpu")]
+use clap::ValueEnum;
 use std::cell::RefCell;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "gpu")]
+use rand::RngCore;
+#[cfg(feature = "gpu")]
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::reporter::Reporter;
+use crate::scoring::ScoredBatch;
+#[cfg(feature = "gpu")]
+use crate::scoring::{self, Hit};
+use crate::secure::EntropyBatch;
+use crate::session::{GpuBatch, GpuSession, Job, SessionBackend, SessionCounters, SimulatedBackend};
+#[cfg(feature = "gpu")]
+use crate::stats::MAX_SCORE;
 
 // Auto-generated module with embedded CUDA kernel PTX
-// This module is generated by build.rs during compilation
+// This module is generated by build.rs during compilation (kernels/gpuseed.cu, one image per
+// compute capability of GPUSEED_CUDA_ARCHS)
 #[cfg(feature = "gpu")]
 mod kernel_ptx {
     include!(concat!(env!("OUT_DIR"), "/kernel_ptx.rs"));
 }
 
-// Thread-local storage for CUDA context, module, and kernels
-// CUDA contexts are not Send/Sync, so we use thread-local storage
+// Compute capability of a device as major * 10 + minor, like the kernel images
 #[cfg(feature = "gpu")]
+fn compute_capability(device: Device) -> CudaResult<u32> {
+    use rustacuda::device::DeviceAttribute;
+    let major = device.get_attribute(DeviceAttribute::ComputeCapabilityMajor)?;
+    let minor = device.get_attribute(DeviceAttribute::ComputeCapabilityMinor)?;
+    Ok((major * 10 + minor) as u32)
+}
+
+// Kernel images a device can load, best first: PTX runs on its own compute capability and every
+// later one (the driver compiles it for the device), so the newest image not newer than the
+// device is preferred and the older ones are fallbacks
+#[cfg(feature = "gpu")]
+fn compatible_images(capability: u32) -> Vec<(u32, &'static str)> {
+    let mut images: Vec<(u32, &'static str)> = kernel_ptx::KERNEL_IMAGES
+        .iter()
+        .copied()
+        .filter(|&(arch, _)| arch <= capability)
+        .collect();
+    images.sort_by_key(|&(arch, _)| std::cmp::Reverse(arch));
+    images
+}
+
+#[cfg(feature = "gpu")]
+fn capability_name(capability: u32) -> String {
+    format!("{}.{}", capability / 10, capability % 10)
+}
+
+// Thread-local GPU session (states, buffers and streams live as long as the feeder thread)
+// CUDA contexts are not Send/Sync, so we use thread-local storage
 thread_local! {
-    static GPU_THREAD_CONTEXT: RefCell<Option<ThreadGpuContext>> = RefCell::new(None);
+    static GPU_SESSION: RefCell<Option<GpuSession>> = const { RefCell::new(None) };
 }
 
+// CUDA context of one thread with the kernels loaded
 #[cfg(feature = "gpu")]
-struct ThreadGpuContext {
-    _context: Context,
+struct CudaModule {
+    // BIP39 word lengths for the scoring kernels
+    word_lengths: DeviceBuffer<u8>,
+    // Dropped before the context they were created in
     module_and_kernels: Box<ModuleAndKernels>,
+    _context: Context,
 }
 
 #[cfg(feature = "gpu")]
 struct ModuleAndKernels {
     _module: Module,
-    init_kernel: Function<'static>,
-    gen_kernel: Function<'static>,
+    xorwow: GeneratorKernels,
+    philox: GeneratorKernels,
+    score_entropies_kernel: Function<'static>,
+}
+
+// State initialization, generation and scoring kernels of one generator
+#[cfg(feature = "gpu")]
+struct GeneratorKernels {
+    init: Function<'static>,
+    generate: Function<'static>,
+    score: Function<'static>,
+}
+
+#[cfg(feature = "gpu")]
+impl ModuleAndKernels {
+    fn generator(&self, rng: GpuRng) -> &GeneratorKernels {
+        match rng {
+            GpuRng::Xorwow => &self.xorwow,
+            GpuRng::Philox => &self.philox,
+        }
+    }
+}
+
+/// Random number generator of the GPU kernels (`--gpu-rng`)
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GpuRng {
+    /// cuRAND's default XORWOW generator
+    Xorwow,
+    /// Counter-based Philox4x32-10, reproducible on the host (see `gpu-check`)
+    Philox,
+}
+
+impl GpuRng {
+    pub fn name(self) -> &'static str {
+        match self {
+            GpuRng::Xorwow => "XORWOW",
+            GpuRng::Philox => "Philox4x32-10",
+        }
+    }
+
+    // Bytes per cuRAND state (curandState / curandStatePhilox4_32_10_t)
+    #[cfg(feature = "gpu")]
+    fn state_size(self) -> usize {
+        match self {
+            GpuRng::Xorwow => 48,
+            GpuRng::Philox => 64,
+        }
+    }
+}
+
+/// A CUDA device (`--devices`)
+#[derive(Clone, Debug)]
+pub struct GpuDevice {
+    pub index: u32,
+    pub name: String,
+    // PCI bus id ("0000:01:00.0"): NVML numbers devices by bus, CUDA fastest first
+    pub pci_bus_id: Option<String>,
+}
+
+/// `--devices`: every device, or a list of CUDA device indices
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeviceSelection {
+    All,
+    List(Vec<u32>),
+}
+
+impl FromStr for DeviceSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        if s.trim().eq_ignore_ascii_case("all") {
+            return Ok(DeviceSelection::All);
+        }
+        let error = || format!("Invalid device list \"{}\" (expected all or indices, e.g. 0,1)", s);
+        let mut indices = Vec::new();
+        for index in s.split(',') {
+            let index: u32 = index.trim().parse().map_err(|_| error())?;
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+        Ok(DeviceSelection::List(indices))
+    }
+}
+
+impl fmt::Display for DeviceSelection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeviceSelection::All => write!(f, "all"),
+            DeviceSelection::List(indices) => {
+                let indices: Vec<String> = indices.iter().map(u32::to_string).collect();
+                write!(f, "{}", indices.join(","))
+            }
+        }
+    }
+}
+
+impl DeviceSelection {
+    /// The selected devices among `available`; indices that do not exist are skipped
+    pub fn resolve(&self, available: &[GpuDevice]) -> Vec<GpuDevice> {
+        match self {
+            DeviceSelection::All => available.to_vec(),
+            DeviceSelection::List(indices) => indices
+                .iter()
+                .filter_map(|&index| available.iter().find(|device| device.index == index).cloned())
+                .collect(),
+        }
+    }
+
+    /// Selected indices that are not among `available` (`resolve` skips them)
+    pub fn missing(&self, available: &[GpuDevice]) -> Vec<u32> {
+        match self {
+            DeviceSelection::All => Vec::new(),
+            DeviceSelection::List(indices) => indices
+                .iter()
+                .copied()
+                .filter(|&index| !available.iter().any(|device| device.index == index))
+                .collect(),
+        }
+    }
+
+    /// Devices of `--simulate-gpu`: the listed indices, or a single device for `all`
+    pub fn simulated(&self) -> Vec<GpuDevice> {
+        let indices = match self {
+            DeviceSelection::All => vec![0],
+            DeviceSelection::List(indices) => indices.clone(),
+        };
+        indices
+            .into_iter()
+            .map(|index| GpuDevice { index, name: "simulated".to_string(), pci_bus_id: None })
+            .collect()
+    }
+}
+
+/// CUDA devices of this machine. Without GPUs (no driver, no device, or a build without the
+/// `gpu` feature) this is an empty list, with the reason as the error.
+pub fn enumerate_devices() -> Result<Vec<GpuDevice>, String> {
+    #[cfg(feature = "gpu")]
+    {
+        let devices = rustacuda::init(CudaFlags::empty()).and_then(|_| {
+            (0..Device::num_devices()?)
+                .map(|index| {
+                    let device = Device::get_device(index)?;
+                    Ok(GpuDevice {
+                        index,
+                        name: device.name()?,
+                        pci_bus_id: pci_bus_id(device).ok(),
+                    })
+                })
+                .collect::<CudaResult<Vec<_>>>()
+        });
+        match devices {
+            Ok(devices) if devices.is_empty() => Err("no CUDA device found".to_string()),
+            Ok(devices) => Ok(devices),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    {
+        Err("GPU support not compiled (rebuild with --features gpu)".to_string())
+    }
+}
+
+// In the format NVML accepts (domain:bus:device.function)
+#[cfg(feature = "gpu")]
+fn pci_bus_id(device: Device) -> CudaResult<String> {
+    use rustacuda::device::DeviceAttribute;
+    Ok(format!(
+        "{:04x}:{:02x}:{:02x}.0",
+        device.get_attribute(DeviceAttribute::PciDomainId)?,
+        device.get_attribute(DeviceAttribute::PciBusId)?,
+        device.get_attribute(DeviceAttribute::PciDeviceId)?,
+    ))
+}
+
+// Bytes per hit record in the scoring kernels' output: index and chars (u32 little endian
+// each) followed by the entropy
+#[cfg(feature = "gpu")]
+const HIT_RECORD_SIZE: usize = 24;
+
+// 256 threads per block
+#[cfg(feature = "gpu")]
+fn launch_dims(size: usize) -> ((u32, u32, u32), (u32, u32, u32)) {
+    (((size as u32).div_ceil(256), 1, 1), (256, 1, 1))
+}
+
+// Device buffers of the scoring kernels, with page-locked host memory for the counters
+#[cfg(feature = "gpu")]
+struct ScoringBuffers {
+    hits: DeviceBuffer<u8>,
+    hit_count: DeviceBuffer<u32>,
+    histogram: DeviceBuffer<u32>,
+    best: DeviceBuffer<u64>,
+    hit_count_host: LockedBuffer<u32>,
+    histogram_host: LockedBuffer<u32>,
+    best_host: LockedBuffer<u64>,
+}
+
+#[cfg(feature = "gpu")]
+impl ScoringBuffers {
+    fn new(capacity: usize) -> Result<Self, String> {
+        let alloc = |e: rustacuda::error::CudaError| format!("Failed to allocate scoring buffers: {}", e);
+        Ok(Self {
+            // Room for every candidate, so compaction never overflows
+            hits: DeviceBuffer::from_slice(&vec![0u8; capacity * HIT_RECORD_SIZE]).map_err(alloc)?,
+            hit_count: DeviceBuffer::from_slice(&[0u32]).map_err(alloc)?,
+            histogram: DeviceBuffer::from_slice(&[0u32; MAX_SCORE + 1]).map_err(alloc)?,
+            best: DeviceBuffer::from_slice(&[u64::MAX]).map_err(alloc)?,
+            hit_count_host: LockedBuffer::new(&0u32, 1).map_err(alloc)?,
+            histogram_host: LockedBuffer::new(&0u32, MAX_SCORE + 1).map_err(alloc)?,
+            best_host: LockedBuffer::new(&u64::MAX, 1).map_err(alloc)?,
+        })
+    }
+
+    /// Queue the reset of the counters on `stream` (before a launch)
+    ///
+    /// Safety: the host buffers must not be touched until `stream` is synchronized
+    unsafe fn reset(&mut self, stream: &Stream) -> CudaResult<()> {
+        self.hit_count_host[0] = 0;
+        self.histogram_host.iter_mut().for_each(|count| *count = 0);
+        self.best_host[0] = u64::MAX;
+        self.hit_count.async_copy_from(&self.hit_count_host, stream)?;
+        self.histogram.async_copy_from(&self.histogram_host, stream)?;
+        self.best.async_copy_from(&self.best_host, stream)
+    }
+
+    /// Queue the copy of the counters to the host on `stream` (after a launch)
+    ///
+    /// Safety: as for `reset`
+    unsafe fn fetch(&mut self, stream: &Stream) -> CudaResult<()> {
+        self.hit_count.async_copy_to(&mut self.hit_count_host, stream)?;
+        self.histogram.async_copy_to(&mut self.histogram_host, stream)?;
+        self.best.async_copy_to(&mut self.best_host, stream)
+    }
+
+    /// Results of a finished launch (stream synchronized): only the hits are copied now, the
+    /// histogram and the best candidate came with `fetch` (its entropy is read with
+    /// `best_entropy`)
+    fn read(
+        &self,
+        size: usize,
+        best_entropy: impl FnOnce(usize) -> Result<[u8; 16], String>,
+    ) -> Result<ScoredBatch, String> {
+        let copy = |e: rustacuda::error::CudaError| format!("Failed to copy scores from GPU: {}", e);
+        let count = (self.hit_count_host[0] as usize).min(size);
+        let mut records = Zeroizing::new(vec![0u8; count * HIT_RECORD_SIZE]);
+        if count > 0 {
+            self.hits[..count * HIT_RECORD_SIZE].copy_to(&mut records[..]).map_err(copy)?;
+        }
+        let mut hits = Zeroizing::new(Vec::with_capacity(count));
+        for record in records.chunks_exact(HIT_RECORD_SIZE) {
+            let mut entropy = [0u8; 16];
+            entropy.copy_from_slice(&record[8..]);
+            hits.push(Hit {
+                index: u32::from_le_bytes([record[0], record[1], record[2], record[3]]),
+                chars: u32::from_le_bytes([record[4], record[5], record[6], record[7]]) as usize,
+                entropy,
+            });
+        }
+        // Slots are claimed in whatever order threads finish
+        hits.sort_unstable_by_key(|hit| hit.index);
+
+        let mut histogram = [0u64; MAX_SCORE + 1];
+        for (total, &count) in histogram.iter_mut().zip(self.histogram_host.iter()) {
+            *total = count as u64;
+        }
+        let best = match self.best_host[0] {
+            u64::MAX => None,
+            packed => {
+                let index = (packed & 0xffff_ffff) as usize;
+                Some(Zeroizing::new(Hit {
+                    index: index as u32,
+                    chars: (packed >> 32) as usize,
+                    entropy: best_entropy(index)?,
+                }))
+            }
+        };
+
+        Ok(ScoredBatch {
+            candidates: size,
+            hits,
+            histogram,
+            best,
+        })
+    }
+}
+
+// One of the two slots of a CUDA session: its own stream, cuRAND states and buffers
+#[cfg(feature = "gpu")]
+struct Slot {
+    stream: Stream,
+    states: DeviceBuffer<u8>,
+    entropy: DeviceBuffer<u8>,
+    scoring: ScoringBuffers,
+    // Page-locked host copy of the entropy (Job::Entropy), wiped after every batch
+    entropy_host: LockedBuffer<u8>,
+    launched: Option<(Job, usize)>,
+}
+
+#[cfg(feature = "gpu")]
+impl Slot {
+    fn new(module: &CudaModule, rng: GpuRng, capacity: usize) -> Result<Self, String> {
+        let stream = Stream::new(StreamFlags::NON_BLOCKING, None)
+            .map_err(|e| format!("Failed to create CUDA stream: {}", e))?;
+        let mut states = DeviceBuffer::from_slice(&vec![0u8; capacity * rng.state_size()])
+            .map_err(|e| format!("Failed to allocate cuRAND states: {}", e))?;
+        let entropy = DeviceBuffer::from_slice(&vec![0u8; capacity * 16])
+            .map_err(|e| format!("Failed to allocate entropy buffer: {}", e))?;
+        let scoring = ScoringBuffers::new(capacity)?;
+        let entropy_host = LockedBuffer::new(&0u8, capacity * 16)
+            .map_err(|e| format!("Failed to allocate page-locked memory: {}", e))?;
+
+        // Seeded once from the OS CSPRNG: independent of the clock and of every other slot
+        let (grid_dim, block_size) = launch_dims(capacity);
+        let init_kernel = &module.module_and_kernels.generator(rng).init;
+        let mut os_rng = rand::rngs::OsRng;
+        unsafe {
+            match rng {
+                GpuRng::Xorwow => launch!(init_kernel<<<grid_dim, block_size, 0, stream>>>(
+                    states.as_device_ptr(),
+                    os_rng.next_u64(),
+                    capacity as i32
+                )),
+                GpuRng::Philox => launch!(init_kernel<<<grid_dim, block_size, 0, stream>>>(
+                    states.as_device_ptr(),
+                    os_rng.next_u64(),
+                    os_rng.next_u64(),
+                    capacity as i32
+                )),
+            }
+            .map_err(|e| format!("Failed to launch init kernel: {}", e))?;
+        }
+        stream.synchronize()
+            .map_err(|e| format!("Failed to synchronize CUDA stream: {}", e))?;
+
+        Ok(Self {
+            stream,
+            states,
+            entropy,
+            scoring,
+            entropy_host,
+            launched: None,
+        })
+    }
+}
+
+// Session backend on a CUDA device
+#[cfg(feature = "gpu")]
+struct CudaBackend {
+    // Dropped before the module and its context
+    slots: Vec<Slot>,
+    module: CudaModule,
+    rng: GpuRng,
+}
+
+#[cfg(feature = "gpu")]
+impl CudaBackend {
+    fn new(device: u32, rng: GpuRng, capacity: usize, reporter: &Reporter) -> Result<Self, String> {
+        let module = CudaModule::load(device, reporter)?;
+        let slots = vec![Slot::new(&module, rng, capacity)?, Slot::new(&module, rng, capacity)?];
+        reporter.notice(&format!(
+            "[GPU {}] Session buffers allocated for {} candidates (2 slots, {})",
+            device,
+            capacity,
+            rng.name()
+        ));
+        Ok(Self { slots, module, rng })
+    }
+}
+
+#[cfg(feature = "gpu")]
+impl SessionBackend for CudaBackend {
+    fn launch(&mut self, slot: usize, job: Job, size: usize) -> Result<(), String> {
+        let kernels = self.module.module_and_kernels.generator(self.rng);
+        let word_lengths = &mut self.module.word_lengths;
+        let slot = &mut self.slots[slot];
+        if slot.launched.take().is_some() {
+            // A batch nobody collected (after an error): let it finish before reusing the buffers
+            slot.stream.synchronize()
+                .map_err(|e| format!("Failed to synchronize CUDA stream: {}", e))?;
+        }
+        let (grid_dim, block_size) = launch_dims(size);
+        let stream = &slot.stream;
+
+        // The host buffers are only read in `collect`, after the stream is synchronized
+        unsafe {
+            match job {
+                Job::Entropy => {
+                    let gen_kernel = &kernels.generate;
+                    launch!(gen_kernel<<<grid_dim, block_size, 0, stream>>>(
+                        slot.states.as_device_ptr(),
+                        slot.entropy.as_device_ptr(),
+                        size as i32
+                    ))
+                    .map_err(|e| format!("Failed to launch generation kernel: {}", e))?;
+                    slot.entropy[..size * 16]
+                        .async_copy_to(&mut slot.entropy_host[..size * 16], stream)
+                        .map_err(|e| format!("Failed to copy results from GPU: {}", e))?;
+                }
+                Job::Scored { threshold } => {
+                    slot.scoring.reset(stream)
+                        .map_err(|e| format!("Failed to reset scoring buffers: {}", e))?;
+                    let score_kernel = &kernels.score;
+                    launch!(score_kernel<<<grid_dim, block_size, 0, stream>>>(
+                        slot.states.as_device_ptr(),
+                        word_lengths.as_device_ptr(),
+                        threshold as i32,
+                        slot.entropy.as_device_ptr(),
+                        slot.scoring.hits.as_device_ptr(),
+                        slot.scoring.hit_count.as_device_ptr(),
+                        slot.scoring.histogram.as_device_ptr(),
+                        slot.scoring.best.as_device_ptr(),
+                        size as i32
+                    ))
+                    .map_err(|e| format!("Failed to launch scoring kernel: {}", e))?;
+                    slot.scoring.fetch(stream)
+                        .map_err(|e| format!("Failed to copy scores from GPU: {}", e))?;
+                }
+            }
+        }
+        slot.launched = Some((job, size));
+        Ok(())
+    }
+
+    fn collect(&mut self, slot: usize) -> Result<GpuBatch, String> {
+        let slot = &mut self.slots[slot];
+        let (job, size) = slot.launched.take()
+            .ok_or_else(|| "Slot collected without a launch".to_string())?;
+        slot.stream.synchronize()
+            .map_err(|e| format!("Failed to synchronize CUDA stream: {}", e))?;
+
+        match job {
+            Job::Entropy => {
+                let mut batch = Zeroizing::new(Vec::with_capacity(size));
+                for chunk in slot.entropy_host[..size * 16].chunks_exact(16) {
+                    let mut entropy = [0u8; 16];
+                    entropy.copy_from_slice(chunk);
+                    batch.push(entropy);
+                }
+                slot.entropy_host[..size * 16].zeroize();
+                Ok(GpuBatch::Entropy(batch))
+            }
+            Job::Scored { .. } => {
+                // Candidates stay in device memory; only the best one's entropy is read back
+                let entropy = &slot.entropy;
+                let scored = slot.scoring.read(size, |index| {
+                    let mut best = [0u8; 16];
+                    entropy[index * 16..(index + 1) * 16]
+                        .copy_to(&mut best[..])
+                        .map_err(|e| format!("Failed to copy best candidate from GPU: {}", e))?;
+                    Ok(best)
+                })?;
+                Ok(GpuBatch::Scored(Box::new(scored)))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gpu")]
+impl Drop for CudaBackend {
+    fn drop(&mut self) {
+        // Asynchronous copies may still target the page-locked buffers
+        for slot in &self.slots {
+            let _ = slot.stream.synchronize();
+        }
+        for slot in &mut self.slots {
+            slot.entropy_host.zeroize();
+        }
+    }
 }
 
 pub struct GpuContext {
+    // CUDA device index (`--devices`)
+    device: u32,
     available: bool,
+    // --simulate-gpu: sessions use the simulated backend, with this delay per batch
+    simulated: Option<Duration>,
+    rng: GpuRng,
+    // Largest batch a worker asks for; every session is sized for it
+    max_batch: usize,
     // Note: CUDA Context and Stream are not Send/Sync
-    // Each thread that needs GPU will create its own context
-    // We only store availability flag here
+    // The thread that uses the context creates its own session on the device
+    counters: Arc<SessionCounters>,
+    // Session setup notices of CUDA devices
+    #[cfg(feature = "gpu")]
+    reporter: Arc<Reporter>,
 }
 
 impl GpuContext {
-    pub fn new() -> Self {
+    pub fn new(device: u32, max_batch: usize, rng: GpuRng, reporter: Arc<Reporter>) -> Self {
         #[cfg(feature = "gpu")]
         {
             // Try to initialize CUDA
-            match Self::init_cuda() {
+            match Self::init_cuda(device) {
                 Ok(_) => {
-                    println!("GPU initialized successfully (CUDA)");
+                    reporter.notice(&format!("GPU {} initialized successfully (CUDA)", device));
                     Self {
+                        device,
                         available: true,
+                        simulated: None,
+                        rng,
+                        max_batch,
+                        counters: Arc::default(),
+                        reporter,
                     }
                 }
                 Err(e) => {
-                    eprintln!("GPU initialization failed: {}. Falling back to CPU.", e);
+                    reporter.notice(&format!("GPU {} initialization failed: {}. Falling back to CPU.", device, e));
                     Self {
+                        device,
                         available: false,
+                        simulated: None,
+                        rng,
+                        max_batch,
+                        counters: Arc::default(),
+                        reporter,
                     }
                 }
             }
         }
-        
+
         #[cfg(not(feature = "gpu"))]
         {
+            reporter.notice(&format!("GPU {} unavailable: GPU support not compiled (rebuild with --features gpu)", device));
             Self {
+                device,
                 available: false,
+                simulated: None,
+                rng,
+                max_batch,
+                counters: Arc::default(),
             }
         }
     }
-    
+
+    /// A context whose sessions run the simulated backend (`--simulate-gpu`)
+    pub fn simulated(device: u32, max_batch: usize, rng: GpuRng, delay: Duration, reporter: Arc<Reporter>) -> Self {
+        reporter.notice(&format!("GPU {} simulated (batches are computed on the CPU)", device));
+        Self {
+            device,
+            available: true,
+            simulated: Some(delay),
+            rng,
+            max_batch,
+            counters: Arc::default(),
+            #[cfg(feature = "gpu")]
+            reporter,
+        }
+    }
+
     #[cfg(feature = "gpu")]
-    fn init_cuda() -> Result<(), rustacuda::error::CudaError> {
+    fn init_cuda(device: u32) -> Result<(), String> {
         // Initialize CUDA - just verify it works
-        rustacuda::init(CudaFlags::empty())?;
-        
-        // Get the first device to verify CUDA is available
-        let _device = Device::get_device(0)?;
-        
+        rustacuda::init(CudaFlags::empty()).map_err(|e| e.to_string())?;
+
+        // Get the device to verify it is available, and check a kernel image fits it
+        let device = Device::get_device(device).map_err(|e| e.to_string())?;
+        let capability = compute_capability(device).map_err(|e| e.to_string())?;
+        if kernel_ptx::KERNEL_IMAGES.is_empty() {
+            return Err("the CUDA kernels were not compiled (nvcc was not found at build time)".to_string());
+        }
+        if compatible_images(capability).is_empty() {
+            let built: Vec<String> = kernel_ptx::KERNEL_IMAGES
+                .iter()
+                .map(|&(arch, _)| capability_name(arch))
+                .collect();
+            return Err(format!(
+                "no kernel image for compute capability {} (built for {}; rebuild with GPUSEED_CUDA_ARCHS)",
+                capability_name(capability),
+                built.join(", ")
+            ));
+        }
+
         // Don't create context here - each thread will create its own
         Ok(())
     }
-    
+
+    pub fn device(&self) -> u32 {
+        self.device
+    }
+
     pub fn is_available(&self) -> bool {
         self.available
     }
-    
-    #[cfg(feature = "gpu")]
+
+    /// Batch counts of all sessions, updated while workers run
+    pub fn counters(&self) -> Arc<SessionCounters> {
+        self.counters.clone()
+    }
+
     pub fn cleanup(&self) {
-        // Cleanup thread-local GPU contexts
-        // This forces destruction of CUDA contexts when shutting down
-        GPU_THREAD_CONTEXT.with(|ctx_cell| {
-            let mut ctx_opt = ctx_cell.borrow_mut();
-            if ctx_opt.is_some() {
-                // Drop the context explicitly
-                *ctx_opt = None;
-            }
+        // Drop this thread's session: waits for its batch in flight, then frees its buffers
+        // and destroys its CUDA context
+        GPU_SESSION.with(|session| {
+            session.borrow_mut().take();
         });
     }
-    
-    #[cfg(not(feature = "gpu"))]
-    pub fn cleanup(&self) {
-        // No-op when GPU is not enabled
+
+    /// Next batch of `job` from this thread's session, created on first use
+    pub fn next_batch(&self, job: Job, size: usize) -> Result<GpuBatch, String> {
+        if !self.available {
+            return Err("GPU not available".to_string());
+        }
+        GPU_SESSION.with(|session| {
+            let mut session = session.borrow_mut();
+            if session.is_none() {
+                *session = Some(GpuSession::new(self.backend()?, self.max_batch, self.counters.clone()));
+            }
+            session.as_mut().unwrap().next(job, size)
+        })
+    }
+
+    fn backend(&self) -> Result<Box<dyn SessionBackend>, String> {
+        if let Some(delay) = self.simulated {
+            return Ok(Box::new(SimulatedBackend::new(self.rng, self.max_batch, delay)));
+        }
+        #[cfg(feature = "gpu")]
+        {
+            Ok(Box::new(CudaBackend::new(self.device, self.rng, self.max_batch, &self.reporter)?))
+        }
+        #[cfg(not(feature = "gpu"))]
+        {
+            Err("GPU support not compiled. Rebuild with --features gpu".to_string())
+        }
+    }
+
+    /// Score candidates given by the host with the GPU kernel (`gpu-check`)
+    #[cfg(feature = "gpu")]
+    pub fn score_entropies(&self, entropies: &[[u8; 16]], threshold: usize) -> Result<ScoredBatch, String> {
+        if !self.available || self.simulated.is_some() {
+            return Err("GPU not available".to_string());
+        }
+        let size = entropies.len();
+        let mut module = CudaModule::load(self.device, &self.reporter)?;
+        let mut input = DeviceBuffer::from_slice(entropies.as_flattened())
+            .map_err(|e| format!("Failed to upload candidates: {}", e))?;
+        let mut buffers = ScoringBuffers::new(size)?;
+        let stream = Stream::new(StreamFlags::NON_BLOCKING, None)
+            .map_err(|e| format!("Failed to create CUDA stream: {}", e))?;
+        let (grid_dim, block_size) = launch_dims(size);
+        let score_kernel = &module.module_and_kernels.score_entropies_kernel;
+
+        unsafe {
+            buffers.reset(&stream)
+                .map_err(|e| format!("Failed to reset scoring buffers: {}", e))?;
+            launch!(score_kernel<<<grid_dim, block_size, 0, stream>>>(
+                input.as_device_ptr(),
+                module.word_lengths.as_device_ptr(),
+                threshold as i32,
+                buffers.hits.as_device_ptr(),
+                buffers.hit_count.as_device_ptr(),
+                buffers.histogram.as_device_ptr(),
+                buffers.best.as_device_ptr(),
+                size as i32
+            ))
+            .map_err(|e| format!("Failed to launch scoring kernel: {}", e))?;
+            buffers.fetch(&stream)
+                .map_err(|e| format!("Failed to copy scores from GPU: {}", e))?;
+        }
+        stream.synchronize()
+            .map_err(|e| format!("Failed to synchronize CUDA stream: {}", e))?;
+
+        buffers.read(size, |index| Ok(entropies[index]))
     }
-    
+
+    /// First candidate of `count` fresh Philox streams (`gpu-check` compares them with the
+    /// host implementation)
     #[cfg(feature = "gpu")]
-    fn init_thread_context() -> Result<ThreadGpuContext, String> {
+    pub fn philox_entropies(&self, count: usize, seed: u64, subsequence: u64) -> Result<EntropyBatch, String> {
+        if !self.available || self.simulated.is_some() {
+            return Err("GPU not available".to_string());
+        }
+        let module = CudaModule::load(self.device, &self.reporter)?;
+        let mut states = DeviceBuffer::from_slice(&vec![0u8; count * GpuRng::Philox.state_size()])
+            .map_err(|e| format!("Failed to allocate cuRAND states: {}", e))?;
+        let mut output = DeviceBuffer::from_slice(&vec![0u8; count * 16])
+            .map_err(|e| format!("Failed to allocate entropy buffer: {}", e))?;
+        let stream = Stream::new(StreamFlags::NON_BLOCKING, None)
+            .map_err(|e| format!("Failed to create CUDA stream: {}", e))?;
+        let (grid_dim, block_size) = launch_dims(count);
+        let kernels = module.module_and_kernels.generator(GpuRng::Philox);
+        let (init_kernel, gen_kernel) = (&kernels.init, &kernels.generate);
+
+        unsafe {
+            launch!(init_kernel<<<grid_dim, block_size, 0, stream>>>(
+                states.as_device_ptr(),
+                seed,
+                subsequence,
+                count as i32
+            ))
+            .map_err(|e| format!("Failed to launch init kernel: {}", e))?;
+            launch!(gen_kernel<<<grid_dim, block_size, 0, stream>>>(
+                states.as_device_ptr(),
+                output.as_device_ptr(),
+                count as i32
+            ))
+            .map_err(|e| format!("Failed to launch generation kernel: {}", e))?;
+        }
+        stream.synchronize()
+            .map_err(|e| format!("Failed to synchronize CUDA stream: {}", e))?;
+
+        let mut bytes = Zeroizing::new(vec![0u8; count * 16]);
+        output.copy_to(&mut bytes[..])
+            .map_err(|e| format!("Failed to copy results from GPU: {}", e))?;
+        Ok(Zeroizing::new(bytes.chunks_exact(16).map(|chunk| chunk.try_into().unwrap()).collect()))
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    pub fn philox_entropies(&self, _count: usize, _seed: u64, _subsequence: u64) -> Result<EntropyBatch, String> {
+        Err("GPU support not compiled. Rebuild with --features gpu".to_string())
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    pub fn score_entropies(&self, _entropies: &[[u8; 16]], _threshold: usize) -> Result<ScoredBatch, String> {
+        Err("GPU support not compiled. Rebuild with --features gpu".to_string())
+    }
+}
+
+#[cfg(feature = "gpu")]
+impl CudaModule {
+    fn load(index: u32, reporter: &Reporter) -> Result<Self, String> {
         // Initialize CUDA if not already initialized (ignore error if already initialized)
         let _ = rustacuda::init(CudaFlags::empty());
-        
-        let device = Device::get_device(0)
-            .map_err(|e| format!("Failed to get device: {}", e))?;
-        
-        // Create CUDA context (only once per thread)
-        static INIT_LOGGED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
-        let is_first_init = !INIT_LOGGED.swap(true, std::sync::atomic::Ordering::Relaxed);
-        
-        if is_first_init {
-            eprintln!("[GPU] Initializing thread-local CUDA context...");
-        }
-        
+
+        let device = Device::get_device(index)
+            .map_err(|e| format!("Failed to get device {}: {}", index, e))?;
+
+        // Create the CUDA context of this thread on the device
+        reporter.notice(&format!("[GPU {}] Initializing thread-local CUDA context...", index));
         let context = Context::create_and_push(
             ContextFlags::MAP_HOST | ContextFlags::SCHED_AUTO,
             device
-        ).map_err(|e| {
-            eprintln!("[GPU] ERROR: Failed to create context: {}", e);
-            format!("Failed to create context: {}", e)
-        })?;
-        
-        if is_first_init {
-            eprintln!("[GPU] CUDA context created successfully");
-        }
-        
-        // Load compiled CUDA kernel PTX (embedded at compile time)
-        let ptx_content = kernel_ptx::KERNEL_PTX;
-        
-        if ptx_content.is_empty() {
-            eprintln!("[GPU] ERROR: CUDA kernel PTX is empty. Kernel was not compiled during build.");
-            eprintln!("[GPU] Rebuild with: cargo build --release --features gpu");
-            return Err("CUDA kernel PTX not available. Make sure CUDA Toolkit is installed and kernel was compiled successfully.".to_string());
-        }
-        
-        let ptx = CString::new(ptx_content)
-            .map_err(|e| format!("Invalid PTX string: {}", e))?;
-        
-        // Load module from PTX
-        let module = Module::load_from_string(ptx.as_c_str())
-            .map_err(|e| {
-                eprintln!("[GPU] ERROR: Failed to load CUDA module: {}", e);
-                format!("Failed to load CUDA module: {}", e)
-            })?;
-        
+        ).map_err(|e| format!("Failed to create context: {}", e))?;
+        reporter.notice(&format!("[GPU {}] CUDA context created successfully", index));
+
+        // Load compiled CUDA kernel PTX (embedded at compile time): the best image for the
+        // device, or an older one if the driver rejects it (e.g. a PTX version newer than the
+        // driver)
+        let capability = compute_capability(device)
+            .map_err(|e| format!("Failed to get compute capability: {}", e))?;
+        let images = compatible_images(capability);
+        if images.is_empty() {
+            return Err(format!(
+                "No CUDA kernel PTX for compute capability {}. Make sure CUDA Toolkit is installed and \
+                rebuild with: cargo build --release --features gpu",
+                capability_name(capability)
+            ));
+        }
+
+        let mut loaded = None;
+        let mut last_error = String::new();
+        for (arch, ptx_content) in images {
+            let ptx = CString::new(ptx_content)
+                .map_err(|e| format!("Invalid PTX string: {}", e))?;
+            match Module::load_from_string(ptx.as_c_str()) {
+                Ok(module) => {
+                    reporter.notice(&format!(
+                        "[GPU {}] Loaded kernels for compute capability {} (PTX compute_{})",
+                        index,
+                        capability_name(capability),
+                        arch
+                    ));
+                    loaded = Some(module);
+                    break;
+                }
+                Err(e) => {
+                    reporter.notice(&format!("[GPU {}] WARNING: Failed to load PTX compute_{}: {}", index, arch, e));
+                    last_error = e.to_string();
+                }
+            }
+        }
+        let module = loaded.ok_or_else(|| format!("Failed to load CUDA module: {}", last_error))?;
+
         // Get kernel functions
-        let init_func_name = CString::new("init_curand_states")
-            .map_err(|e| format!("Invalid function name: {}", e))?;
-        let gen_func_name = CString::new("generate_random_bytes")
-            .map_err(|e| format!("Invalid function name: {}", e))?;
-        
-        let init_kernel = module.get_function(init_func_name.as_c_str())
-            .map_err(|e| {
-                eprintln!("[GPU] ERROR: Failed to get init_curand_states function: {}", e);
-                format!("Failed to get init_curand_states function: {}", e)
-            })?;
-        let gen_kernel = module.get_function(gen_func_name.as_c_str())
-            .map_err(|e| {
-                eprintln!("[GPU] ERROR: Failed to get generate_random_bytes function: {}", e);
-                format!("Failed to get generate_random_bytes function: {}", e)
-            })?;
-        
         // SAFETY: We need to transmute the Functions to 'static before moving the module
         // This is safe because we ensure the module lives as long as the functions
         // by storing them together in the same struct
-        let init_kernel_static: Function<'static> = unsafe { std::mem::transmute(init_kernel) };
-        let gen_kernel_static: Function<'static> = unsafe { std::mem::transmute(gen_kernel) };
-        
+        let kernel = |name: &str| -> Result<Function<'static>, String> {
+            let c_name = CString::new(name)
+                .map_err(|e| format!("Invalid function name: {}", e))?;
+            let function = module.get_function(c_name.as_c_str())
+                .map_err(|e| format!("Failed to get {} function: {}", name, e))?;
+            Ok(unsafe { std::mem::transmute::<Function<'_>, Function<'static>>(function) })
+        };
+        let xorwow = GeneratorKernels {
+            init: kernel("init_curand_states")?,
+            generate: kernel("generate_random_bytes")?,
+            score: kernel("score_random")?,
+        };
+        let philox = GeneratorKernels {
+            init: kernel("init_philox_states")?,
+            generate: kernel("generate_random_bytes_philox")?,
+            score: kernel("score_random_philox")?,
+        };
+        let score_entropies_kernel = kernel("score_entropies")?;
+
         // Now we can move the module since we've already transmuted the functions
         let module_and_kernels = Box::new(ModuleAndKernels {
             _module: module,
-            init_kernel: init_kernel_static,
-            gen_kernel: gen_kernel_static,
+            xorwow,
+            philox,
+            score_entropies_kernel,
         });
-        
-        Ok(ThreadGpuContext {
-            _context: context,
+
+        let word_lengths = DeviceBuffer::from_slice(&scoring::word_lengths())
+            .map_err(|e| format!("Failed to upload word lengths: {}", e))?;
+
+        Ok(CudaModule {
+            word_lengths,
             module_and_kernels,
+            _context: context,
         })
     }
-    
-    #[cfg(feature = "gpu")]
-    pub fn generate_entropy_batch(&self, size: usize) -> Result<Vec<[u8; 16]>, String> {
-        if !self.available {
-            return Err("GPU not available".to_string());
-        }
-        
-        // Get or initialize thread-local GPU context
-        GPU_THREAD_CONTEXT.with(|ctx_cell| {
-            let mut ctx_opt = ctx_cell.borrow_mut();
-            
-            // Initialize if not already initialized
-            if ctx_opt.is_none() {
-                *ctx_opt = Some(Self::init_thread_context()?);
-            }
-            
-            let ctx = ctx_opt.as_ref().unwrap();
-            
-            // Allocate memory for cuRAND states (48 bytes per state)
-            // cuRAND state size is typically 48 bytes
-            const CURAND_STATE_SIZE: usize = 48;
-            let states_size = size * CURAND_STATE_SIZE;
-            let mut curand_states = DeviceBuffer::from_slice(&vec![0u8; states_size])
-                .map_err(|e| format!("Failed to allocate cuRAND states: {}", e))?;
-            
-            // Initialize cuRAND states with random seed
-            let seed = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_nanos() as u64;
-            
-            // Create a CUDA stream for kernel execution
-            let stream = Stream::new(StreamFlags::NON_BLOCKING, None)
-                .map_err(|e| format!("Failed to create CUDA stream: {}", e))?;
-            
-            let grid_size = (size as u32 + 255) / 256; // 256 threads per block
-            let block_size = (256u32, 1u32, 1u32);
-            let grid_dim = (grid_size, 1u32, 1u32);
-            let shared_mem_bytes = 0u32;
-            
-            // Get references to kernels for use in launch! macro
-            let init_kernel = &ctx.module_and_kernels.init_kernel;
-            let gen_kernel = &ctx.module_and_kernels.gen_kernel;
-            
-            // Launch init kernel using the launch! macro (rustacuda 0.1 API)
-            static KERNEL_LAUNCH_LOGGED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
-            let is_first_launch = !KERNEL_LAUNCH_LOGGED.swap(true, std::sync::atomic::Ordering::Relaxed);
-            
-            if is_first_launch {
-                eprintln!("[GPU] Launching init kernel (grid: {:?}, block: {:?})", grid_dim, block_size);
-            }
-            
-            unsafe {
-                launch!(init_kernel<<<grid_dim, block_size, shared_mem_bytes, stream>>>(
-                    curand_states.as_device_ptr(),
-                    seed,
-                    size as i32
-                ))
-                    .map_err(|e| {
-                        eprintln!("[GPU] ERROR: Failed to launch init kernel: {}", e);
-                        format!("Failed to launch init kernel: {}", e)
-                    })?;
-            }
-            
-            if is_first_launch {
-                eprintln!("[GPU] Init kernel launched successfully");
-            }
-            
-            // Allocate output buffer on GPU
-            let total_bytes = size * 16;
-            let mut output_buffer = DeviceBuffer::from_slice(&vec![0u8; total_bytes])
-                .map_err(|e| format!("Failed to allocate output buffer: {}", e))?;
-            
-            // Generate random bytes using cuRAND kernel
-            if is_first_launch {
-                eprintln!("[GPU] Launching generation kernel");
-            }
-            
-            unsafe {
-                launch!(gen_kernel<<<grid_dim, block_size, shared_mem_bytes, stream>>>(
-                    curand_states.as_device_ptr(),
-                    output_buffer.as_device_ptr(),
-                    size as i32
-                ))
-                    .map_err(|e| {
-                        eprintln!("[GPU] ERROR: Failed to launch generation kernel: {}", e);
-                        format!("Failed to launch generation kernel: {}", e)
-                    })?;
-            }
-            
-            if is_first_launch {
-                eprintln!("[GPU] Generation kernel launched successfully");
-            }
-            
-            // Synchronize stream to ensure kernels complete
-            if is_first_launch {
-                eprintln!("[GPU] Synchronizing CUDA stream...");
-            }
-            
-            stream.synchronize()
-                .map_err(|e| {
-                    eprintln!("[GPU] ERROR: Failed to synchronize CUDA stream: {}", e);
-                    format!("Failed to synchronize CUDA stream: {}", e)
-                })?;
-            
-            if is_first_launch {
-                eprintln!("[GPU] CUDA stream synchronized successfully");
-            }
-            
-            // Copy results back to CPU
-            if is_first_launch {
-                eprintln!("[GPU] Copying results from GPU to CPU...");
-            }
-            
-            let mut cpu_data = vec![0u8; total_bytes];
-            output_buffer.copy_to(&mut cpu_data)
-                .map_err(|e| {
-                    eprintln!("[GPU] ERROR: Failed to copy results from GPU: {}", e);
-                    format!("Failed to copy results from GPU: {}", e)
-                })?;
-            
-            if is_first_launch {
-                eprintln!("[GPU] Results copied successfully ({} bytes)", total_bytes);
-            }
-            
-            // Convert to Vec<[u8; 16]>
-            let mut result = Vec::with_capacity(size);
-            for i in 0..size {
-                let mut entropy = [0u8; 16];
-                entropy.copy_from_slice(&cpu_data[i * 16..(i + 1) * 16]);
-                result.push(entropy);
-            }
-            
-            Ok(result)
-        })
-    }
-    
-    #[cfg(not(feature = "gpu"))]
-    pub fn generate_entropy_batch(&self, _size: usize) -> Result<Vec<[u8; 16]>, String> {
-        Err("GPU support not compiled. Rebuild with --features gpu".to_string())
-    }
 }
 
 // IMPLEMENTATION NOTE:
 // This implementation uses compiled CUDA kernels with cuRAND to generate random numbers
 // directly on the GPU, providing true GPU acceleration.
+// With Job::Scored the GPU also computes checksum, word indices and length of every candidate
+// and only returns the ones under the threshold (CPU reference: scoring.rs).
 //
-// The kernel is compiled during build.rs and loaded at runtime.
-// cuRAND states are initialized once per batch and reused for generation.
+// The kernels (kernels/gpuseed.cu) are compiled to PTX by build.rs, one image per target
+// compute capability, and the best image for each device is loaded at runtime.
+// Each device has its own feeder thread, which keeps one session (session.rs): cuRAND states
+// are initialized once per slot from an OS-random seed, and batches alternate between the two
+// slots' streams.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_an_empty_device_list() {
+        let all: DeviceSelection = "all".parse().unwrap();
+        let list: DeviceSelection = "0,2".parse().unwrap();
+        assert!(all.resolve(&[]).is_empty());
+        assert!(all.missing(&[]).is_empty());
+        assert!(list.resolve(&[]).is_empty());
+        assert_eq!(list.missing(&[]), [0, 2]);
+
+        let available = list.simulated();
+        assert_eq!(list.resolve(&available[1..]).iter().map(|device| device.index).collect::<Vec<_>>(), [2]);
+        assert_eq!(list.missing(&available[1..]), [0]);
+    }
+}
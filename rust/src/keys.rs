@@ -0,0 +1,137 @@
+// BIP32 key helpers for found seeds
+// Fingerprints and account xpubs let a restored seed be verified on a hardware
+// wallet without ever exporting the phrase itself.
+use bip39::{Language, Mnemonic};
+use bitcoin::bip32::{ChildNumber, DerivationPath, Fingerprint, Xpriv, Xpub};
+use bitcoin::secp256k1::Secp256k1;
+use bitcoin::NetworkKind;
+use clap::ValueEnum;
+use zeroize::Zeroizing;
+
+/// Standard single-sig account derivation schemes
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Purpose {
+    /// Legacy P2PKH (m/44'/0'/account')
+    Bip44,
+    /// Nested SegWit P2SH-P2WPKH (m/49'/0'/account')
+    Bip49,
+    /// Native SegWit P2WPKH (m/84'/0'/account')
+    Bip84,
+    /// Taproot P2TR (m/86'/0'/account')
+    Bip86,
+}
+
+impl Purpose {
+    fn number(self) -> u32 {
+        match self {
+            Purpose::Bip44 => 44,
+            Purpose::Bip49 => 49,
+            Purpose::Bip84 => 84,
+            Purpose::Bip86 => 86,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Purpose::Bip44 => "BIP44",
+            Purpose::Bip49 => "BIP49",
+            Purpose::Bip84 => "BIP84",
+            Purpose::Bip86 => "BIP86",
+        }
+    }
+
+    /// Account path for Bitcoin mainnet
+    pub fn account_path(self, account: u32) -> Result<DerivationPath, String> {
+        let hardened = |index| {
+            ChildNumber::from_hardened_idx(index).map_err(|e| format!("Invalid path index: {}", e))
+        };
+        Ok(DerivationPath::from(vec![
+            hardened(self.number())?,
+            hardened(0)?,
+            hardened(account)?,
+        ]))
+    }
+
+    fn descriptor(self, key: &str) -> String {
+        match self {
+            Purpose::Bip44 => format!("pkh({}/<0;1>/*)", key),
+            Purpose::Bip49 => format!("sh(wpkh({}/<0;1>/*))", key),
+            Purpose::Bip84 => format!("wpkh({}/<0;1>/*)", key),
+            Purpose::Bip86 => format!("tr({}/<0;1>/*)", key),
+        }
+    }
+}
+
+/// What the post-processing stage derives for each found seed
+pub struct DeriveSettings {
+    pub passphrase: Zeroizing<String>,
+    pub purposes: Vec<Purpose>,
+    pub account: u32,
+}
+
+/// Public key material derived from a found seed
+#[derive(Clone)]
+pub struct SeedKeys {
+    pub fingerprint: Fingerprint,
+    pub accounts: Vec<AccountKey>,
+}
+
+#[derive(Clone)]
+pub struct AccountKey {
+    pub purpose: Purpose,
+    pub path: DerivationPath,
+    pub xpub: Xpub,
+}
+
+impl AccountKey {
+    /// Output descriptor with key origin, e.g. `wpkh([73c5da0a/84'/0'/0']xpub.../<0;1>/*)`
+    pub fn descriptor(&self, fingerprint: Fingerprint) -> String {
+        let key = format!("[{}/{}]{}", fingerprint, path_without_prefix(&self.path), self.xpub);
+        self.purpose.descriptor(&key)
+    }
+}
+
+/// BIP32 master key fingerprint of a 128-bit entropy (as shown by hardware wallets)
+pub fn master_fingerprint(entropy: &[u8; 16], passphrase: &str) -> Result<Fingerprint, String> {
+    let master = master_key(entropy, passphrase)?;
+    Ok(master.fingerprint(&Secp256k1::signing_only()))
+}
+
+/// BIP32 master private key derived from the BIP39 seed
+pub fn master_key(entropy: &[u8; 16], passphrase: &str) -> Result<Xpriv, String> {
+    let mnemonic = Mnemonic::from_entropy_in(Language::English, entropy)
+        .map_err(|e| format!("Invalid entropy: {}", e))?;
+    let seed = Zeroizing::new(mnemonic.to_seed(passphrase));
+    Xpriv::new_master(NetworkKind::Main, seed.as_slice())
+        .map_err(|e| format!("Failed to derive master key: {}", e))
+}
+
+/// Derive the master fingerprint and account xpubs for one seed
+pub fn derive_keys(entropy: &[u8; 16], settings: &DeriveSettings) -> Result<SeedKeys, String> {
+    let secp = Secp256k1::new();
+    let master = master_key(entropy, &settings.passphrase)?;
+
+    let mut accounts = Vec::with_capacity(settings.purposes.len());
+    for &purpose in &settings.purposes {
+        let path = purpose.account_path(settings.account)?;
+        let account = master
+            .derive_priv(&secp, &path)
+            .map_err(|e| format!("Failed to derive {}: {}", path, e))?;
+        accounts.push(AccountKey {
+            purpose,
+            path,
+            xpub: Xpub::from_priv(&secp, &account),
+        });
+    }
+
+    Ok(SeedKeys {
+        fingerprint: master.fingerprint(&secp),
+        accounts,
+    })
+}
+
+// Key origin paths are written without the leading "m/"
+fn path_without_prefix(path: &DerivationPath) -> String {
+    let full = path.to_string();
+    full.strip_prefix("m/").unwrap_or(&full).to_string()
+}
@@ -0,0 +1,306 @@
+// Run limits
+// Stop conditions for unattended runs (--duration, --deadline, --max-iterations,
+// --until-found). Workers check them and set the stop flag, so reaching a limit ends the
+// run through the same graceful shutdown and final save as Ctrl+C.
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Stop once enough seeds of a character count (or below) were collected,
+/// written "<=41:1", "<42:3" or "44:5"
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Goal {
+    comparison: Comparison,
+    chars: usize,
+    count: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparison {
+    Below,
+    AtMost,
+    Exactly,
+}
+
+impl Goal {
+    fn reached(&self, found: &HashMap<usize, usize>) -> bool {
+        let matching: usize = found
+            .iter()
+            .filter(|(&chars, _)| match self.comparison {
+                Comparison::Below => chars < self.chars,
+                Comparison::AtMost => chars <= self.chars,
+                Comparison::Exactly => chars == self.chars,
+            })
+            .map(|(_, &count)| count)
+            .sum();
+        matching >= self.count
+    }
+}
+
+impl FromStr for Goal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (bucket, count) = s
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid goal '{}' (expected e.g. \"<=41:1\")", s))?;
+        let (comparison, chars) = if let Some(chars) = bucket.strip_prefix("<=") {
+            (Comparison::AtMost, chars)
+        } else if let Some(chars) = bucket.strip_prefix('<') {
+            (Comparison::Below, chars)
+        } else {
+            (Comparison::Exactly, bucket.strip_prefix('=').unwrap_or(bucket))
+        };
+        let chars = chars
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid character count in goal '{}'", s))?;
+        let count = count
+            .trim()
+            .parse()
+            .ok()
+            .filter(|&count| count > 0)
+            .ok_or_else(|| format!("Invalid seed count in goal '{}'", s))?;
+        Ok(Self { comparison, chars, count })
+    }
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let comparison = match self.comparison {
+            Comparison::Below => "<",
+            Comparison::AtMost => "<=",
+            Comparison::Exactly => "",
+        };
+        write!(f, "{}{}:{}", comparison, self.chars, self.count)
+    }
+}
+
+/// Parse a duration like "8h", "90m" or "1h 30m"
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    humantime::parse_duration(s).map_err(|e| format!("Invalid duration '{}': {}", s, e))
+}
+
+/// Parse a local wall-clock deadline: "07:00" (next occurrence), "2026-10-19 07:00",
+/// or RFC 3339 with an offset ("2026-10-19T07:00:00+02:00")
+pub fn parse_deadline(s: &str) -> Result<DateTime<Local>, String> {
+    let s = s.trim();
+    if let Ok(deadline) = DateTime::parse_from_rfc3339(s) {
+        return Ok(deadline.with_timezone(&Local));
+    }
+    let invalid = || format!("Invalid deadline '{}' (expected HH:MM or YYYY-MM-DD HH:MM)", s);
+
+    for format in ["%H:%M", "%H:%M:%S"] {
+        if let Ok(time) = NaiveTime::parse_from_str(s, format) {
+            let now = Local::now();
+            let today = local(now.date_naive().and_time(time)).ok_or_else(invalid)?;
+            if today > now {
+                return Ok(today);
+            }
+            let tomorrow = now.date_naive().succ_opt().ok_or_else(invalid)?;
+            return local(tomorrow.and_time(time)).ok_or_else(invalid);
+        }
+    }
+    for format in ["%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(s, format) {
+            return local(datetime).ok_or_else(invalid);
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return local(date.and_time(NaiveTime::MIN)).ok_or_else(invalid);
+    }
+    Err(invalid())
+}
+
+// Earliest matching instant (wall-clock times skipped by a DST change have none)
+fn local(datetime: NaiveDateTime) -> Option<DateTime<Local>> {
+    Local.from_local_datetime(&datetime).earliest()
+}
+
+pub struct RunLimits {
+    start: Instant,
+    // End of the --duration and --deadline limits
+    duration_end: Mutex<Option<Instant>>,
+    deadline_end: Mutex<Option<Instant>>,
+    // u64::MAX: no limit (read for every candidate, so no lock)
+    max_iterations: AtomicU64,
+    goals: Mutex<Vec<Goal>>,
+    // Why the run was stopped (first limit reached wins)
+    reason: Mutex<Option<&'static str>>,
+}
+
+impl RunLimits {
+    pub fn new(
+        duration: Option<Duration>,
+        deadline: Option<DateTime<Local>>,
+        max_iterations: Option<u64>,
+        goals: Vec<Goal>,
+    ) -> Result<Self, String> {
+        let limits = Self {
+            start: Instant::now(),
+            duration_end: Mutex::new(None),
+            deadline_end: Mutex::new(None),
+            max_iterations: AtomicU64::new(u64::MAX),
+            goals: Mutex::new(goals),
+            reason: Mutex::new(None),
+        };
+        limits.set_duration(duration);
+        limits.set_deadline(deadline)?;
+        limits.set_max_iterations(max_iterations);
+        Ok(limits)
+    }
+
+    /// Total run time, counted from the start of the run
+    pub fn set_duration(&self, duration: Option<Duration>) {
+        *self.duration_end.lock().unwrap() = duration.map(|duration| self.start + duration);
+    }
+
+    pub fn set_deadline(&self, deadline: Option<DateTime<Local>>) -> Result<(), String> {
+        let end = match deadline {
+            Some(deadline) => {
+                let remaining = (deadline - Local::now())
+                    .to_std()
+                    .map_err(|_| format!("Deadline {} is in the past", deadline.format("%Y-%m-%d %H:%M:%S")))?;
+                Some(Instant::now() + remaining)
+            }
+            None => None,
+        };
+        *self.deadline_end.lock().unwrap() = end;
+        Ok(())
+    }
+
+    pub fn set_max_iterations(&self, max_iterations: Option<u64>) {
+        self.max_iterations.store(max_iterations.unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+
+    pub fn max_iterations(&self) -> Option<u64> {
+        Some(self.max_iterations.load(Ordering::Relaxed)).filter(|&max| max != u64::MAX)
+    }
+
+    pub fn set_goals(&self, goals: Vec<Goal>) {
+        *self.goals.lock().unwrap() = goals;
+    }
+
+    // Time limits as (end, kind)
+    fn time_limits(&self) -> Vec<(Instant, &'static str)> {
+        let duration = self.duration_end.lock().unwrap().map(|end| (end, "duration"));
+        let deadline = self.deadline_end.lock().unwrap().map(|end| (end, "deadline"));
+        duration.into_iter().chain(deadline).collect()
+    }
+
+    /// Human-readable list of the active limits
+    pub fn describe(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        for (end, kind) in self.time_limits() {
+            let remaining = end.saturating_duration_since(Instant::now());
+            let at = Local::now() + remaining;
+            out.push(format!(
+                "{} ends at {} (in {})",
+                kind,
+                at.format("%Y-%m-%d %H:%M:%S"),
+                humantime::format_duration(Duration::from_secs(remaining.as_secs()))
+            ));
+        }
+        if let Some(max) = self.max_iterations() {
+            out.push(format!("max {} iterations", max));
+        }
+        let goals = self.goals.lock().unwrap();
+        if !goals.is_empty() {
+            let goals: Vec<String> = goals.iter().map(Goal::to_string).collect();
+            out.push(format!("until found {}", goals.join(",")));
+        }
+        out
+    }
+
+    /// Stop the run if a time limit passed
+    pub fn check_time(&self, stop_flag: &AtomicBool) -> bool {
+        let now = Instant::now();
+        match self.time_limits().into_iter().find(|(end, _)| now >= *end) {
+            Some((_, kind)) => {
+                self.stop(kind, stop_flag);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop the run if any goal is reached (call with the collected counts locked)
+    pub fn check_goals(&self, found: &HashMap<usize, usize>, stop_flag: &AtomicBool) -> bool {
+        if self.goals.lock().unwrap().iter().any(|goal| goal.reached(found)) {
+            self.stop("until_found", stop_flag);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn stop(&self, reason: &'static str, stop_flag: &AtomicBool) {
+        let mut current = self.reason.lock().unwrap();
+        if current.is_none() && !stop_flag.load(Ordering::Relaxed) {
+            *current = Some(reason);
+        }
+        stop_flag.store(true, Ordering::Relaxed);
+    }
+
+    /// Limit that ended the run, if any ("duration", "deadline", "max_iterations", "until_found")
+    pub fn reason(&self) -> Option<&'static str> {
+        *self.reason.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+
+    #[test]
+    fn parses_goals() {
+        let found = HashMap::from([(40, 1), (42, 2)]);
+        for (text, shown, reached) in [
+            ("<=40:1", "<=40:1", true),
+            ("<=41:2", "<=41:2", false),
+            (" <43 : 3 ", "<43:3", true),
+            ("<40:1", "<40:1", false),
+            ("42:2", "42:2", true),
+            ("=42:3", "42:3", false),
+        ] {
+            let goal: Goal = text.parse().unwrap();
+            assert_eq!(goal.to_string(), shown);
+            assert_eq!(goal.reached(&found), reached, "{}", text);
+        }
+        for invalid in ["", "41", "<=:1", "<=x:1", "41:0", "41:-1", "41:y", "=<41:1"] {
+            assert!(invalid.parse::<Goal>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn parses_deadlines() {
+        let now = Local::now();
+        let next = parse_deadline("07:00").unwrap();
+        assert!(next > now && next - now <= chrono::Duration::days(1) + chrono::Duration::hours(1));
+        assert_eq!((next.hour(), next.minute(), next.second()), (7, 0, 0));
+        assert_eq!(parse_deadline("07:00:30").unwrap().second(), 30);
+
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let expected = local(date.and_hms_opt(7, 0, 0).unwrap()).unwrap();
+        for text in ["2026-10-19 07:00", "2026-10-19T07:00", "2026-10-19 07:00:00"] {
+            assert_eq!(parse_deadline(text).unwrap(), expected, "{}", text);
+        }
+        assert_eq!(
+            parse_deadline("2026-10-19").unwrap(),
+            local(date.and_time(NaiveTime::MIN)).unwrap()
+        );
+        assert_eq!(
+            parse_deadline("2026-10-19T07:00:00+02:00").unwrap(),
+            DateTime::parse_from_rfc3339("2026-10-19T05:00:00Z").unwrap()
+        );
+        for invalid in ["", "7", "25:00", "2026-13-01", "tomorrow"] {
+            assert!(parse_deadline(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
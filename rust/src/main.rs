@@ -2,27 +2,69 @@
 #[macro_use]
 extern crate rustacuda;
 
-use clap::Parser;
+use bitcoin::hex::DisplayHex;
+use clap::parser::ValueSource;
+use clap::{ArgGroup, CommandFactory, FromArgMatches, Parser, Subcommand};
 use std::collections::HashMap;
+use std::fmt::Write as _;
 use std::fs;
-use std::io::{self, Write};
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 
+mod bip85;
+mod cluster;
 mod config;
+#[cfg(unix)]
+mod control;
+mod dashboard;
+mod export;
+mod feeder;
 mod gpu;
+mod keys;
+mod limits;
+mod metrics;
+mod mixing;
 mod monitor;
+mod partition;
+mod philox;
+mod plate;
+mod reporter;
+mod schedule;
+mod results;
+mod scoring;
+mod secure;
+mod seedqr;
+mod session;
+mod shares;
+#[cfg(unix)]
+mod signals;
+mod slip39;
+mod stats;
+mod status_json;
+mod webhook;
 mod worker;
 
 use config::Config;
+use keys::{DeriveSettings, Purpose};
 use monitor::ResourceMonitor;
-use worker::WorkerPool;
+use reporter::{ConsoleMode, Reporter};
+use secure::SecretVec;
+use slip39::{Scheme, ShareScoring};
+use stats::RunStats;
+use worker::{CollectionRules, FoundSeed, WorkerPool};
+use zeroize::Zeroizing;
 
 #[derive(Parser, Debug)]
 #[command(name = "gpuseed-rust")]
 #[command(about = "High-performance BIP39 mnemonic generator with GPU support")]
+#[command(args_conflicts_with_subcommands = true)]
+#[command(group(ArgGroup::new("work_units").args(["coordinator", "partition"])))]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Character count threshold
     #[arg(long, default_value_t = 46)]
     threshold: usize,
@@ -43,30 +85,260 @@ struct Args {
     #[arg(long, default_value = "seeds_output.txt")]
     output: String,
 
+    /// Run summary (best candidate seen, score histogram) written at shutdown
+    #[arg(long, default_value = "run_summary.txt")]
+    summary: String,
+
     /// Reset GPU configuration
     #[arg(long)]
     reset_config: bool,
+
+    /// Run the GPU code path on a simulated device (batches computed on the CPU), without CUDA;
+    /// an optional delay per batch in milliseconds models a slow device
+    #[arg(long, value_name = "MS", num_args = 0..=1, default_missing_value = "0")]
+    simulate_gpu: Option<u64>,
+
+    /// GPUs to use: CUDA device indices (e.g. 0,1) or "all"; each gets its own feeder
+    #[arg(long, value_name = "LIST", default_value = "0")]
+    devices: gpu::DeviceSelection,
+
+    /// Batches each GPU feeder may queue ahead of the workers
+    #[arg(long, value_name = "N", default_value_t = 4)]
+    gpu_queue: usize,
+
+    /// Random number generator of the GPU kernels
+    #[arg(long, value_enum, default_value = "xorwow")]
+    gpu_rng: gpu::GpuRng,
+
+    /// Plain status line instead of the full-screen dashboard
+    #[arg(long)]
+    no_tui: bool,
+
+    /// Serve Prometheus metrics and /healthz on this address (e.g. 127.0.0.1:9184)
+    #[arg(long, value_name = "ADDR")]
+    metrics: Option<String>,
+
+    /// Write NDJSON status and event records to a file, FIFO or inherited file descriptor number
+    #[arg(long, value_name = "PATH|FD")]
+    status_json: Option<String>,
+
+    /// POST every collected seed to this HTTP endpoint (signed with --webhook-secret)
+    #[arg(long, value_name = "URL", requires = "webhook_secret")]
+    webhook: Option<String>,
+
+    /// File with the HMAC signing secret of the webhook (at least 16 characters)
+    #[arg(long, value_name = "FILE", requires = "webhook")]
+    webhook_secret: Option<String>,
+
+    /// What webhook records reveal about a seed
+    #[arg(long, value_enum, default_value_t = webhook::WebhookPayload::Redacted, requires = "webhook")]
+    webhook_payload: webhook::WebhookPayload,
+
+    /// Queue file for webhook records that were not delivered yet
+    #[arg(long, value_name = "PATH", default_value = "webhook_queue.ndjson", requires = "webhook")]
+    webhook_queue: String,
+
+    /// Coordinate a distributed search: hand out work units to `--join` nodes on this address
+    #[arg(
+        long,
+        value_name = "ADDR",
+        requires = "cluster_secret",
+        conflicts_with_all = ["join", "bip85", "user_entropy", "slip39_score"]
+    )]
+    coordinator: Option<String>,
+
+    /// Search work units of the coordinator at this address instead of random entropy
+    #[arg(
+        long,
+        value_name = "ADDR",
+        requires = "cluster_secret",
+        conflicts_with_all = ["bip85", "user_entropy", "slip39_score"]
+    )]
+    join: Option<String>,
+
+    /// Search only partition K of N of the stream seeded by --cluster-secret (no coordinator needed)
+    #[arg(
+        long,
+        value_name = "K/N",
+        requires = "cluster_secret",
+        conflicts_with_all = ["coordinator", "join", "bip85", "user_entropy"]
+    )]
+    partition: Option<partition::Partition>,
+
+    /// File with the shared secret of the cluster (same on every node, at least 16 characters)
+    #[arg(long, value_name = "FILE")]
+    cluster_secret: Option<String>,
+
+    /// Candidates per work unit (coordinator, or every run of a --partition search)
+    #[arg(long, default_value_t = 1_000_000, requires = "work_units", value_parser = clap::value_parser!(u64).range(1..))]
+    unit_size: u64,
+
+    /// Accept control commands (`ctl`) on this Unix socket (default: gpuseed.sock)
+    #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "gpuseed.sock")]
+    control_socket: Option<String>,
+
+    /// Seconds between status updates (status line and --status-json)
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+    status_interval: u64,
+
+    /// Lock found seeds in RAM so they are never swapped to disk (Linux only)
+    #[arg(long)]
+    mlock: bool,
+
+    /// How found seeds are shown on the console
+    #[arg(long, value_enum, default_value_t = ConsoleMode::Full)]
+    console: ConsoleMode,
+
+    /// Derive BIP32 master fingerprint and account xpubs for found seeds
+    #[arg(long)]
+    derive: bool,
+
+    /// Account derivation schemes used with --derive
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "bip44,bip49,bip84,bip86")]
+    derive_paths: Vec<Purpose>,
+
+    /// Account index used with --derive
+    #[arg(long, default_value_t = 0)]
+    account: u32,
+
+    /// Prompt for a BIP39 passphrase used for fingerprints and xpubs
+    #[arg(long)]
+    passphrase: bool,
+
+    /// Score found seeds by their SLIP-39 share lengths for this scheme (e.g. 1:2/3)
+    #[arg(long, value_name = "SCHEME")]
+    slip39_score: Option<Scheme>,
+
+    /// Random splits tried per found seed with --slip39-score
+    #[arg(long, default_value_t = 16)]
+    slip39_attempts: usize,
+
+    /// Only collect seeds whose longest SLIP-39 share has at most N characters
+    #[arg(long, value_name = "N", requires = "slip39_score")]
+    slip39_max: Option<usize>,
+
+    /// Search BIP85 child seeds of a master mnemonic or xprv (prompted) instead of random entropy
+    #[arg(long)]
+    bip85: bool,
+
+    /// First BIP85 child index to search
+    #[arg(long, default_value_t = 0, requires = "bip85")]
+    bip85_start: u32,
+
+    /// Prompt for the BIP39 passphrase of the BIP85 master mnemonic
+    #[arg(long, requires = "bip85")]
+    bip85_passphrase: bool,
+
+    /// Mix user entropy (prompted, or read from --user-entropy-file) into every candidate
+    #[arg(long, value_enum, value_name = "FORMAT", conflicts_with = "bip85")]
+    user_entropy: Option<mixing::EntropyFormat>,
+
+    /// Read the user entropy from a file instead of prompting
+    #[arg(long, value_name = "PATH", requires = "user_entropy")]
+    user_entropy_file: Option<String>,
+
+    /// Stop after this long (e.g. 8h, 90m, "1h 30m")
+    #[arg(long, value_name = "DURATION", value_parser = limits::parse_duration)]
+    duration: Option<std::time::Duration>,
+
+    /// Stop at this local time: HH:MM (next occurrence), "YYYY-MM-DD HH:MM" or RFC 3339
+    #[arg(long, value_name = "TIME", value_parser = limits::parse_deadline)]
+    deadline: Option<chrono::DateTime<chrono::Local>>,
+
+    /// Stop after this many candidates
+    #[arg(long, value_name = "N")]
+    max_iterations: Option<u64>,
+
+    /// Stop once enough seeds were collected, e.g. "<=41:1" or "44:5" (comma-separated, any)
+    #[arg(long, value_name = "GOALS", value_delimiter = ',')]
+    until_found: Vec<limits::Goal>,
+
+    /// Only run inside this time window, e.g. "mon-fri 19:00-07:00" (repeat for more windows)
+    #[arg(long, value_name = "WINDOW")]
+    run_window: Vec<schedule::Window>,
+
+    /// Pause while other processes are using the CPU
+    #[arg(long)]
+    idle_only: bool,
+
+    /// CPU usage of other processes (percent of the machine) that counts as busy
+    #[arg(long, default_value_t = 25.0, requires = "idle_only")]
+    idle_cpu: f64,
+
+    /// How long the machine must stay idle before the workers resume
+    #[arg(long, default_value = "1m", value_parser = limits::parse_duration, requires = "idle_only")]
+    idle_resume: std::time::Duration,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Export stored mnemonics (SeedQR, CompactSeedQR, steel plate template)
+    Export(export::ExportArgs),
+    /// Decode a SeedQR (digits) or CompactSeedQR (hex) payload into a mnemonic
+    SeedqrDecode {
+        /// Payload digits or hex bytes
+        payload: String,
+    },
+    /// Split a stored mnemonic into SLIP-39 Shamir shares
+    Split(shares::SplitArgs),
+    /// Recover a mnemonic from SLIP-39 shares
+    Combine(shares::CombineArgs),
+    /// Re-derive the BIP85 child mnemonic at an index from the master
+    Bip85Derive {
+        /// Child index (as recorded in the log file)
+        index: u32,
+
+        /// Prompt for the BIP39 passphrase of the master mnemonic
+        #[arg(long)]
+        passphrase: bool,
+    },
+    /// Check the user entropy commitment and recompute the mixed seeds in a log file
+    VerifyEntropy(mixing::VerifyArgs),
+    /// Check the GPU kernels (scoring, Philox generator) against their CPU references
+    GpuCheck {
+        /// Random candidates to score
+        #[arg(long, default_value_t = 100_000)]
+        count: usize,
+
+        /// Character threshold for the hits
+        #[arg(long, default_value_t = 46)]
+        threshold: usize,
+    },
+    /// Merge the logs of a --partition search and check that they cover every partition once
+    Merge(partition::MergeArgs),
+    /// Send a command to a running search (status, pause, resume, set-rules, set-limits, flush-results, stop)
+    #[cfg(unix)]
+    Ctl(control::CtlArgs),
 }
 
 const CONFIG_FILE: &str = "../gpuseed_config.json";
 
 fn main() {
-    let args = Args::parse();
-    
-    // Validate inputs
-    if args.threshold == 0 || args.threshold > 200 {
-        eprintln!("Error: Threshold must be between 1 and 200");
-        std::process::exit(1);
-    }
-    
-    if args.count == 0 || args.count > 1000 {
-        eprintln!("Error: Count must be between 1 and 1000");
-        std::process::exit(1);
-    }
+    // Found seeds are real wallet secrets - never let a crash dump them to disk
+    secure::disable_core_dumps();
+
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
     
-    if args.batch_size == 0 || args.batch_size > 1_000_000 {
-        eprintln!("Error: Batch size must be between 1 and 1,000,000");
-        std::process::exit(1);
+    // Subcommands work on existing results and exit when done
+    if let Some(ref command) = args.command {
+        let result = match command {
+            Command::Export(export_args) => export::run(export_args),
+            Command::SeedqrDecode { payload } => export::decode(payload),
+            Command::Split(split_args) => shares::split(split_args),
+            Command::Combine(combine_args) => shares::combine(combine_args),
+            Command::Bip85Derive { index, passphrase } => bip85::derive(*index, *passphrase),
+            Command::VerifyEntropy(verify_args) => mixing::verify(verify_args),
+            Command::GpuCheck { count, threshold } => scoring::check(*count, *threshold),
+            Command::Merge(merge_args) => partition::merge(merge_args),
+            #[cfg(unix)]
+            Command::Ctl(ctl_args) => control::ctl(ctl_args),
+        };
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
     }
     
     // Handle config reset
@@ -83,77 +355,489 @@ fn main() {
     // Load or create configuration
     let config = Config::load_or_create();
     
-    // Determine GPU usage
-    let use_gpu = if args.reset_config || config.is_none() {
-        prompt_gpu_setup()
+    // Collection rules from the config file, unless given on the command line
+    let from_command_line = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    if let Some(ref config) = config {
+        if let Some(threshold) = config.threshold.filter(|_| !from_command_line("threshold")) {
+            args.threshold = threshold;
+        }
+        if let Some(count) = config.count.filter(|_| !from_command_line("count")) {
+            args.count = count;
+        }
+    }
+    
+    // Validate inputs
+    let rules = match CollectionRules::new(args.threshold, args.count) {
+        Ok(rules) => Arc::new(rules),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    
+    if args.batch_size == 0 || args.batch_size > 1_000_000 {
+        eprintln!("Error: Batch size must be between 1 and 1,000,000");
+        std::process::exit(1);
+    }
+    
+    if args.slip39_attempts == 0 || args.slip39_attempts > 1000 {
+        eprintln!("Error: SLIP-39 attempts must be between 1 and 1000");
+        std::process::exit(1);
+    }
+    
+    if args.bip85_start > bip85::MAX_INDEX {
+        eprintln!("Error: BIP85 start index must be at most {}", bip85::MAX_INDEX);
+        std::process::exit(1);
+    }
+    
+    // BIP39 passphrase (never taken from the command line, so it stays out of shell history)
+    let passphrase = if args.passphrase {
+        match rpassword::prompt_password("BIP39 passphrase: ") {
+            Ok(p) => Zeroizing::new(p),
+            Err(e) => {
+                eprintln!("Error: Could not read passphrase: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        Zeroizing::new(String::new())
+    };
+    
+    // BIP85 master (children are derived on the CPU, so no GPU prompt)
+    let bip85_source = if args.bip85 {
+        match bip85::prompt_master(args.bip85_passphrase)
+            .and_then(|master| bip85::Bip85Source::new(&master, args.bip85_start))
+        {
+            Ok(source) => Some(Arc::new(source)),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
     } else {
-        prompt_gpu_usage(&config.unwrap())
+        None
+    };
+    
+    // User entropy, committed to before the search starts
+    let user_entropy = match args.user_entropy {
+        Some(format) => match mixing::UserEntropy::load(format, args.user_entropy_file.as_deref()) {
+            Ok(user_entropy) => Some(Arc::new(user_entropy)),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    
+    // Distributed search (bound / connected before the search starts, so problems fail fast)
+    let num_workers = num_cpus::get().max(1); // Ensure at least 1 worker
+    let cluster_secret = args.cluster_secret.as_ref().map(|path| {
+        cluster::ClusterSecret::load(path).unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        })
+    });
+    let mut coordinator = None;
+    let mut cluster_client = None;
+    let mut partition_run = None;
+    let mut resumed_seeds: SecretVec<FoundSeed> = SecretVec::default();
+    if let Some(secret) = cluster_secret {
+        if let Some(partition) = args.partition {
+            // A log of this partition from an earlier run is continued rather than overwritten
+            let mut run = partition::PartitionRun::new(partition, &secret, args.unit_size);
+            match run.resume(&args.logfile) {
+                Ok(seeds) => resumed_seeds = seeds,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            partition_run = Some(Arc::new(run));
+        } else if let Some(ref addr) = args.coordinator {
+            match cluster::Coordinator::bind(addr, secret, args.unit_size) {
+                Ok(bound) => coordinator = Some(Arc::new(bound)),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        } else if let Some(ref addr) = args.join {
+            let name = sysinfo::System::host_name().unwrap_or_else(|| "node".to_string());
+            match cluster::ClusterClient::connect(addr, &secret, &name, num_workers) {
+                Ok((client, (threshold, count))) => {
+                    // The coordinator's collection rules apply to every node
+                    (args.threshold, args.count) = (threshold, count);
+                    if let Err(e) = rules.set(threshold, count) {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1);
+                    }
+                    cluster_client = Some(Arc::new(client));
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+    
+    // Determine GPU usage (BIP85 children and work units are derived on the CPU)
+    let use_gpu = match config {
+        _ if bip85_source.is_some() || coordinator.is_some() || cluster_client.is_some() || partition_run.is_some() => {
+            false
+        }
+        _ if args.simulate_gpu.is_some() => true,
+        Some(ref config) if !args.reset_config => prompt_gpu_usage(config),
+        _ => prompt_gpu_setup(),
     };
     
     if !use_gpu {
         println!("\nRunning in CPU-only mode.");
+    } else if args.simulate_gpu.is_some() {
+        println!("\nSimulated GPU mode enabled.");
     } else {
         println!("\nGPU mode enabled (configuration saved).");
     }
     
+    // GPUs of the run (--devices); none found or selected means the CPU does all the work
+    let gpu_devices = if !use_gpu {
+        Vec::new()
+    } else if args.simulate_gpu.is_some() {
+        args.devices.simulated()
+    } else {
+        let available = gpu::enumerate_devices().unwrap_or_else(|e| {
+            eprintln!("No CUDA devices available: {}.", e);
+            Vec::new()
+        });
+        for index in args.devices.missing(&available) {
+            eprintln!("Warning: GPU device {} not found ({} available), skipping it", index, available.len());
+        }
+        let devices = args.devices.resolve(&available);
+        if devices.is_empty() {
+            println!("No GPU device to use (--devices {}): running on the CPU.", args.devices);
+        }
+        devices
+    };
+    
     // Initialize output file
     if let Err(e) = fs::File::create(&args.output) {
         eprintln!("Warning: Could not create output file: {}", e);
     }
     
     println!("\nStarting mnemonic generation...");
-    println!("Looking for mnemonics with LESS than 46 characters");
-    println!("  - 43-45 chars: limit of 5 per count");
+    println!("Looking for mnemonics with LESS than {} characters", args.threshold);
+    if args.threshold > 43 {
+        println!("  - 43-{} chars: limit of {} per count", args.threshold - 1, args.count);
+    }
     println!("  - 42 or less: NO LIMIT (collect all unique)");
     println!("Batch size: {}", args.batch_size);
     println!("Output file: {}", args.output);
-    println!("GPU: {}", if use_gpu { "Enabled" } else { "Disabled" });
+    println!("GPU: {}", if gpu_devices.is_empty() { "Disabled" } else { "Enabled" });
+    if !gpu_devices.is_empty() {
+        println!("GPU generator: {}", args.gpu_rng.name());
+        for device in &gpu_devices {
+            match device.pci_bus_id {
+                Some(ref bus_id) => println!("GPU device {}: {} (PCI {})", device.index, device.name, bus_id),
+                None => println!("GPU device {}: {}", device.index, device.name),
+            }
+        }
+    }
+    if bip85_source.is_some() {
+        println!("Entropy: BIP85 children from index {}", args.bip85_start);
+    }
+    if let Some(ref user_entropy) = user_entropy {
+        println!("User entropy: ~{:.0} bits mixed into every candidate", user_entropy.bits);
+        println!("User entropy commitment: {}", user_entropy.commitment);
+    }
+    if let Some(ref scheme) = args.slip39_score {
+        println!("SLIP-39 share scoring: {} ({} splits per seed)", scheme, args.slip39_attempts);
+    }
+    if let Some(ref addr) = args.coordinator {
+        println!("Distributed search: coordinator on {} ({} candidates per work unit)", addr, args.unit_size);
+    }
+    if let Some(ref addr) = args.join {
+        println!("Distributed search: worker node of {}", addr);
+    }
+    if let Some(ref run) = partition_run {
+        println!("Partitioned search: {}", run.describe());
+        if run.searched() > 0 {
+            println!(
+                "Resuming {}: {} units already searched, {} seeds",
+                args.logfile,
+                run.searched(),
+                resumed_seeds.len()
+            );
+        }
+    }
     
-    // Start resource monitor
+    // Stop flag and throttle data shared by the monitor, the workers and the control paths
     let stop_flag = Arc::new(AtomicBool::new(false));
     let throttle_data = Arc::new(std::sync::Mutex::new(HashMap::new()));
+    if let Some(percent) = config.as_ref().and_then(|config| config.max_usage_percent) {
+        if let Err(e) = monitor::set_max_usage(&throttle_data, percent) {
+            eprintln!("Error: {} (max_usage_percent in the config file)", e);
+            std::process::exit(1);
+        }
+    }
+    
+    // Run limits (checked by the workers, a past deadline fails fast)
+    let limits = match limits::RunLimits::new(args.duration, args.deadline, args.max_iterations, args.until_found.clone()) {
+        Ok(limits) => Arc::new(limits),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
     
+    // Status stream (opened before the search starts, so a bad target fails fast)
+    let status_stream = args.status_json.as_ref().map(|target| match status_json::StatusStream::open(target) {
+        Ok(stream) => Arc::new(stream),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    });
+
+    // Webhook sink (records queued by an earlier run are delivered first)
+    let webhook = args.webhook.as_ref().map(|url| {
+        let secret = args.webhook_secret.as_deref().unwrap_or_default();
+        match webhook::Webhook::open(url, secret, args.webhook_payload, &args.webhook_queue, passphrase.clone()) {
+            Ok(webhook) => Arc::new(webhook),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    });
+
+    // Start worker pool
+    let mut reporter = Reporter::new(args.console, passphrase.clone());
+    if let Some(ref stream) = status_stream {
+        reporter = reporter.with_status_stream(stream.clone());
+    }
+    if let Some(ref webhook) = webhook {
+        reporter = reporter.with_webhook(webhook.clone());
+    }
+    let reporter = Arc::new(reporter);
+    let webhook_handle = webhook.as_ref().map(|webhook| webhook.spawn(reporter.clone()));
+    
+    // Start resource monitor (its notices go through the reporter)
     let monitor_handle = {
         let stop_flag = stop_flag.clone();
         let throttle_data = throttle_data.clone();
+        let reporter = reporter.clone();
+        // Simulated devices have nothing to watch
+        let devices = if args.simulate_gpu.is_some() { Vec::new() } else { gpu_devices.clone() };
         std::thread::spawn(move || {
-            ResourceMonitor::new(stop_flag, throttle_data).run();
+            ResourceMonitor::new(stop_flag, throttle_data, devices, &reporter).run();
         })
     };
     
-    // Start worker pool
-    let num_workers = num_cpus::get().max(1); // Ensure at least 1 worker
+    let share_scoring = args.slip39_score.clone().map(|scheme| ShareScoring {
+        scheme,
+        attempts: args.slip39_attempts,
+        max_chars: args.slip39_max,
+    });
     println!("Starting {} worker threads...", num_workers);
+    let stats = Arc::new(RunStats::new(num_workers, &gpu_devices));
+    
+    // Metrics endpoint (bound before the search starts, so a bad address fails fast)
+    let metrics_handle = args.metrics.as_ref().map(|addr| {
+        match metrics::MetricsServer::bind(
+            addr,
+            stats.clone(),
+            throttle_data.clone(),
+            reporter.clone(),
+            stop_flag.clone(),
+        ) {
+            Ok(server) => {
+                println!("Metrics: http://{}/metrics", addr);
+                server.spawn()
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    });
+    
+    let status_interval = std::time::Duration::from_secs(args.status_interval);
+    let status_json_handle = status_stream.as_ref().map(|stream| {
+        status_json::spawn(
+            stream.clone(),
+            stats.clone(),
+            throttle_data.clone(),
+            reporter.clone(),
+            stop_flag.clone(),
+            status_interval,
+        )
+    });
+    
+    // Run windows and idle-only mode (evaluated once now, so workers never start outside a window)
+    let mut scheduler = schedule::Scheduler::new(
+        args.run_window.clone(),
+        args.idle_only.then_some(args.idle_cpu / 100.0),
+        args.idle_resume,
+        stats.clone(),
+        reporter.clone(),
+        stop_flag.clone(),
+    );
+    let scheduler_handle = scheduler.is_active().then(|| {
+        let windows: Vec<String> = args.run_window.iter().map(ToString::to_string).collect();
+        if !windows.is_empty() {
+            println!("Run windows: {}", windows.join(", "));
+        }
+        if args.idle_only {
+            println!("Idle-only: pausing while other processes use more than {:.0}% CPU", args.idle_cpu);
+        }
+        scheduler.update();
+        scheduler.spawn()
+    });
+    
+    let units = if let Some(ref coordinator) = coordinator {
+        Some(cluster::UnitSource::local(coordinator.clone(), rules.clone(), reporter.clone(), stop_flag.clone()))
+    } else if let Some(ref run) = partition_run {
+        Some(cluster::UnitSource::partition(run.clone(), rules.clone(), reporter.clone(), stop_flag.clone()))
+    } else {
+        cluster_client.as_ref().map(|client| {
+            cluster::UnitSource::remote(client.clone(), rules.clone(), reporter.clone(), stop_flag.clone())
+        })
+    };
     
     let worker_pool = WorkerPool::new(
         num_workers,
         args.batch_size,
-        args.threshold,
-        args.count,
+        rules.clone(),
         use_gpu,
+        &gpu_devices,
+        args.simulate_gpu,
+        args.gpu_rng,
+        args.gpu_queue,
+        args.mlock,
+        share_scoring,
+        bip85_source,
+        user_entropy.clone(),
+        units.map(Arc::new),
+        limits.clone(),
         stop_flag.clone(),
         throttle_data.clone(),
+        stats.clone(),
+        reporter.clone(),
     );
     
+    // Seeds of the resumed partition log count toward the collection rules and goals
+    if !resumed_seeds.is_empty() {
+        let mut counts = stats.found_counts.lock().unwrap();
+        let results = worker_pool.results();
+        let mut results = results.lock().unwrap();
+        for found in resumed_seeds.iter() {
+            *counts.entry(found.chars).or_insert(0) += 1;
+            results.push(found.clone());
+        }
+    }
+    
     let start_time = Instant::now();
     
-    println!("Press Ctrl+C to stop...\n");
-    println!("Resource limit: 80% (safety system active)");
+    // Worker nodes report to the coordinator (results go into the same store as local finds)
+    let coordinator_handle = coordinator.as_ref().map(|coordinator| {
+        coordinator.serve(
+            worker_pool.results(),
+            stats.clone(),
+            rules.clone(),
+            reporter.clone(),
+            limits.clone(),
+            stop_flag.clone(),
+        )
+    });
+    let progress_handle = cluster_client
+        .as_ref()
+        .map(|client| client.spawn_progress(stats.clone(), rules.clone(), reporter.clone(), stop_flag.clone()));
     
-    // Get iterations counter for status display
-    let iterations_counter = worker_pool.get_iterations_counter();
+    // Control socket (flush-results writes the seeds collected so far, without --derive keys)
+    #[cfg(unix)]
+    let control_handle = args.control_socket.as_ref().map(|path| {
+        let results = worker_pool.results();
+        let commitment = user_entropy.as_ref().map(|user_entropy| user_entropy.commitment);
+        let (logfile, output) = (args.logfile.clone(), args.output.clone());
+        let partition_run = partition_run.clone();
+        let flush: control::FlushResults = Box::new(move || {
+            let header = partition_run.as_ref().map(|run| run.header()).unwrap_or_default();
+            let (count, log, seeds) = {
+                let results = results.lock().unwrap();
+                let log = log_content(&header, &results, commitment, start_time.elapsed());
+                (results.len(), log, output_content(&results))
+            };
+            fs::write(&logfile, log.as_bytes()).map_err(|e| format!("Could not write to log file: {}", e))?;
+            fs::write(&output, seeds.as_bytes()).map_err(|e| format!("Could not write to output file: {}", e))?;
+            Ok(format!("Saved {} seeds to {} and {}", count, logfile, output))
+        });
+        match control::ControlServer::bind(
+            path,
+            stats.clone(),
+            throttle_data.clone(),
+            rules.clone(),
+            limits.clone(),
+            reporter.clone(),
+            stop_flag.clone(),
+            flush,
+        ) {
+            Ok(server) => {
+                println!("Control socket: {}", path);
+                server.spawn()
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    });
+    #[cfg(not(unix))]
+    if args.control_socket.is_some() {
+        eprintln!("Error: Control sockets are only supported on Unix");
+        std::process::exit(1);
+    }
+    
+    println!("Press Ctrl+C to stop...\n");
+    println!(
+        "Resource limit: {:.0}% (safety system active)",
+        monitor::max_usage(&throttle_data) * 100.0
+    );
+    let run_limits = limits.describe();
+    if !run_limits.is_empty() {
+        println!("Run limits: {}", run_limits.join(", "));
+    }
     
     // Handle Ctrl+C
     let stop_flag_ctrlc = stop_flag.clone();
+    let reporter_ctrlc = reporter.clone();
     ctrlc::set_handler(move || {
-        println!("\n\nInterrupted by user. Shutting down gracefully...");
+        reporter_ctrlc.notice("\n\nInterrupted by user. Shutting down gracefully...");
         stop_flag_ctrlc.store(true, Ordering::Relaxed);
     })
     .expect("Error setting Ctrl-C handler");
     
+    // SIGUSR1 status dump, SIGUSR2 pause, SIGHUP config reload, SIGTERM graceful stop
+    #[cfg(unix)]
+    let signal_handler = match signals::SignalHandler::install(
+        stats.clone(),
+        throttle_data.clone(),
+        rules.clone(),
+        args.join.is_some(),
+        reporter.clone(),
+        stop_flag.clone(),
+    ) {
+        Ok(handler) => Some(handler),
+        Err(e) => {
+            eprintln!("Warning: {}", e);
+            None
+        }
+    };
+    
     // Run worker pool in a separate thread
-    let worker_results = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let worker_results = Arc::new(std::sync::Mutex::new(SecretVec::default()));
     let worker_results_clone = worker_results.clone();
+    let gpu_feeders = worker_pool.gpu_feeders();
     let worker_pool_for_thread = worker_pool;
     
     let worker_handle = std::thread::spawn(move || {
@@ -161,12 +845,32 @@ fn main() {
         *worker_results_clone.lock().unwrap() = results;
     });
     
+    // Dashboard when attached to a terminal, otherwise the status line
+    let use_tui = !args.no_tui && io::stdout().is_terminal();
+    let dashboard_ran = use_tui && {
+        let mut dashboard = dashboard::Dashboard::new(
+            stats.clone(),
+            throttle_data.clone(),
+            reporter.clone(),
+            stop_flag.clone(),
+            rules.clone(),
+        );
+        match dashboard.run(&worker_handle) {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("Warning: Dashboard failed ({}), falling back to the status line", e);
+                false
+            }
+        }
+    };
+    
     // Status display loop
-    let status_interval = std::time::Duration::from_secs(5);
     let mut last_status_time = Instant::now();
     
     // Show initial status immediately
-    display_status(&iterations_counter, &start_time, &throttle_data);
+    if !dashboard_ran {
+        display_status(&reporter, &stats, &throttle_data);
+    }
     
     while !stop_flag.load(Ordering::Relaxed) {
         std::thread::sleep(std::time::Duration::from_millis(100));
@@ -176,14 +880,24 @@ fn main() {
             break;
         }
         
-        // Display status every 5 seconds
+        // Display status every status interval
         let now = Instant::now();
         if now.duration_since(last_status_time) >= status_interval {
-            display_status(&iterations_counter, &start_time, &throttle_data);
+            display_status(&reporter, &stats, &throttle_data);
             last_status_time = now;
         }
     }
     
+    // Workers only finish on their own when there is nothing left to search
+    let stop_reason = match limits.reason() {
+        Some(reason) => {
+            reporter.notice(&format!("\n\nRun limit reached ({}). Shutting down gracefully...", reason));
+            reason
+        }
+        None if stop_flag.load(Ordering::Relaxed) => "stopped",
+        None => "completed",
+    };
+
     // Stop monitor and workers
     stop_flag.store(true, Ordering::Relaxed);
 
@@ -196,40 +910,168 @@ fn main() {
 
     // Join worker thread first (wait for it to finish)
     let _ = worker_handle.join();
+    if let Some(ref client) = cluster_client {
+        client.close(&stats);
+    }
 
-    // Join monitor (should finish quickly)
+    // Join monitor and metrics endpoint (should finish quickly)
     monitor_handle.join().ok();
+    if let Some(handle) = metrics_handle {
+        handle.join().ok();
+    }
+    if let Some(handle) = status_json_handle {
+        handle.join().ok();
+    }
+    if let Some(handle) = scheduler_handle {
+        handle.join().ok();
+    }
+    if let Some(handle) = coordinator_handle {
+        handle.join().ok();
+    }
+    if let Some(handle) = progress_handle {
+        handle.join().ok();
+    }
+    #[cfg(unix)]
+    if let Some(handler) = signal_handler {
+        handler.close();
+    }
+    #[cfg(unix)]
+    if let Some(handle) = control_handle {
+        handle.join().ok();
+    }
 
-    // Get results - clone from Arc since try_unwrap may fail
-    let results = {
-        let results_guard = worker_results.lock().unwrap();
-        results_guard.clone()
-    };
+    // Get results - take them out of the Arc rather than cloning secrets
+    let mut results = std::mem::take(&mut *worker_results.lock().unwrap());
+    
+    // Post-processing: fingerprints and account xpubs
+    if args.derive {
+        let settings = DeriveSettings {
+            passphrase,
+            purposes: args.derive_paths.clone(),
+            account: args.account,
+        };
+        derive_results(&mut results, &settings);
+    }
     
     // Clear status line
     println!();
     
     // Save results
-    save_results(&results, &args.logfile, &args.output, start_time);
+    let commitment = user_entropy.as_ref().map(|user_entropy| user_entropy.commitment);
+    // A partitioned run always writes its log: it records which units were searched
+    let header = partition_run.as_ref().map(|run| run.header()).unwrap_or_default();
+    save_results(&header, &results, rules.get().0, commitment, &args.logfile, &args.output, start_time);
+    save_summary(&stats, commitment, &args.summary, start_time);
+    
+    // Deliver the last webhook records (undelivered ones stay in the queue file)
+    if let (Some(ref webhook), Some(handle)) = (&webhook, webhook_handle) {
+        webhook.close();
+        handle.join().ok();
+    }
+    
+    reporter.event("shutdown", serde_json::json!({
+        "reason": stop_reason,
+        "found": results.len(),
+        "iterations": stats.iterations.load(Ordering::Relaxed),
+        "elapsed": start_time.elapsed().as_secs_f64(),
+    }));
     
     println!("\nGeneration complete!");
     println!("Total mnemonics found: {}", results.len());
+    if let Some(best) = stats.best_chars() {
+        println!("Best seen: {} characters", best);
+    }
+    if let Some(ref coordinator) = coordinator {
+        println!("Work units: {}", coordinator.summary());
+    }
+    if let Some(ref run) = partition_run {
+        println!("Work units: {}", run.summary());
+    }
+    if let Some(ref webhook) = webhook {
+        println!("Webhook: {}", webhook.summary());
+    }
+    if let Some(ref feeders) = gpu_feeders {
+        for line in feeders.summary() {
+            println!("{}", line);
+        }
+    }
     println!("Time elapsed: {:?}", start_time.elapsed());
 }
 
+// Upper bound of one seed's log record. Record buffers are allocated once with this capacity,
+// since growing a zeroizing buffer would leave the old allocation unwiped
+fn record_capacity(found: &FoundSeed) -> usize {
+    let keys = found.keys.as_ref().map_or(0, |keys| 32 + keys.accounts.len() * 256);
+    let shares = found.shares.as_ref().map_or(0, |split| {
+        128 + split.groups.iter().flatten().map(|share| share.len() + 24).sum::<usize>()
+    });
+    512 + found.chars + keys + shares
+}
+
+// Mnemonic, character count and where the entropy came from
+fn describe_seed(out: &mut Zeroizing<String>, found: &FoundSeed, commitment: Option<mixing::Commitment>) {
+    writeln!(out, "Mnemonic: {}", *found.mnemonic()).unwrap();
+    writeln!(out, "Total characters: {}", found.chars).unwrap();
+    if let Some(index) = found.bip85_index {
+        writeln!(out, "BIP85 index: {} ({})", index, bip85::child_path(index)).unwrap();
+    }
+    if let Some((unit, offset)) = found.unit {
+        writeln!(out, "Work unit: {}, candidate {}", unit, offset).unwrap();
+    }
+    if let (Some(mix), Some(commitment)) = (found.mix, commitment) {
+        writeln!(
+            out,
+            "Mixed from machine entropy {}, counter {}\nUser entropy commitment: {}",
+            mix.machine_entropy.as_hex(),
+            mix.counter,
+            commitment
+        )
+        .unwrap();
+    }
+}
+
+// Best candidate seen (collected or not) and the score histogram of the whole run
+fn save_summary(stats: &RunStats, commitment: Option<mixing::Commitment>, path: &str, start_time: Instant) {
+    let content = summary_content(stats, commitment, start_time.elapsed());
+    match fs::write(path, content.as_bytes()) {
+        Ok(_) => println!("Saved run summary to {}", path),
+        Err(e) => eprintln!("Error: Could not write run summary: {}", e),
+    }
+}
+
+// The whole summary is reserved up front so that appending after the mnemonic never
+// reallocates and leaves a copy of it behind
+fn summary_content(
+    stats: &RunStats,
+    commitment: Option<mixing::Commitment>,
+    elapsed: std::time::Duration,
+) -> Zeroizing<String> {
+    let header = format!("Run summary\nTime elapsed: {:?}\n\n", elapsed);
+    let report = stats.score_report();
+    let best = stats.best();
+    let capacity = header.len() + report.len() + best.as_ref().map_or(0, |best| record_capacity(best)) + 128;
+    let mut content = Zeroizing::new(String::with_capacity(capacity));
+    content.push_str(&header);
+    match best {
+        Some(best) => {
+            content.push_str("Best candidate seen:\n");
+            describe_seed(&mut content, &best, commitment);
+        }
+        None => content.push_str("Best candidate seen: none\n"),
+    }
+    content.push_str("\nCharacter counts of all candidates vs. the theoretical distribution:\n");
+    content.push_str(&report);
+    content
+}
+
 fn display_status(
-    iterations_counter: &Arc<AtomicU64>,
-    start_time: &Instant,
+    reporter: &Reporter,
+    stats: &RunStats,
     throttle_data: &Arc<std::sync::Mutex<HashMap<String, f64>>>,
 ) {
-    let iterations = iterations_counter.load(Ordering::Relaxed);
-    let elapsed = start_time.elapsed();
+    let iterations = stats.iterations.load(Ordering::Relaxed);
     // Calculate speed in iterations per second (same as Python)
-    let speed = if elapsed.as_secs_f64() > 0.0 {
-        iterations as f64 / elapsed.as_secs_f64()
-    } else {
-        0.0
-    };
+    let speed = stats.average_rate();
     
     // Get resource usage from throttle data
     let (cpu_usage, gpu_usage, cpu_throttle, gpu_throttle) = {
@@ -241,6 +1083,34 @@ fn display_status(
         (cpu_usage, gpu_usage, cpu_throttle, gpu_throttle)
     };
     
+    let best = stats
+        .best_chars()
+        .map(|chars| format!(" | Best: {}", chars))
+        .unwrap_or_default();
+    
+    let reasons: Vec<&str> = stats.pause_reasons().map(stats::PauseReason::name).collect();
+    let paused = if reasons.is_empty() {
+        String::new()
+    } else {
+        format!(" | PAUSED ({})", reasons.join(", "))
+    };
+    
+    // Usage and throughput of each GPU, when the run has any
+    let gpu_status = if stats.devices.is_empty() {
+        format!("GPU: {:.1}%", gpu_usage)
+    } else {
+        let data = throttle_data.lock().unwrap();
+        let devices: Vec<String> = stats
+            .devices
+            .iter()
+            .map(|device| {
+                let usage = data.get(&monitor::device_key("gpu_usage", device.index)).copied().unwrap_or(0.0);
+                format!("GPU{}: {:.1}% {}/s", device.index, usage * 100.0, format_number(stats.device_rate(device) as u64))
+            })
+            .collect();
+        devices.join(" | ")
+    };
+    
     // Build throttle status string
     let throttle_status = if cpu_throttle < 1.0 || gpu_throttle < 1.0 {
         format!(" [THROTTLE: CPU={:.2}, GPU={:.2}]", cpu_throttle, gpu_throttle)
@@ -252,9 +1122,8 @@ fn display_status(
     let iterations_str = format_number(iterations);
     
     // Print status line (overwrite previous line) - same format as Python
-    print!("\rProcessed: {} ({:.0}/s) | CPU: {:.1}% | GPU: {:.1}%{}", 
-        iterations_str, speed, cpu_usage, gpu_usage, throttle_status);
-    io::stdout().flush().ok();
+    reporter.status(&format!("Processed: {} ({:.0}/s){} | CPU: {:.1}% | {}{}{}",
+        iterations_str, speed, best, cpu_usage, gpu_status, throttle_status, paused));
 }
 
 fn format_number(n: u64) -> String {
@@ -262,7 +1131,7 @@ fn format_number(n: u64) -> String {
     let mut result = String::new();
     let chars: Vec<char> = s.chars().collect();
     for (i, &ch) in chars.iter().enumerate() {
-        if i > 0 && (chars.len() - i) % 3 == 0 {
+        if i > 0 && (chars.len() - i).is_multiple_of(3) {
             result.push(',');
         }
         result.push(ch);
@@ -349,8 +1218,24 @@ fn prompt_gpu_usage(_config: &Config) -> bool {
     input.trim() == "1"
 }
 
+fn derive_results(results: &mut SecretVec<FoundSeed>, settings: &DeriveSettings) {
+    if results.is_empty() {
+        return;
+    }
+    println!("\nDeriving fingerprints and xpubs for {} seeds...", results.len());
+    for found in results.iter_mut() {
+        match keys::derive_keys(&found.entropy, settings) {
+            Ok(seed_keys) => found.keys = Some(seed_keys),
+            Err(e) => eprintln!("Warning: Key derivation failed: {}", e),
+        }
+    }
+}
+
 fn save_results(
-    results: &[(String, usize)],
+    header: &str,
+    results: &SecretVec<FoundSeed>,
+    threshold: usize,
+    commitment: Option<mixing::Commitment>,
     logfile: &str,
     output: &str,
     start_time: Instant,
@@ -358,31 +1243,100 @@ fn save_results(
     let elapsed = start_time.elapsed();
 
     if results.is_empty() {
-        println!("No seeds found matching criteria (< 46 characters).");
-        return;
+        println!("No seeds found matching criteria (< {} characters).", threshold);
+        if header.is_empty() {
+            return;
+        }
     }
 
     // Save to log file
-    let mut log_content = String::new();
-    for (mnemonic, chars) in results {
-        log_content.push_str(&format!(
-            "Mnemonic: {}\nTotal characters: {}\nTime elapsed: {:?}\n----------------------------------\n",
-            mnemonic, chars, elapsed
-        ));
-    }
-    match fs::write(logfile, &log_content) {
+    let log_content = log_content(header, results, commitment, elapsed);
+    match fs::write(logfile, log_content.as_bytes()) {
         Ok(_) => println!("Saved {} seeds to {}", results.len(), logfile),
         Err(e) => eprintln!("Error: Could not write to log file: {}", e),
     }
 
     // Save to output file (simple format)
-    let mut output_content = String::new();
-    for (mnemonic, chars) in results {
-        output_content.push_str(&format!("{} {}\n", mnemonic, chars));
-    }
-    match fs::write(output, &output_content) {
+    let output_content = output_content(results);
+    match fs::write(output, output_content.as_bytes()) {
         Ok(_) => println!("Saved {} seeds to {}", results.len(), output),
         Err(e) => eprintln!("Error: Could not write to output file: {}", e),
     }
 }
 
+// Log file: every seed with its details (buffers hold every phrase, so they are zeroizing and
+// sized up front)
+fn log_content(
+    header: &str,
+    results: &SecretVec<FoundSeed>,
+    commitment: Option<mixing::Commitment>,
+    elapsed: std::time::Duration,
+) -> Zeroizing<String> {
+    let capacity = header.len() + results.iter().map(record_capacity).sum::<usize>();
+    let mut log_content = Zeroizing::new(String::with_capacity(capacity));
+    log_content.push_str(header);
+    for found in results.iter() {
+        describe_seed(&mut log_content, found, commitment);
+        if let Some(ref seed_keys) = found.keys {
+            writeln!(log_content, "Fingerprint: {}", seed_keys.fingerprint).unwrap();
+            for account in &seed_keys.accounts {
+                writeln!(
+                    log_content,
+                    "{} {}: {}",
+                    account.purpose.name(),
+                    account.path,
+                    account.descriptor(seed_keys.fingerprint)
+                )
+                .unwrap();
+            }
+        }
+        if let Some(ref split) = found.shares {
+            // Shares use an empty SLIP-39 passphrase
+            writeln!(
+                log_content,
+                "SLIP-39 {} (longest share {} characters):",
+                split.scheme,
+                split.longest_share_chars()
+            )
+            .unwrap();
+            for (i, shares) in split.groups.iter().enumerate() {
+                for (j, share) in shares.iter().enumerate() {
+                    writeln!(log_content, "  Share {}.{}: {}", i + 1, j + 1, **share).unwrap();
+                }
+            }
+        }
+        writeln!(log_content, "Time elapsed: {:?}\n----------------------------------", elapsed).unwrap();
+    }
+    log_content
+}
+
+// Output file: one "mnemonic chars" line per seed
+fn output_content(results: &SecretVec<FoundSeed>) -> Zeroizing<String> {
+    let capacity = results.iter().map(|found| found.chars + 32).sum();
+    let mut output_content = Zeroizing::new(String::with_capacity(capacity));
+    for found in results.iter() {
+        writeln!(output_content, "{} {}", *found.mnemonic(), found.chars).unwrap();
+    }
+    output_content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn summary_never_reallocates_after_the_seed() {
+        let stats = RunStats::new(1, &[]);
+        stats.merge_scores(&mut vec![1; stats::MAX_SCORE + 1]);
+        stats.offer_best(FoundSeed::from_entropy([7; 16]));
+        let report = stats.score_report();
+
+        let content = summary_content(&stats, None, Duration::from_secs(1));
+        let header = format!("Run summary\nTime elapsed: {:?}\n\n", Duration::from_secs(1));
+        let capacity = header.len() + report.len() + record_capacity(&stats.best().unwrap()) + 128;
+        assert!(content.contains(&*stats.best().unwrap().mnemonic()));
+        assert!(content.ends_with(&report));
+        assert_eq!(content.capacity(), capacity);
+    }
+}
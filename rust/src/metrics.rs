@@ -0,0 +1,261 @@
+// Prometheus metrics endpoint
+// Serves GET /metrics (Prometheus text format 0.0.4) and GET /healthz from a background
+// thread. Only counters and resource data are exposed - never seeds or fingerprints.
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Response, Server};
+
+use crate::monitor::device_key;
+use crate::reporter::Reporter;
+use crate::stats::{Backend, PauseReason, RunStats};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+// Window for the instantaneous rate gauge
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+pub struct MetricsServer {
+    server: Server,
+    stats: Arc<RunStats>,
+    throttle_data: Arc<Mutex<HashMap<String, f64>>>,
+    reporter: Arc<Reporter>,
+    stop_flag: Arc<AtomicBool>,
+    // (time, iterations) samples covering the rate window
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl MetricsServer {
+    pub fn bind(
+        addr: &str,
+        stats: Arc<RunStats>,
+        throttle_data: Arc<Mutex<HashMap<String, f64>>>,
+        reporter: Arc<Reporter>,
+        stop_flag: Arc<AtomicBool>,
+    ) -> Result<Self, String> {
+        let server = Server::http(addr).map_err(|e| format!("Could not bind metrics endpoint {}: {}", addr, e))?;
+        Ok(Self {
+            server,
+            stats,
+            throttle_data,
+            reporter,
+            stop_flag,
+            samples: VecDeque::new(),
+        })
+    }
+
+    /// Serve requests on a background thread until the stop flag is set
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        std::thread::spawn(move || {
+            while !self.stop_flag.load(Ordering::Relaxed) {
+                self.sample();
+                let request = match self.server.recv_timeout(POLL_INTERVAL) {
+                    Ok(Some(request)) => request,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        self.reporter.notice(&format!("Warning: Metrics endpoint stopped: {}", e));
+                        break;
+                    }
+                };
+
+                let path = request.url().split('?').next().unwrap_or("");
+                let response = match path {
+                    "/metrics" => Response::from_string(self.render()).with_header(
+                        Header::from_bytes("Content-Type", "text/plain; version=0.0.4; charset=utf-8")
+                            .expect("valid header"),
+                    ),
+                    "/healthz" => Response::from_string("ok\n"),
+                    _ => Response::from_string("not found\n").with_status_code(404),
+                };
+                // A client that hung up is not our problem
+                request.respond(response).ok();
+            }
+        })
+    }
+
+    fn sample(&mut self) {
+        let now = Instant::now();
+        self.samples
+            .push_back((now, self.stats.iterations.load(Ordering::Relaxed)));
+        while self
+            .samples
+            .front()
+            .is_some_and(|(time, _)| now.duration_since(*time) > RATE_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    // Candidates per second over the rate window
+    fn rate(&self) -> f64 {
+        match (self.samples.front(), self.samples.back()) {
+            (Some((first_time, first)), Some((last_time, last))) if last_time > first_time => {
+                last.saturating_sub(*first) as f64 / last_time.duration_since(*first_time).as_secs_f64()
+            }
+            _ => self.stats.average_rate(),
+        }
+    }
+
+    fn render(&self) -> String {
+        let stats = &self.stats;
+        let mut out = String::new();
+
+        metric(&mut out, "gpuseed_iterations_total", "counter", "Candidates processed");
+        sample(&mut out, "gpuseed_iterations_total", "", stats.iterations.load(Ordering::Relaxed));
+
+        metric(&mut out, "gpuseed_backend_iterations_total", "counter", "Candidates processed per backend");
+        for backend in Backend::ALL {
+            let labels = format!("backend=\"{}\"", backend.name());
+            sample(&mut out, "gpuseed_backend_iterations_total", &labels, stats.backend_iterations(backend));
+        }
+
+        metric(&mut out, "gpuseed_worker_iterations_total", "counter", "Candidates processed per worker thread");
+        for (worker, counter) in stats.worker_iterations.iter().enumerate() {
+            let labels = format!("worker=\"{}\"", worker);
+            sample(&mut out, "gpuseed_worker_iterations_total", &labels, counter.load(Ordering::Relaxed));
+        }
+
+        metric(&mut out, "gpuseed_rate", "gauge", "Candidates per second over the last 10 seconds");
+        sample(&mut out, "gpuseed_rate", "", format!("{:.1}", self.rate()));
+
+        let found = stats.found_snapshot();
+        let mut buckets: Vec<(&usize, &usize)> = found.iter().collect();
+        buckets.sort();
+        metric(&mut out, "gpuseed_found", "gauge", "Seeds collected per character count");
+        for (chars, count) in &buckets {
+            sample(&mut out, "gpuseed_found", &format!("chars=\"{}\"", chars), count);
+        }
+        metric(&mut out, "gpuseed_found_total", "gauge", "Seeds collected");
+        sample(&mut out, "gpuseed_found_total", "", found.values().sum::<usize>());
+        if let Some((best, _)) = buckets.first() {
+            metric(&mut out, "gpuseed_best_score", "gauge", "Fewest characters of any collected seed");
+            sample(&mut out, "gpuseed_best_score", "", best);
+        }
+
+        if let Some(best) = stats.best_chars() {
+            metric(&mut out, "gpuseed_best_seen_chars", "gauge", "Fewest characters of any candidate, collected or not");
+            sample(&mut out, "gpuseed_best_seen_chars", "", best);
+        }
+        metric(&mut out, "gpuseed_scored_total", "counter", "Candidates per character count (merged about once a second)");
+        for (chars, count) in stats.score_snapshot().iter().enumerate().filter(|(_, &count)| count > 0) {
+            sample(&mut out, "gpuseed_scored_total", &format!("chars=\"{}\"", chars), count);
+        }
+
+        let data = self.throttle_data.lock().unwrap().clone();
+        metric(&mut out, "gpuseed_usage_ratio", "gauge", "CPU/GPU utilization (0-1)");
+        for resource in ["cpu", "gpu"] {
+            let value = data.get(&format!("{}_usage", resource)).copied().unwrap_or(0.0);
+            sample(&mut out, "gpuseed_usage_ratio", &format!("resource=\"{}\"", resource), value);
+        }
+        metric(&mut out, "gpuseed_throttle_factor", "gauge", "Batch size factor from the resource monitor (1 = unthrottled)");
+        for resource in ["cpu", "gpu"] {
+            let value = data.get(&format!("{}_throttle", resource)).copied().unwrap_or(1.0);
+            sample(&mut out, "gpuseed_throttle_factor", &format!("resource=\"{}\"", resource), value);
+        }
+
+        if !stats.devices.is_empty() {
+            metric(&mut out, "gpuseed_device_candidates_total", "counter", "Candidates generated per GPU");
+            for device in &stats.devices {
+                let labels = format!("device=\"{}\"", device.index);
+                sample(&mut out, "gpuseed_device_candidates_total", &labels, device.candidates.load(Ordering::Relaxed));
+            }
+            metric(&mut out, "gpuseed_device_usage_ratio", "gauge", "Utilization per GPU (0-1)");
+            for device in &stats.devices {
+                let labels = format!("device=\"{}\"", device.index);
+                let value = data.get(&device_key("gpu_usage", device.index)).copied().unwrap_or(0.0);
+                sample(&mut out, "gpuseed_device_usage_ratio", &labels, value);
+            }
+            metric(&mut out, "gpuseed_device_throttle_factor", "gauge", "Batch size factor per GPU (1 = unthrottled)");
+            for device in &stats.devices {
+                let labels = format!("device=\"{}\"", device.index);
+                let value = data.get(&device_key("gpu_throttle", device.index)).copied().unwrap_or(1.0);
+                sample(&mut out, "gpuseed_device_throttle_factor", &labels, value);
+            }
+        }
+
+        metric(&mut out, "gpuseed_gpu_batch_failures_total", "counter", "GPU batches that failed and fell back to the CPU");
+        sample(&mut out, "gpuseed_gpu_batch_failures_total", "", stats.gpu_failures.load(Ordering::Relaxed));
+
+        metric(&mut out, "gpuseed_paused", "gauge", "1 while the workers are paused");
+        sample(&mut out, "gpuseed_paused", "", stats.is_paused() as u8);
+        metric(&mut out, "gpuseed_pause_reason", "gauge", "1 while paused for this reason (manual, schedule, busy)");
+        for reason in PauseReason::ALL {
+            let labels = format!("reason=\"{}\"", reason.name());
+            sample(&mut out, "gpuseed_pause_reason", &labels, stats.is_paused_by(reason) as u8);
+        }
+
+        metric(&mut out, "gpuseed_uptime_seconds", "gauge", "Seconds since the search started");
+        sample(&mut out, "gpuseed_uptime_seconds", "", format!("{:.1}", stats.start.elapsed().as_secs_f64()));
+
+        out
+    }
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind).unwrap();
+}
+
+fn sample(out: &mut String, name: &str, labels: &str, value: impl std::fmt::Display) {
+    if labels.is_empty() {
+        writeln!(out, "{} {}", name, value).unwrap();
+    } else {
+        writeln!(out, "{}{{{}}} {}", name, labels, value).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu::DeviceSelection;
+    use crate::reporter::ConsoleMode;
+    use zeroize::Zeroizing;
+
+    #[test]
+    fn renders_prometheus_exposition() {
+        let stats = Arc::new(RunStats::new(1, &DeviceSelection::List(vec![0, 1]).simulated()));
+        stats.iterations.store(96, Ordering::Relaxed);
+        stats.add_batch(0, Backend::Gpu, 96);
+        stats.add_device_batch(1, 64);
+        stats.found_counts.lock().unwrap().insert(40, 2);
+        let throttle_data = Arc::new(Mutex::new(HashMap::from([(device_key("gpu_usage", 1), 0.5)])));
+        let server = MetricsServer::bind(
+            "127.0.0.1:0",
+            stats,
+            throttle_data,
+            Arc::new(Reporter::new(ConsoleMode::Redacted, Zeroizing::default())),
+            Arc::new(AtomicBool::new(false)),
+        )
+        .unwrap();
+        let text = server.render();
+        let lines: Vec<&str> = text.lines().collect();
+
+        for (name, kind) in [
+            ("gpuseed_iterations_total", "counter"),
+            ("gpuseed_rate", "gauge"),
+            ("gpuseed_found", "gauge"),
+            ("gpuseed_device_candidates_total", "counter"),
+            ("gpuseed_device_usage_ratio", "gauge"),
+        ] {
+            assert!(lines.contains(&format!("# TYPE {} {}", name, kind).as_str()), "{}", name);
+        }
+        assert!(lines.contains(&"gpuseed_iterations_total 96"));
+        assert!(lines.contains(&"gpuseed_backend_iterations_total{backend=\"gpu\"} 96"));
+        assert!(lines.contains(&"gpuseed_found{chars=\"40\"} 2"));
+        assert!(lines.contains(&"gpuseed_best_score 40"));
+        assert!(lines.contains(&"gpuseed_device_candidates_total{device=\"0\"} 0"));
+        assert!(lines.contains(&"gpuseed_device_candidates_total{device=\"1\"} 64"));
+        assert!(lines.contains(&"gpuseed_device_usage_ratio{device=\"1\"} 0.5"));
+        assert!(lines.contains(&"gpuseed_pause_reason{reason=\"manual\"} 0"));
+        // Every sample follows the HELP/TYPE header of its metric
+        let mut current = "";
+        for line in lines {
+            match line.strip_prefix("# TYPE ") {
+                Some(rest) => current = rest.split(' ').next().unwrap(),
+                None if line.starts_with("# HELP ") => {}
+                None => assert!(line.starts_with(current), "{} outside {}", line, current),
+            }
+        }
+    }
+}
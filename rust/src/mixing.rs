@@ -0,0 +1,358 @@
+// User entropy mixing
+// Every candidate is HMAC-SHA256(key = user key, msg = machine entropy || counter), truncated
+// to 128 bits, so a found seed depends on both the machine RNG and the user's dice rolls, coin
+// flips or hex string. The log records the machine entropy and counter of each seed plus a
+// salted commitment to the user entropy, so a seed can be recomputed and audited later.
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use bitcoin::hex::{DisplayHex, FromHex};
+use bip39::{Language, Mnemonic};
+use clap::{Args, ValueEnum};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use zeroize::Zeroizing;
+
+// Domain separation for the mixing key
+const KEY_DOMAIN: &[u8] = b"gpuseed/user-entropy/v1";
+// Below this estimate the user entropy alone could not stand in for the machine RNG
+const RECOMMENDED_BITS: f64 = 128.0;
+
+/// How user entropy is written
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntropyFormat {
+    /// Dice rolls, digits 1-6 (2.58 bits per roll)
+    Dice,
+    /// Coin flips, H/T or 1/0 (1 bit per flip)
+    Coins,
+    /// Hex string (4 bits per digit)
+    Hex,
+}
+
+impl EntropyFormat {
+    fn name(self) -> &'static str {
+        match self {
+            EntropyFormat::Dice => "dice",
+            EntropyFormat::Coins => "coins",
+            EntropyFormat::Hex => "hex",
+        }
+    }
+
+    fn bits_per_symbol(self) -> f64 {
+        match self {
+            EntropyFormat::Dice => 6f64.log2(),
+            EntropyFormat::Coins => 1.0,
+            EntropyFormat::Hex => 4.0,
+        }
+    }
+
+    // Canonical symbol for an input character; whitespace, commas and dashes are separators
+    fn symbol(self, c: char) -> Result<Option<char>, String> {
+        if c.is_whitespace() || c == ',' || c == '-' {
+            return Ok(None);
+        }
+        let symbol = match self {
+            EntropyFormat::Dice => ('1'..='6').contains(&c).then_some(c),
+            EntropyFormat::Coins => match c.to_ascii_uppercase() {
+                'H' | '1' => Some('1'),
+                'T' | '0' => Some('0'),
+                _ => None,
+            },
+            EntropyFormat::Hex => c.is_ascii_hexdigit().then(|| c.to_ascii_lowercase()),
+        };
+        symbol
+            .map(Some)
+            .ok_or_else(|| format!("Invalid character '{}' in {} entropy", c, self.name()))
+    }
+}
+
+/// Normalized user entropy, e.g. `dice:3615...`
+fn normalize(format: EntropyFormat, input: &str) -> Result<Zeroizing<String>, String> {
+    let mut normalized = Zeroizing::new(format!("{}:", format.name()));
+    for c in input.chars() {
+        if let Some(symbol) = format.symbol(c)? {
+            normalized.push(symbol);
+        }
+    }
+    if normalized.len() == format.name().len() + 1 {
+        return Err("User entropy is empty".to_string());
+    }
+    Ok(normalized)
+}
+
+/// Read user entropy from a file, or from a hidden prompt
+fn read_input(format: EntropyFormat, file: Option<&str>) -> Result<Zeroizing<String>, String> {
+    match file {
+        Some(path) => fs::read_to_string(path)
+            .map(Zeroizing::new)
+            .map_err(|e| format!("Could not read {}: {}", path, e)),
+        None => {
+            let prompt = match format {
+                EntropyFormat::Dice => "Dice rolls (1-6): ",
+                EntropyFormat::Coins => "Coin flips (H/T): ",
+                EntropyFormat::Hex => "Entropy (hex): ",
+            };
+            rpassword::prompt_password(prompt)
+                .map(Zeroizing::new)
+                .map_err(|e| format!("Could not read user entropy: {}", e))
+        }
+    }
+}
+
+/// How a mixed candidate was built: enough to recompute it given the user entropy
+#[derive(Clone, Copy)]
+pub struct MixRecord {
+    pub machine_entropy: [u8; 16],
+    pub counter: u64,
+}
+
+/// Salted SHA-256 commitment to the normalized user entropy
+#[derive(Clone, Copy)]
+pub struct Commitment {
+    pub salt: [u8; 16],
+    pub hash: [u8; 32],
+}
+
+impl Commitment {
+    fn new(salt: [u8; 16], normalized: &str) -> Self {
+        let mut engine = sha256::Hash::engine();
+        engine.input(&salt);
+        engine.input(normalized.as_bytes());
+        Self {
+            salt,
+            hash: sha256::Hash::from_engine(engine).to_byte_array(),
+        }
+    }
+}
+
+impl std::fmt::Display for Commitment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "sha256 {} salt {}",
+            self.hash.to_lower_hex_string(),
+            self.salt.to_lower_hex_string()
+        )
+    }
+}
+
+pub struct UserEntropy {
+    key: Zeroizing<[u8; 32]>,
+    pub commitment: Commitment,
+    pub bits: f64,
+    // Next per-candidate counter; claimed in ranges, one per batch
+    counter: AtomicU64,
+}
+
+impl UserEntropy {
+    pub fn new(format: EntropyFormat, input: &str, salt: [u8; 16]) -> Result<Self, String> {
+        let normalized = normalize(format, input)?;
+        let symbols = normalized.len() - format.name().len() - 1;
+
+        let mut engine = sha256::Hash::engine();
+        engine.input(KEY_DOMAIN);
+        engine.input(normalized.as_bytes());
+        Ok(Self {
+            key: Zeroizing::new(sha256::Hash::from_engine(engine).to_byte_array()),
+            commitment: Commitment::new(salt, &normalized),
+            bits: symbols as f64 * format.bits_per_symbol(),
+            counter: AtomicU64::new(0),
+        })
+    }
+
+    /// Read user entropy (file or prompt) and commit to it with a fresh random salt
+    pub fn load(format: EntropyFormat, file: Option<&str>) -> Result<Self, String> {
+        let input = read_input(format, file)?;
+        let mut salt = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let user_entropy = Self::new(format, &input, salt)?;
+        if user_entropy.bits < RECOMMENDED_BITS {
+            eprintln!(
+                "Warning: User entropy is only ~{:.0} bits; {:.0}+ bits are needed for it to stand on its own",
+                user_entropy.bits, RECOMMENDED_BITS
+            );
+        }
+        Ok(user_entropy)
+    }
+
+    /// Claim counters for a batch of `count` candidates
+    pub fn claim(&self, count: usize) -> u64 {
+        self.counter.fetch_add(count as u64, Ordering::Relaxed)
+    }
+
+    /// Candidate entropy = HMAC-SHA256(user key, machine entropy || counter)[..16]
+    pub fn mix(&self, machine_entropy: &[u8; 16], counter: u64) -> [u8; 16] {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.key.as_slice()).expect("HMAC accepts any key length");
+        mac.update(machine_entropy);
+        mac.update(&counter.to_be_bytes());
+        let digest = Zeroizing::new(<[u8; 32]>::from(mac.finalize().into_bytes()));
+        let mut entropy = [0u8; 16];
+        entropy.copy_from_slice(&digest[..16]);
+        entropy
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct VerifyArgs {
+    /// Log file written by a run with --user-entropy
+    #[arg(long, default_value = "mnemonics_log.txt")]
+    input: String,
+
+    /// How the user entropy is written
+    #[arg(long, value_enum)]
+    format: EntropyFormat,
+
+    /// Read the user entropy from a file instead of prompting
+    #[arg(long)]
+    file: Option<String>,
+}
+
+/// `verify-entropy` subcommand: check the commitment and recompute every mixed seed in a log
+pub fn verify(args: &VerifyArgs) -> Result<(), String> {
+    let content = Zeroizing::new(
+        fs::read_to_string(&args.input).map_err(|e| format!("Could not read {}: {}", args.input, e))?,
+    );
+    let input = read_input(args.format, args.file.as_deref())?;
+
+    let mut checked = 0;
+    let mut failed = 0;
+    let mut mnemonic: Option<Mnemonic> = None;
+    let mut record: Option<MixRecord> = None;
+    for line in content.lines() {
+        if let Some(phrase) = line.strip_prefix("Mnemonic:") {
+            mnemonic = Mnemonic::parse_in_normalized(Language::English, phrase.trim()).ok();
+            record = None;
+        } else if let Some(rest) = line.strip_prefix("Mixed from machine entropy ") {
+            record = parse_record(rest);
+        } else if let Some(rest) = line.strip_prefix("User entropy commitment: ") {
+            let (Some(ref m), Some(mix)) = (&mnemonic, record) else {
+                continue;
+            };
+            let commitment = parse_commitment(rest)
+                .ok_or_else(|| format!("Invalid commitment line: {}", line))?;
+            let user_entropy = UserEntropy::new(args.format, &input, commitment.salt)?;
+
+            checked += 1;
+            let (mut entropy, len) = m.to_entropy_array();
+            let commitment_ok = user_entropy.commitment.hash == commitment.hash;
+            let seed_ok = user_entropy.mix(&mix.machine_entropy, mix.counter)[..] == entropy[..len];
+            zeroize::Zeroize::zeroize(&mut entropy);
+            let id = format!("counter {}", mix.counter);
+            match (commitment_ok, seed_ok) {
+                (true, true) => println!("OK    {}", id),
+                (false, _) => {
+                    failed += 1;
+                    println!("FAIL  {}: commitment does not match this user entropy", id);
+                }
+                (true, false) => {
+                    failed += 1;
+                    println!("FAIL  {}: seed does not match machine entropy, counter and user entropy", id);
+                }
+            }
+        }
+    }
+
+    if checked == 0 {
+        return Err(format!("No mixed seeds found in {}", args.input));
+    }
+    println!("\n{} of {} seeds verified", checked - failed, checked);
+    if failed > 0 {
+        return Err(format!("{} seeds failed verification", failed));
+    }
+    Ok(())
+}
+
+// "<hex>, counter <n>"
+fn parse_record(rest: &str) -> Option<MixRecord> {
+    let (hex, counter) = rest.split_once(", counter ")?;
+    let bytes = <[u8; 16]>::from_hex(hex.trim()).ok()?;
+    Some(MixRecord {
+        machine_entropy: bytes,
+        counter: counter.trim().parse().ok()?,
+    })
+}
+
+// "sha256 <hex> salt <hex>"
+fn parse_commitment(rest: &str) -> Option<Commitment> {
+    let rest = rest.trim().strip_prefix("sha256 ")?;
+    let (hash, salt) = rest.split_once(" salt ")?;
+    Some(Commitment {
+        salt: <[u8; 16]>::from_hex(salt.trim()).ok()?,
+        hash: <[u8; 32]>::from_hex(hash.trim()).ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worker::FoundSeed;
+
+    const DICE: &str = "3615 2444 1662 5361 4253 3116";
+    const SALT: [u8; 16] = [7; 16];
+
+    #[test]
+    fn mixes_deterministically() {
+        let machine = [0x5a; 16];
+        let user = UserEntropy::new(EntropyFormat::Dice, DICE, SALT).unwrap();
+        // Separators do not matter, the symbols do
+        let same = UserEntropy::new(EntropyFormat::Dice, &DICE.replace(' ', ","), [9; 16]).unwrap();
+        assert_eq!(user.mix(&machine, 42), same.mix(&machine, 42));
+        assert_eq!(user.bits, 24.0 * 6f64.log2());
+
+        let other = UserEntropy::new(EntropyFormat::Dice, &DICE.replace("3116", "3115"), SALT).unwrap();
+        assert_ne!(user.mix(&machine, 42), other.mix(&machine, 42));
+        assert_ne!(user.mix(&machine, 42), user.mix(&machine, 43));
+        assert_ne!(user.mix(&machine, 42), user.mix(&[0x5b; 16], 42));
+
+        // Coin flips in either notation, and invalid input
+        let heads = UserEntropy::new(EntropyFormat::Coins, "HTTH", SALT).unwrap();
+        let digits = UserEntropy::new(EntropyFormat::Coins, "1001", SALT).unwrap();
+        assert_eq!(heads.mix(&machine, 0), digits.mix(&machine, 0));
+        assert!(UserEntropy::new(EntropyFormat::Dice, "1237", SALT).is_err());
+        assert!(UserEntropy::new(EntropyFormat::Hex, " - ", SALT).is_err());
+    }
+
+    #[test]
+    fn commitment_round_trips() {
+        let user = UserEntropy::new(EntropyFormat::Dice, DICE, SALT).unwrap();
+        let line = user.commitment.to_string();
+        let parsed = parse_commitment(&line).unwrap();
+        assert_eq!((parsed.salt, parsed.hash), (SALT, user.commitment.hash));
+
+        // The same entropy under the recorded salt reveals the commitment, other entropy does not
+        let reveal = UserEntropy::new(EntropyFormat::Dice, DICE, parsed.salt).unwrap();
+        assert_eq!(reveal.commitment.hash, parsed.hash);
+        let tampered = UserEntropy::new(EntropyFormat::Dice, &DICE.replace("3116", "3115"), parsed.salt).unwrap();
+        assert_ne!(tampered.commitment.hash, parsed.hash);
+        assert!(parse_commitment("sha256 00 salt 00").is_none());
+    }
+
+    #[test]
+    fn verifies_a_logged_seed() {
+        let dir = std::env::temp_dir().join(format!("gpuseed-mixing-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let user = UserEntropy::new(EntropyFormat::Dice, DICE, SALT).unwrap();
+        let mix = MixRecord { machine_entropy: [0x33; 16], counter: user.claim(5) + 3 };
+        let found = FoundSeed {
+            mix: Some(mix),
+            ..FoundSeed::from_entropy(user.mix(&mix.machine_entropy, mix.counter))
+        };
+        let mut log = Zeroizing::new(String::with_capacity(crate::record_capacity(&found)));
+        crate::describe_seed(&mut log, &found, Some(user.commitment));
+        let input = dir.join("log.txt");
+        fs::write(&input, log.as_bytes()).unwrap();
+
+        let verify_with = |dice: &str| {
+            let file = dir.join("dice.txt");
+            fs::write(&file, dice).unwrap();
+            verify(&VerifyArgs {
+                input: input.to_str().unwrap().to_string(),
+                format: EntropyFormat::Dice,
+                file: Some(file.to_str().unwrap().to_string()),
+            })
+        };
+        assert!(verify_with(DICE).is_ok());
+        assert_eq!(verify_with("1111").err().unwrap(), "1 seeds failed verification");
+    }
+}
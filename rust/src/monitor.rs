@@ -7,7 +7,11 @@ use sysinfo::System;
 #[cfg(feature = "gpu-monitoring")]
 use nvml_wrapper::NVML;
 
-const MAX_USAGE_PERCENT: f64 = 0.80;
+use crate::gpu::GpuDevice;
+use crate::reporter::Reporter;
+
+// Default usage cap; "max_usage" in the throttle data overrides it (config file, SIGHUP)
+pub const MAX_USAGE_PERCENT: f64 = 0.80;
 const MONITOR_INTERVAL: Duration = Duration::from_millis(500);
 
 pub struct ResourceMonitor {
@@ -21,44 +25,50 @@ pub struct ResourceMonitor {
 struct GpuMonitor {
     #[cfg(feature = "gpu-monitoring")]
     nvml: NVML,
-    #[cfg(feature = "gpu-monitoring")]
-    device_index: u32,
+    // The devices of the run (--devices)
+    devices: Vec<GpuDevice>,
 }
 
 impl GpuMonitor {
     #[cfg(feature = "gpu-monitoring")]
-    fn new() -> Option<Self> {
+    fn new(devices: Vec<GpuDevice>, reporter: &Reporter) -> Option<Self> {
+        if devices.is_empty() {
+            return None;
+        }
         match NVML::init() {
             Ok(nvml) => {
                 match nvml.device_count() {
                     Ok(count) if count > 0 => {
-                        println!("GPU monitoring initialized (NVML)");
-                        Some(Self {
-                            nvml,
-                            device_index: 0,
-                        })
+                        reporter.notice(&format!("GPU monitoring initialized (NVML, {} device(s))", devices.len()));
+                        Some(Self { nvml, devices })
                     }
                     _ => {
-                        eprintln!("No GPU devices found for monitoring");
+                        reporter.notice("No GPU devices found for monitoring");
                         None
                     }
                 }
             }
             Err(e) => {
-                eprintln!("Failed to initialize NVML: {:?}", e);
+                reporter.notice(&format!("Failed to initialize NVML: {:?}", e));
                 None
             }
         }
     }
     
     #[cfg(not(feature = "gpu-monitoring"))]
-    fn new() -> Option<Self> {
+    fn new(_devices: Vec<GpuDevice>, _reporter: &Reporter) -> Option<Self> {
         None
     }
     
     #[cfg(feature = "gpu-monitoring")]
-    fn get_usage(&self) -> f64 {
-        match self.nvml.device_by_index(self.device_index) {
+    fn get_usage(&self, device: &GpuDevice) -> f64 {
+        // Same device by PCI bus id; NVML and CUDA only agree on indices with
+        // CUDA_DEVICE_ORDER=PCI_BUS_ID
+        let nvml_device = match device.pci_bus_id {
+            Some(ref bus_id) => self.nvml.device_by_pci_bus_id(bus_id.as_str()),
+            None => self.nvml.device_by_index(device.index),
+        };
+        match nvml_device {
             Ok(device) => {
                 match device.utilization_rates() {
                     Ok(utilization) => utilization.gpu as f64 / 100.0,
@@ -70,20 +80,23 @@ impl GpuMonitor {
     }
     
     #[cfg(not(feature = "gpu-monitoring"))]
-    fn get_usage(&self) -> f64 {
+    fn get_usage(&self, _device: &GpuDevice) -> f64 {
         0.0
     }
 }
 
 impl ResourceMonitor {
+    /// Watches the CPU and the GPUs in `devices`
     pub fn new(
         stop_flag: Arc<AtomicBool>,
         throttle_data: Arc<Mutex<HashMap<String, f64>>>,
+        devices: Vec<GpuDevice>,
+        reporter: &Reporter,
     ) -> Self {
         let mut system = System::new();
         system.refresh_cpu_all();
         
-        let gpu_monitor = GpuMonitor::new();
+        let gpu_monitor = GpuMonitor::new(devices, reporter);
         
         Self {
             stop_flag,
@@ -96,6 +109,7 @@ impl ResourceMonitor {
     pub fn run(mut self) {
         while !self.stop_flag.load(Ordering::Relaxed) {
             self.system.refresh_cpu_all();
+            let max_usage = max_usage(&self.throttle_data);
             
             // Calculate CPU usage
             let cpu_count = self.system.cpus().len();
@@ -110,23 +124,30 @@ impl ResourceMonitor {
             };
             
             // Calculate throttle factor
-            let cpu_throttle = if cpu_usage > MAX_USAGE_PERCENT {
-                MAX_USAGE_PERCENT / cpu_usage
+            let cpu_throttle = if cpu_usage > max_usage {
+                max_usage / cpu_usage
             } else {
                 1.0
             };
             
-            // Get GPU usage (currently always 0.0 because GPU entropy generation not implemented)
-            let gpu_usage = self.gpu_monitor.as_ref()
-                .map(|m| m.get_usage())
-                .unwrap_or(0.0);
-            
-            // Calculate GPU throttle factor
-            let gpu_throttle = if gpu_usage > MAX_USAGE_PERCENT {
-                MAX_USAGE_PERCENT / gpu_usage
-            } else {
-                1.0
-            };
+            // Usage and throttle factor of every GPU; each feeder follows its own device,
+            // "gpu_usage"/"gpu_throttle" are the busiest device's
+            let mut gpu_usage = 0.0;
+            let mut gpu_throttle = 1.0;
+            let mut devices = Vec::new();
+            if let Some(ref monitor) = self.gpu_monitor {
+                for device in &monitor.devices {
+                    let usage = monitor.get_usage(device);
+                    let throttle = if usage > max_usage {
+                        max_usage / usage
+                    } else {
+                        1.0
+                    };
+                    gpu_usage = f64::max(gpu_usage, usage);
+                    gpu_throttle = f64::min(gpu_throttle, throttle);
+                    devices.push((device.index, usage, throttle));
+                }
+            }
             
             // Update throttle data
             let mut data = self.throttle_data.lock().unwrap();
@@ -134,9 +155,32 @@ impl ResourceMonitor {
             data.insert("gpu_usage".to_string(), gpu_usage);
             data.insert("cpu_throttle".to_string(), cpu_throttle);
             data.insert("gpu_throttle".to_string(), gpu_throttle);
+            for (index, usage, throttle) in devices {
+                data.insert(device_key("gpu_usage", index), usage);
+                data.insert(device_key("gpu_throttle", index), throttle);
+            }
+            drop(data);
             
             std::thread::sleep(MONITOR_INTERVAL);
         }
     }
 }
 
+/// Throttle data key of one GPU ("gpu_usage.1", "gpu_throttle.1")
+pub fn device_key(key: &str, device: u32) -> String {
+    format!("{}.{}", key, device)
+}
+
+/// Current usage cap (fraction of CPU / GPU)
+pub fn max_usage(throttle_data: &Mutex<HashMap<String, f64>>) -> f64 {
+    throttle_data.lock().unwrap().get("max_usage").copied().unwrap_or(MAX_USAGE_PERCENT)
+}
+
+/// Change the usage cap, given in percent
+pub fn set_max_usage(throttle_data: &Mutex<HashMap<String, f64>>, percent: f64) -> Result<(), String> {
+    if !(1.0..=100.0).contains(&percent) {
+        return Err("Resource limit must be between 1 and 100 percent".to_string());
+    }
+    throttle_data.lock().unwrap().insert("max_usage".to_string(), percent / 100.0);
+    Ok(())
+}
@@ -0,0 +1,535 @@
+// Partitioned search (--partition k/n) and merging its results
+// Machines that cannot talk to each other split one candidate stream between them: the stream
+// is keyed by the shared secret (the same ChaCha20 work units a distributed search uses) and its
+// units are cut into n contiguous ranges, one per partition. Each run records its partition and
+// the units it completed at the top of its log file; `merge` combines the logs of all partitions
+// and checks that every partition is present and no two runs searched the same unit.
+use clap::Args;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+use crate::cluster::{ClusterSecret, UnitKey};
+use crate::results;
+use crate::secure::SecretVec;
+use crate::worker::FoundSeed;
+
+const SEPARATOR: &str = "----------------------------------";
+
+/// Partition k of n (1-based)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Partition {
+    pub index: u64,
+    pub count: u64,
+}
+
+impl Partition {
+    /// First unit of the partition and one past its last
+    pub fn units(&self) -> (u64, u64) {
+        let bound = |k: u64| ((k as u128) << 64) / self.count as u128;
+        (bound(self.index - 1) as u64, bound(self.index).min(u64::MAX as u128) as u64)
+    }
+}
+
+impl FromStr for Partition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let error = || format!("Invalid partition \"{}\" (expected k/n with 1 <= k <= n, e.g. 2/4)", s);
+        let (index, count) = s.split_once('/').ok_or_else(error)?;
+        let index: u64 = index.trim().parse().map_err(|_| error())?;
+        let count: u64 = count.trim().parse().map_err(|_| error())?;
+        if index == 0 || index > count {
+            return Err(error());
+        }
+        Ok(Self { index, count })
+    }
+}
+
+impl fmt::Display for Partition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+/// This run's share of a partitioned search; unit numbers are absolute, the searched
+/// units are recorded relative to the start of the partition
+pub struct PartitionRun {
+    pub partition: Partition,
+    pub key: UnitKey,
+    pub unit_size: u64,
+    run_id: String,
+    next: AtomicU64,
+    searched: Mutex<BTreeSet<u64>>,
+}
+
+impl PartitionRun {
+    pub fn new(partition: Partition, secret: &ClusterSecret, unit_size: u64) -> Self {
+        let (start, _) = partition.units();
+        Self {
+            partition,
+            key: secret.partition_key(),
+            unit_size,
+            run_id: rand::random::<[u8; 8]>().iter().map(|b| format!("{:02x}", b)).collect(),
+            next: AtomicU64::new(start),
+            searched: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Continue the run recorded in an earlier log of this partition: its run id and searched
+    /// units carry over, and the units are not handed out again. Returns the seeds of that log
+    /// (none if the file does not exist yet)
+    pub fn resume(&mut self, path: &str) -> Result<SecretVec<FoundSeed>, String> {
+        if !Path::new(path).exists() {
+            return Ok(SecretVec::default());
+        }
+        let log = read_log(path).map_err(|e| format!("{} (use another --logfile)", e))?;
+        if (log.stream.as_str(), log.partition, log.unit_size)
+            != (self.key.fingerprint().as_str(), self.partition, self.unit_size)
+        {
+            return Err(format!(
+                "{} belongs to partition {} of stream {} with {}-candidate units, not to this run (use another --logfile)",
+                path, log.partition, log.stream, log.unit_size
+            ));
+        }
+        self.run_id = log.run_id;
+        *self.searched.get_mut().unwrap() = log.searched;
+        Ok(log.seeds)
+    }
+
+    /// Units already searched (by the run this one resumed)
+    pub fn searched(&self) -> usize {
+        self.searched.lock().unwrap().len()
+    }
+
+    /// Next unit of the partition, None once all of them were handed out
+    pub fn claim(&self) -> Option<u64> {
+        let (start, end) = self.partition.units();
+        loop {
+            let unit = self.next.fetch_add(1, Ordering::Relaxed);
+            if unit >= end {
+                return None;
+            }
+            if !self.searched.lock().unwrap().contains(&(unit - start)) {
+                return Some(unit);
+            }
+        }
+    }
+
+    pub fn complete(&self, unit: u64) {
+        let (start, _) = self.partition.units();
+        self.searched.lock().unwrap().insert(unit - start);
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "partition {} of stream {} ({} candidates per work unit)",
+            self.partition,
+            self.key.fingerprint(),
+            self.unit_size
+        )
+    }
+
+    pub fn summary(&self) -> String {
+        let searched = self.searched.lock().unwrap().len() as u64;
+        format!(
+            "partition {}, {} units searched ({} candidates)",
+            self.partition,
+            searched,
+            crate::format_number(searched * self.unit_size)
+        )
+    }
+
+    /// Record of the run at the top of its log file (read back by `merge`)
+    pub fn header(&self) -> String {
+        format!(
+            "Partition: {}\nStream: {}\nUnit size: {}\nRun: {}\nSearched units: {}\n{}\n",
+            self.partition,
+            self.key.fingerprint(),
+            self.unit_size,
+            self.run_id,
+            format_ranges(&self.searched.lock().unwrap()),
+            SEPARATOR
+        )
+    }
+}
+
+// "0-46, 48" (or "none")
+fn format_ranges(units: &BTreeSet<u64>) -> String {
+    let mut ranges: Vec<(u64, u64)> = Vec::new();
+    for &unit in units {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == unit => *last = unit,
+            _ => ranges.push((unit, unit)),
+        }
+    }
+    if ranges.is_empty() {
+        return "none".to_string();
+    }
+    ranges
+        .iter()
+        .map(|&(first, last)| if first == last { first.to_string() } else { format!("{}-{}", first, last) })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn parse_ranges(s: &str) -> Result<BTreeSet<u64>, String> {
+    let mut units = BTreeSet::new();
+    if s == "none" {
+        return Ok(units);
+    }
+    for range in s.split(',') {
+        let range = range.trim();
+        let (first, last) = range.split_once('-').unwrap_or((range, range));
+        let parse = |n: &str| n.parse::<u64>().map_err(|_| format!("invalid unit range \"{}\"", range));
+        let (first, last) = (parse(first)?, parse(last)?);
+        if first > last {
+            return Err(format!("invalid unit range \"{}\"", range));
+        }
+        units.extend(first..=last);
+    }
+    Ok(units)
+}
+
+#[derive(Args, Debug)]
+pub struct MergeArgs {
+    /// Log files of the partitioned runs (--logfile of each run)
+    #[arg(required = true)]
+    inputs: Vec<String>,
+
+    /// Detailed log file of the merged seeds
+    #[arg(long, default_value = "merged_log.txt")]
+    logfile: String,
+
+    /// Simple output file of the merged seeds
+    #[arg(long, default_value = "merged_seeds.txt")]
+    output: String,
+
+    /// Shared secret file: re-derive every seed from its recorded work unit
+    #[arg(long, value_name = "FILE")]
+    cluster_secret: Option<String>,
+}
+
+// Everything `merge` needs from one log file
+struct RunLog {
+    path: String,
+    partition: Partition,
+    stream: String,
+    unit_size: u64,
+    run_id: String,
+    searched: BTreeSet<u64>,
+    seeds: SecretVec<FoundSeed>,
+}
+
+// Units searched by one run and the files that recorded them
+type RunCoverage<'a> = (BTreeSet<u64>, Vec<&'a str>);
+
+fn read_log(path: &str) -> Result<RunLog, String> {
+    let content = Zeroizing::new(fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))?);
+    let invalid = |line: &str| format!("Invalid line in {}: {}", path, line);
+
+    let (mut partition, mut stream, mut unit_size, mut run_id, mut searched) = (None, None, None, None, None);
+    let mut seeds = SecretVec::default();
+    for line in content.lines() {
+        if let Some(value) = line.strip_prefix("Partition: ") {
+            partition = Some(value.parse::<Partition>()?);
+        } else if let Some(value) = line.strip_prefix("Stream: ") {
+            stream = Some(value.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("Unit size: ") {
+            unit_size = Some(value.trim().parse::<u64>().map_err(|_| invalid(line))?);
+        } else if let Some(value) = line.strip_prefix("Run: ") {
+            run_id = Some(value.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("Searched units: ") {
+            searched = Some(parse_ranges(value.trim()).map_err(|e| format!("{} in {}", e, path))?);
+        } else if line.starts_with("Mnemonic:") {
+            let entropy = results::parse_line(line).ok_or_else(|| invalid("Mnemonic: ..."))?;
+            seeds.push(FoundSeed::from_entropy(*entropy));
+        } else if let Some(value) = line.strip_prefix("Work unit: ") {
+            let position = value
+                .split_once(", candidate ")
+                .and_then(|(unit, offset)| Some((unit.parse().ok()?, offset.parse().ok()?)))
+                .ok_or_else(|| invalid(line))?;
+            let seed = seeds.iter_mut().last().ok_or_else(|| invalid(line))?;
+            seed.unit = Some(position);
+        }
+    }
+
+    let missing = |name: &str| format!("{} is not the log of a partitioned run (no {} line)", path, name);
+    Ok(RunLog {
+        path: path.to_string(),
+        partition: partition.ok_or_else(|| missing("Partition"))?,
+        stream: stream.ok_or_else(|| missing("Stream"))?,
+        unit_size: unit_size.ok_or_else(|| missing("Unit size"))?,
+        run_id: run_id.ok_or_else(|| missing("Run"))?,
+        searched: searched.ok_or_else(|| missing("Searched units"))?,
+        seeds,
+    })
+}
+
+/// Merge the logs of a partitioned search and verify its coverage
+pub fn merge(args: &MergeArgs) -> Result<(), String> {
+    let logs = args.inputs.iter().map(|path| read_log(path)).collect::<Result<Vec<_>, _>>()?;
+    let first = &logs[0];
+    for log in &logs[1..] {
+        if (log.stream.as_str(), log.partition.count, log.unit_size)
+            != (first.stream.as_str(), first.partition.count, first.unit_size)
+        {
+            return Err(format!(
+                "{} and {} belong to different searches (stream {} in {} partitions of {}-candidate units vs. stream {} in {} of {})",
+                first.path, log.path, first.stream, first.partition.count, first.unit_size,
+                log.stream, log.partition.count, log.unit_size
+            ));
+        }
+    }
+    let key = match args.cluster_secret {
+        Some(ref path) => {
+            let key = ClusterSecret::load(path)?.partition_key();
+            if key.fingerprint() != first.stream {
+                return Err(format!("The secret in {} does not belong to stream {}", path, first.stream));
+            }
+            Some(key)
+        }
+        None => None,
+    };
+
+    let mut problems = Vec::new();
+    let mut merged: SecretVec<FoundSeed> = SecretVec::default();
+    let mut verified = 0;
+    // Units searched per partition and run; the same run may appear in several files
+    let mut coverage: BTreeMap<u64, BTreeMap<&str, RunCoverage>> = BTreeMap::new();
+    for log in &logs {
+        let (run_units, paths) = coverage
+            .entry(log.partition.index)
+            .or_default()
+            .entry(log.run_id.as_str())
+            .or_default();
+        run_units.extend(log.searched.iter().copied());
+        paths.push(log.path.as_str());
+
+        let (start, end) = log.partition.units();
+        for seed in log.seeds.iter() {
+            if merged.iter().any(|found| found.entropy == seed.entropy) {
+                continue;
+            }
+            match (seed.unit, key.as_ref()) {
+                (Some((unit, _)), _) if unit < start || unit >= end => problems.push(format!(
+                    "A seed in {} comes from unit {}, outside partition {}",
+                    log.path, unit, log.partition
+                )),
+                (Some((unit, offset)), Some(key)) => {
+                    if key.entropy(unit, offset, 1)[0] == seed.entropy {
+                        verified += 1;
+                    } else {
+                        problems.push(format!(
+                            "A seed in {} is not candidate {} of unit {}",
+                            log.path, offset, unit
+                        ));
+                    }
+                }
+                (None, Some(_)) => problems.push(format!("A seed in {} has no work unit recorded", log.path)),
+                _ => {}
+            }
+            merged.push(seed.clone());
+        }
+    }
+
+    println!(
+        "Stream {}: {} partitions, {} candidates per work unit",
+        first.stream, first.partition.count, first.unit_size
+    );
+    let mut total = 0;
+    for index in 1..=first.partition.count {
+        let partition = Partition { index, count: first.partition.count };
+        let Some(runs) = coverage.get(&index) else {
+            println!("  Partition {}: MISSING", partition);
+            problems.push(format!("Partition {} has no log", partition));
+            continue;
+        };
+        let mut units = BTreeSet::new();
+        for (run_id, (run_units, paths)) in runs {
+            let overlap: BTreeSet<u64> = units.intersection(run_units).copied().collect();
+            if !overlap.is_empty() {
+                problems.push(format!(
+                    "Partition {}: units {} were searched again by run {} ({})",
+                    partition,
+                    format_ranges(&overlap),
+                    run_id,
+                    paths.join(", ")
+                ));
+            }
+            units.extend(run_units.iter().copied());
+        }
+        let seeds = merged
+            .iter()
+            .filter(|found| found.unit.is_some_and(|(unit, _)| {
+                let (start, end) = partition.units();
+                unit >= start && unit < end
+            }))
+            .count();
+        total += units.len() as u64 * first.unit_size;
+        println!(
+            "  Partition {}: {} run(s), units {} ({} candidates), {} seeds",
+            partition,
+            runs.len(),
+            format_ranges(&units),
+            crate::format_number(units.len() as u64 * first.unit_size),
+            seeds
+        );
+    }
+    println!("Candidates searched: {}", crate::format_number(total));
+    if key.is_some() {
+        println!("Seeds re-derived from their work units: {} of {}", verified, merged.len());
+    }
+
+    let capacity = 128 + merged.iter().map(|found| crate::record_capacity(found) + SEPARATOR.len()).sum::<usize>();
+    let mut log_content = Zeroizing::new(String::with_capacity(capacity));
+    writeln!(
+        log_content,
+        "Merged partitions of stream {} ({} partitions)\n{}",
+        first.stream, first.partition.count, SEPARATOR
+    )
+    .unwrap();
+    for found in merged.iter() {
+        crate::describe_seed(&mut log_content, found, None);
+        writeln!(log_content, "{}", SEPARATOR).unwrap();
+    }
+    fs::write(&args.logfile, log_content.as_bytes()).map_err(|e| format!("Could not write {}: {}", args.logfile, e))?;
+    fs::write(&args.output, crate::output_content(&merged).as_bytes())
+        .map_err(|e| format!("Could not write {}: {}", args.output, e))?;
+    println!("Saved {} seeds to {} and {}", merged.len(), args.logfile, args.output);
+
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("  {}", problem);
+        }
+        return Err(format!("Coverage check failed: {} problem(s) found", problems.len()));
+    }
+    println!("Coverage check passed: every partition present, no unit searched twice");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn secret() -> ClusterSecret {
+        let dir = temp_dir("secret");
+        let path = dir.join("search.key");
+        fs::write(&path, "partition test secret\n").unwrap();
+        ClusterSecret::load(path.to_str().unwrap()).unwrap()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gpuseed-partition-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn partitions_cover_the_units_without_overlap() {
+        // Each range starts where the one before ends, none is empty, and together they cover
+        // the stream (the last unit, u64::MAX, is the exclusive end)
+        let check = |count: u64, indexes: &mut dyn Iterator<Item = u64>| {
+            for index in indexes {
+                let (start, end) = Partition { index, count }.units();
+                assert!(start < end, "partition {}/{} is empty", index, count);
+                let previous_end = if index == 1 { 0 } else { Partition { index: index - 1, count }.units().1 };
+                assert_eq!(start, previous_end, "gap or overlap before {}/{}", index, count);
+            }
+            assert_eq!(Partition { index: count, count }.units().1, u64::MAX);
+        };
+        for count in (1..=50).chain([1000, 4096]) {
+            check(count, &mut (1..=count));
+        }
+        check(u64::MAX, &mut (1..=3).chain(u64::MAX - 2..=u64::MAX));
+    }
+
+    #[test]
+    fn formats_and_parses_unit_ranges() {
+        let cases: [(&[u64], &str); 4] = [
+            (&[], "none"),
+            (&[7], "7"),
+            (&[0, 1, 2, 3, 5, 7, 8], "0-3, 5, 7-8"),
+            (&[u64::MAX - 1, u64::MAX], "18446744073709551614-18446744073709551615"),
+        ];
+        for (units, text) in cases {
+            let units: BTreeSet<u64> = units.iter().copied().collect();
+            assert_eq!(format_ranges(&units), text);
+            assert_eq!(parse_ranges(text).unwrap(), units);
+        }
+        for invalid in ["", "3-1", "1-", "a", "1, x"] {
+            assert!(parse_ranges(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn merge_detects_units_searched_twice() {
+        let dir = temp_dir("merge");
+        let secret = secret();
+        let log = |name: &str, partition: &str, units: &[u64]| {
+            let mut run = PartitionRun::new(partition.parse().unwrap(), &secret, 10);
+            for &unit in units {
+                let unit = run.partition.units().0 + unit;
+                run.complete(unit);
+            }
+            // A fresh run id for every file, as for runs started from scratch
+            run.run_id = name.to_string();
+            let path = dir.join(name);
+            fs::write(&path, run.header()).unwrap();
+            path.to_str().unwrap().to_string()
+        };
+        let merge_args = |inputs: Vec<String>| MergeArgs {
+            inputs,
+            logfile: dir.join("merged_log.txt").to_str().unwrap().to_string(),
+            output: dir.join("merged_seeds.txt").to_str().unwrap().to_string(),
+            cluster_secret: None,
+        };
+
+        let first = log("first", "1/2", &[0, 1, 2]);
+        let second = log("second", "2/2", &[0, 1]);
+        assert!(merge(&merge_args(vec![first.clone(), second.clone()])).is_ok());
+        // Partition 1 restarted from scratch: units 1-2 searched again
+        let again = log("again", "1/2", &[1, 2, 3]);
+        assert!(merge(&merge_args(vec![first.clone(), second.clone(), again])).is_err());
+        // A missing partition fails as well
+        assert!(merge(&merge_args(vec![first])).is_err());
+    }
+
+    #[test]
+    fn resumes_the_searched_units_of_a_log() {
+        let dir = temp_dir("resume");
+        let secret = secret();
+        let partition: Partition = "2/3".parse().unwrap();
+        let (start, _) = partition.units();
+        let path = dir.join("log.txt");
+        let path = path.to_str().unwrap();
+        fs::remove_file(path).ok();
+
+        let mut run = PartitionRun::new(partition, &secret, 10);
+        assert!(run.resume(path).unwrap().is_empty());
+        for _ in 0..3 {
+            let unit = run.claim().unwrap();
+            run.complete(unit);
+        }
+        run.complete(start + 5);
+        fs::write(path, run.header()).unwrap();
+
+        let mut resumed = PartitionRun::new(partition, &secret, 10);
+        resumed.resume(path).unwrap();
+        assert_eq!(resumed.run_id, run.run_id);
+        assert_eq!(resumed.searched(), 4);
+        let claimed: Vec<u64> = (0..3).map(|_| resumed.claim().unwrap() - start).collect();
+        assert_eq!(claimed, [3, 4, 6]);
+
+        // The log of another partition or unit size is not taken over
+        assert!(PartitionRun::new("1/3".parse().unwrap(), &secret, 10).resume(path).is_err());
+        assert!(PartitionRun::new(partition, &secret, 20).resume(path).is_err());
+    }
+}
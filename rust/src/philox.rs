@@ -0,0 +1,150 @@
+// Philox4x32-10 on the host, exactly as cuRAND runs it (`--gpu-rng philox`)
+// Philox is counter-based: block n of a stream is a keyed bijection of the counter n, so any
+// candidate the GPU generated can be recomputed here from the seed and its thread index. The
+// tests check the round function against the Random123 known-answer vectors, and `gpu-check`
+// compares a GPU batch with this implementation.
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::gpu::GpuContext;
+
+const M0: u32 = 0xD251_1F53;
+const M1: u32 = 0xCD9E_8D57;
+const W0: u32 = 0x9E37_79B9;
+const W1: u32 = 0xBB67_AE85;
+
+/// Philox4x32-10 block function
+pub fn block(counter: [u32; 4], key: [u32; 2]) -> [u32; 4] {
+    let [mut x, mut y, mut z, mut w] = counter;
+    let [mut k0, mut k1] = key;
+    for round in 0..10 {
+        if round > 0 {
+            k0 = k0.wrapping_add(W0);
+            k1 = k1.wrapping_add(W1);
+        }
+        let product0 = M0 as u64 * x as u64;
+        let product1 = M1 as u64 * z as u64;
+        (x, y, z, w) = (
+            (product1 >> 32) as u32 ^ y ^ k0,
+            product1 as u32,
+            (product0 >> 32) as u32 ^ w ^ k1,
+            product0 as u32,
+        );
+    }
+    [x, y, z, w]
+}
+
+/// One stream: `curand_init(seed, subsequence, 0, &state)` followed by `curand4` calls
+pub struct Philox {
+    counter: [u32; 4],
+    key: [u32; 2],
+}
+
+impl Philox {
+    pub fn new(seed: u64, subsequence: u64) -> Self {
+        Self {
+            counter: [0, 0, subsequence as u32, (subsequence >> 32) as u32],
+            key: [seed as u32, (seed >> 32) as u32],
+        }
+    }
+
+    /// The next 128 bits (`curand4`)
+    pub fn next_block(&mut self) -> [u32; 4] {
+        let output = block(self.counter, self.key);
+        // 128-bit increment, lowest word first
+        for word in self.counter.iter_mut() {
+            *word = word.wrapping_add(1);
+            if *word != 0 {
+                break;
+            }
+        }
+        output
+    }
+
+    /// One candidate, as the kernels write it: the four words in little-endian order
+    pub fn fill_entropy(&mut self, entropy: &mut [u8; 16]) {
+        let mut words = Zeroizing::new(self.next_block());
+        for (chunk, word) in entropy.chunks_exact_mut(4).zip(words.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        words.zeroize();
+    }
+}
+
+impl Drop for Philox {
+    fn drop(&mut self) {
+        // The key and counter determine every candidate of the stream
+        self.counter.zeroize();
+        self.key.zeroize();
+    }
+}
+
+/// First candidate of each of `count` GPU threads after `init_philox_states(seed, subsequence)`
+pub fn first_candidates(count: usize, seed: u64, subsequence: u64) -> Zeroizing<Vec<[u8; 16]>> {
+    let mut entropies = Zeroizing::new(vec![[0u8; 16]; count]);
+    for (index, entropy) in entropies.iter_mut().enumerate() {
+        Philox::new(seed, subsequence.wrapping_add(index as u64)).fill_entropy(entropy);
+    }
+    entropies
+}
+
+/// `gpu-check`: a GPU batch against this implementation
+pub fn check(gpu: &GpuContext, count: usize) -> Result<(), String> {
+    let mut os_rng = rand::rngs::OsRng;
+    let (seed, subsequence) = (rand::RngCore::next_u64(&mut os_rng), rand::RngCore::next_u64(&mut os_rng));
+    let generated = gpu.philox_entropies(count, seed, subsequence)?;
+    let expected = first_candidates(count, seed, subsequence);
+    if let Some(index) = (0..count).find(|&index| generated[index] != expected[index]) {
+        return Err(format!("GPU Philox output differs from the host implementation at candidate {}", index));
+    }
+    println!("GPU Philox4x32-10: {} candidates match the host implementation", count);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_random123_known_answers() {
+        // Random123 kat_vectors, philox4x32 with 10 rounds: counter, key, output
+        let known_answers: [([u32; 4], [u32; 2], [u32; 4]); 3] = [
+            ([0, 0, 0, 0], [0, 0], [0x6627e8d5, 0xe169c58d, 0xbc57ac4c, 0x9b00dbd8]),
+            (
+                [0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff],
+                [0xffffffff, 0xffffffff],
+                [0x408f276d, 0x41c83b0e, 0xa20bc7c6, 0x6d5451fd],
+            ),
+            (
+                [0x243f6a88, 0x85a308d3, 0x13198a2e, 0x03707344],
+                [0xa4093822, 0x299f31d0],
+                [0xd16cfe09, 0x94fdcceb, 0x5001e420, 0x24126ea1],
+            ),
+        ];
+        for (counter, key, expected) in known_answers {
+            assert_eq!(block(counter, key), expected, "counter {:08x?}, key {:08x?}", counter, key);
+        }
+    }
+
+    #[test]
+    fn carries_the_counter_across_words() {
+        let key = [0x1234_5678, 0x9abc_def0];
+        let mut stream = Philox {
+            counter: [u32::MAX - 1, 7, 3, 4],
+            key,
+        };
+        assert_eq!(stream.next_block(), block([u32::MAX - 1, 7, 3, 4], key));
+        assert_eq!(stream.next_block(), block([u32::MAX, 7, 3, 4], key));
+        // The low word wraps into the next one
+        assert_eq!(stream.next_block(), block([0, 8, 3, 4], key));
+        assert_eq!(stream.counter, [1, 8, 3, 4]);
+
+        // A carry through several words, and the subsequence words are not touched otherwise
+        stream.counter = [u32::MAX, u32::MAX, u32::MAX, 4];
+        stream.next_block();
+        assert_eq!(stream.counter, [0, 0, 0, 5]);
+
+        // The subsequence is the upper half of the counter
+        let stream = Philox::new(1, u32::MAX as u64 + 1);
+        assert_eq!(stream.counter, [0, 0, 0, 1]);
+    }
+}
@@ -0,0 +1,191 @@
+// Steel plate / stamping templates
+// BIP39 English words are unique in their first four letters, so only those get
+// stamped. Words are numbered and placed column by column on the plate grid.
+use clap::ValueEnum;
+use std::fmt::Write;
+use zeroize::Zeroizing;
+
+use crate::worker::FoundSeed;
+
+// Letters stamped per word
+const ABBREVIATION_LEN: usize = 4;
+
+/// Word grid of common metal backup plates (columns x rows)
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlateLayout {
+    /// 1 column of 12 words (capsule / strip style)
+    Single,
+    /// 2 columns of 6 words (most 12-word stamping plates)
+    Double,
+    /// 3 columns of 4 words
+    Triple,
+    /// 4 columns of 3 words (washer / tile grids)
+    Quad,
+}
+
+impl PlateLayout {
+    pub fn columns(self) -> usize {
+        match self {
+            PlateLayout::Single => 1,
+            PlateLayout::Double => 2,
+            PlateLayout::Triple => 3,
+            PlateLayout::Quad => 4,
+        }
+    }
+}
+
+pub struct PlateWord {
+    pub number: usize,
+    pub word: Zeroizing<String>,
+    pub abbreviation: Zeroizing<String>,
+    pub column: usize,
+    pub row: usize,
+}
+
+/// Words of a seed with their abbreviation and plate position (1-based)
+pub fn plate_words(found: &FoundSeed, layout: PlateLayout) -> Vec<PlateWord> {
+    let mnemonic = found.mnemonic();
+    let words: Vec<&str> = mnemonic.split(' ').collect();
+    let rows = words.len().div_ceil(layout.columns());
+
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            // BIP39 English words are ASCII
+            let mut abbreviation = Zeroizing::new(String::with_capacity(ABBREVIATION_LEN));
+            abbreviation.extend(word.chars().take(ABBREVIATION_LEN).map(|c| c.to_ascii_uppercase()));
+            PlateWord {
+                number: i + 1,
+                word: Zeroizing::new(word.to_string()),
+                abbreviation,
+                column: i / rows + 1,
+                row: i % rows + 1,
+            }
+        })
+        .collect()
+}
+
+/// Plain text template: position table, character breakdown and stamping grid
+pub fn render_text(found: &FoundSeed, layout: PlateLayout) -> Zeroizing<String> {
+    let words = plate_words(found, layout);
+    let stamped: usize = words.iter().map(|w| w.abbreviation.len()).sum();
+    // Sized up front: the buffer holds the phrase and must not leave copies when growing
+    let mut out = Zeroizing::new(String::with_capacity(128 + words.len() * 96));
+
+    out.push_str(" #  Word      Stamp  Chars  Position\n");
+    for w in &words {
+        writeln!(
+            out,
+            "{:>2}  {:<9} {:<5}  {:>5}  col {} row {}",
+            w.number,
+            *w.word,
+            *w.abbreviation,
+            w.word.len(),
+            w.column,
+            w.row
+        )
+        .unwrap();
+    }
+    writeln!(out, "Total: {} characters in full words, {} letters to stamp\n", found.chars, stamped).unwrap();
+
+    // Plate preview, one line per row
+    let rows = words.iter().map(|w| w.row).max().unwrap_or(0);
+    for row in 1..=rows {
+        for (i, w) in words.iter().filter(|w| w.row == row).enumerate() {
+            if i > 0 {
+                out.push_str("   ");
+            }
+            write!(out, "{:>2} {:<4}", w.number, *w.abbreviation).unwrap();
+        }
+        let len = out.trim_end().len();
+        out.truncate(len);
+        out.push('\n');
+    }
+    out
+}
+
+/// Printable A4 SVG (dimensions in millimetres) with one box per letter to stamp
+pub fn render_svg(found: &FoundSeed, layout: PlateLayout, title: &str) -> Zeroizing<String> {
+    const PAGE_WIDTH: f64 = 210.0;
+    const PAGE_HEIGHT: f64 = 297.0;
+    const MAX_CELL: f64 = 9.0;
+    const MARGIN: f64 = 20.0;
+    const NUMBER_WIDTH: f64 = 10.0;
+    const COLUMN_GAP: f64 = 8.0;
+    const ROW_HEIGHT: f64 = 16.0;
+
+    let words = plate_words(found, layout);
+    let columns = layout.columns();
+    let rows = words.iter().map(|w| w.row).max().unwrap_or(0);
+    // Letter boxes shrink so wide layouts still fit the page
+    let available = PAGE_WIDTH - 2.0 * MARGIN - 20.0 - (columns - 1) as f64 * COLUMN_GAP;
+    let cell = ((available / columns as f64 - NUMBER_WIDTH) / ABBREVIATION_LEN as f64).min(MAX_CELL);
+    let column_width = NUMBER_WIDTH + cell * ABBREVIATION_LEN as f64;
+    let plate_width = columns as f64 * column_width + (columns - 1) as f64 * COLUMN_GAP + 20.0;
+    let plate_height = rows as f64 * ROW_HEIGHT + 20.0;
+    let left = (PAGE_WIDTH - plate_width) / 2.0;
+    let top = 40.0;
+
+    // Sized up front like the text template (about 1 KB per word)
+    let mut svg = Zeroizing::new(String::with_capacity(1024 + title.len() + words.len() * 1536));
+    write!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}mm\" height=\"{h}mm\" viewBox=\"0 0 {w} {h}\" font-family=\"monospace\">\n\
+         <rect width=\"{w}\" height=\"{h}\" fill=\"#fff\"/>\n\
+         <text x=\"{cx}\" y=\"20\" font-size=\"6\" text-anchor=\"middle\">{title}</text>\n\
+         <text x=\"{cx}\" y=\"28\" font-size=\"3.5\" text-anchor=\"middle\">{chars} characters - stamp the first 4 letters of each word</text>\n\
+         <rect x=\"{left:.1}\" y=\"{top:.1}\" width=\"{pw:.1}\" height=\"{ph:.1}\" rx=\"4\" fill=\"none\" stroke=\"#000\" stroke-width=\"0.5\"/>\n",
+        w = PAGE_WIDTH,
+        h = PAGE_HEIGHT,
+        cx = PAGE_WIDTH / 2.0,
+        title = title,
+        chars = found.chars,
+        left = left,
+        top = top,
+        pw = plate_width,
+        ph = plate_height,
+    )
+    .unwrap();
+
+    for w in &words {
+        let x = left + 10.0 + (w.column - 1) as f64 * (column_width + COLUMN_GAP);
+        let y = top + 10.0 + (w.row - 1) as f64 * ROW_HEIGHT;
+        writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"4\">{:>2}</text>",
+            x,
+            y + cell * 0.7,
+            w.number
+        )
+        .unwrap();
+        for (i, letter) in w.abbreviation.chars().enumerate() {
+            let cx = x + NUMBER_WIDTH + i as f64 * cell;
+            writeln!(
+                svg,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{c:.1}\" height=\"{c:.1}\" fill=\"none\" stroke=\"#000\" stroke-width=\"0.3\"/>\
+                 <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+                cx,
+                y,
+                cx + cell / 2.0,
+                y + cell * 0.75,
+                cell * 0.67,
+                letter,
+                c = cell
+            )
+            .unwrap();
+        }
+        writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"2.5\" fill=\"#666\">{} ({})</text>",
+            x + NUMBER_WIDTH,
+            y + cell + 3.0,
+            *w.word,
+            w.word.len()
+        )
+        .unwrap();
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
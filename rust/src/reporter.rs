@@ -0,0 +1,192 @@
+// Console reporting
+// All run-time console output (found seeds, status line, worker notices) goes through
+// the Reporter so secrets are only printed when the console mode allows it.
+use bitcoin::hashes::{sha256, Hash};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use zeroize::Zeroizing;
+
+use crate::keys;
+use crate::status_json::StatusStream;
+use crate::webhook::Webhook;
+use crate::worker::FoundSeed;
+
+/// How found seeds are shown on the console
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConsoleMode {
+    /// Only the character count and a short hash of the entropy
+    Redacted,
+    /// Character count and BIP32 master key fingerprint
+    Fingerprint,
+    /// Full mnemonic phrase
+    Full,
+}
+
+pub struct Reporter {
+    mode: ConsoleMode,
+    // BIP39 passphrase, so fingerprints match what a hardware wallet shows
+    passphrase: Zeroizing<String>,
+    // Recent lines while the dashboard owns the terminal (None: print directly).
+    // Also serializes console writes so lines from different threads never interleave.
+    events: Mutex<Option<VecDeque<Zeroizing<String>>>>,
+    // NDJSON event records (--status-json)
+    stream: Option<Arc<StatusStream>>,
+    // Found records for an HTTP endpoint (--webhook)
+    webhook: Option<Arc<Webhook>>,
+    // Length of the last status line
+    status_width: AtomicUsize,
+}
+
+// Lines kept for the dashboard event log
+const MAX_EVENTS: usize = 200;
+
+impl Reporter {
+    pub fn new(mode: ConsoleMode, passphrase: Zeroizing<String>) -> Self {
+        Self {
+            mode,
+            passphrase,
+            events: Mutex::new(None),
+            stream: None,
+            webhook: None,
+            status_width: AtomicUsize::new(0),
+        }
+    }
+
+    /// Also emit event records to a status stream
+    pub fn with_status_stream(mut self, stream: Arc<StatusStream>) -> Self {
+        self.stream = Some(stream);
+        self
+    }
+
+    /// Also send found records to a webhook
+    pub fn with_webhook(mut self, webhook: Arc<Webhook>) -> Self {
+        self.webhook = Some(webhook);
+        self
+    }
+
+    /// Structured event for the status stream (no console output)
+    pub fn event(&self, event: &str, fields: Value) {
+        if let Some(ref stream) = self.stream {
+            if let Err(e) = stream.event(event, fields) {
+                self.notice(&e);
+            }
+        }
+    }
+
+    /// Keep lines for the dashboard instead of printing them (and print nothing meanwhile)
+    pub fn capture(&self, enabled: bool) {
+        *self.events.lock().unwrap() = enabled.then(VecDeque::new);
+    }
+
+    /// Most recent captured lines, oldest first
+    pub fn recent_events(&self, count: usize) -> Vec<Zeroizing<String>> {
+        match *self.events.lock().unwrap() {
+            Some(ref events) => events.iter().skip(events.len().saturating_sub(count)).cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Report a newly collected seed
+    pub fn found(&self, found: &FoundSeed) {
+        // Share lengths and BIP85 indices are no secret, so every mode shows them
+        let extra = found
+            .shares
+            .as_ref()
+            .map(|split| format!(" | longest SLIP-39 share {}", split.longest_share_chars()))
+            .unwrap_or_default();
+        let extra = match found.bip85_index {
+            Some(index) => format!(" | BIP85 index {}{}", index, extra),
+            None => extra,
+        };
+        // The status stream follows the console mode: secrets only in full mode
+        let mut record = json!({
+            "chars": found.chars,
+            "id": short_hash(&found.entropy),
+            "bip85_index": found.bip85_index,
+            "longest_share": found.shares.as_ref().map(|split| split.longest_share_chars()),
+        });
+        let lines = match self.mode {
+            ConsoleMode::Full => vec![
+                Zeroizing::new(format!("Mnemonic: {}", *found.mnemonic())),
+                Zeroizing::new(format!("Total characters: {}{}", found.chars, extra)),
+            ],
+            ConsoleMode::Fingerprint => {
+                let fingerprint = keys::master_fingerprint(&found.entropy, &self.passphrase)
+                    .map(|fp| fp.to_string())
+                    .unwrap_or_else(|_| "????????".to_string());
+                record["fingerprint"] = json!(fingerprint);
+                vec![Zeroizing::new(format!(
+                    "Found: {} characters | fingerprint {}{}",
+                    found.chars, fingerprint, extra
+                ))]
+            }
+            ConsoleMode::Redacted => vec![Zeroizing::new(format!(
+                "Found: {} characters | id {}{}",
+                found.chars,
+                short_hash(&found.entropy),
+                extra
+            ))],
+        };
+
+        if self.mode == ConsoleMode::Full && self.stream.is_some() {
+            record["mnemonic"] = json!(found.mnemonic().as_str());
+        }
+        self.event("found", record);
+        if let Some(ref webhook) = self.webhook {
+            if let Err(e) = webhook.found(found) {
+                self.notice(&e);
+            }
+        }
+
+        let mut events = self.events.lock().unwrap();
+        match *events {
+            Some(ref mut events) => lines.into_iter().for_each(|line| push_event(events, line)),
+            None => {
+                println!();
+                for line in &lines {
+                    println!("{}", **line);
+                }
+            }
+        }
+    }
+
+    /// Overwrite the single status line (the dashboard shows its own status)
+    pub fn status(&self, line: &str) {
+        let events = self.events.lock().unwrap();
+        if events.is_none() {
+            // Pad to the previous length so a shorter line leaves no stale text behind
+            let width = self.status_width.swap(line.len(), Ordering::Relaxed);
+            print!("\r{:<width$}", line, width = width);
+            io::stdout().flush().ok();
+        }
+    }
+
+    /// Diagnostic notice (backend selection, fallbacks, ...)
+    pub fn notice(&self, message: &str) {
+        let mut events = self.events.lock().unwrap();
+        match *events {
+            Some(ref mut events) => push_event(events, Zeroizing::new(message.trim().to_string())),
+            None => eprintln!("{}", message),
+        }
+    }
+}
+
+fn push_event(events: &mut VecDeque<Zeroizing<String>>, line: Zeroizing<String>) {
+    if events.len() == MAX_EVENTS {
+        events.pop_front();
+    }
+    events.push_back(line);
+}
+
+/// Short, non-reversible identifier for a seed (first 4 bytes of SHA-256 of the entropy)
+pub fn short_hash(entropy: &[u8; 16]) -> String {
+    let hash = sha256::Hash::hash(entropy);
+    hash.as_byte_array()[..4]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
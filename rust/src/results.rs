@@ -0,0 +1,50 @@
+// Loading results files written by the generator
+// Accepts both the simple output file ("<12 words> <chars>") and the detailed
+// log file ("Mnemonic: <12 words>" blocks); other lines are ignored.
+use bip39::{Language, Mnemonic};
+use std::fs;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::secure::SecretVec;
+use crate::worker::FoundSeed;
+
+pub fn load_results(path: &str) -> Result<SecretVec<FoundSeed>, String> {
+    let content = Zeroizing::new(
+        fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))?,
+    );
+
+    let mut results = SecretVec::default();
+    for line in content.lines() {
+        let Some(entropy) = parse_line(line) else {
+            continue;
+        };
+        // The log and output files list the same seeds - keep each once
+        if !results.iter().any(|found: &FoundSeed| found.entropy == *entropy) {
+            results.push(FoundSeed::from_entropy(*entropy));
+        }
+    }
+
+    if results.is_empty() {
+        return Err(format!("No 12-word mnemonics found in {}", path));
+    }
+    Ok(results)
+}
+
+/// Entropy of the mnemonic on one line of a results file, if it has one
+pub fn parse_line(line: &str) -> Option<Zeroizing<[u8; 16]>> {
+    let line = line.trim();
+    let line = line.strip_prefix("Mnemonic:").unwrap_or(line);
+
+    let words: Vec<&str> = line.split_whitespace().take(12).collect();
+    if words.len() != 12 {
+        return None;
+    }
+    let phrase = Zeroizing::new(words.join(" "));
+    let mnemonic = Mnemonic::parse_in_normalized(Language::English, &phrase).ok()?;
+
+    let (mut entropy_bytes, len) = mnemonic.to_entropy_array();
+    let mut entropy = Zeroizing::new([0u8; 16]);
+    entropy.copy_from_slice(&entropy_bytes[..len]);
+    entropy_bytes.zeroize();
+    Some(entropy)
+}
@@ -0,0 +1,332 @@
+// Run windows and idle-only operation
+// The scheduler pauses the workers outside the configured time windows and, with
+// --idle-only, while other processes keep the CPU busy. Pausing only sets a pause reason in
+// RunStats; workers, status line, dashboard, metrics and the status stream all pick it up.
+use chrono::{Datelike, Local, NaiveDateTime, NaiveTime, Weekday};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::reporter::Reporter;
+use crate::stats::{PauseReason, RunStats};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// A recurring time window, e.g. "mon-fri 19:00-07:00". Windows that end before their start
+/// run past midnight; the days name the day a window starts on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Window {
+    // Indexed by days from Monday
+    days: [bool; 7],
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl Window {
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        let today = now.weekday().num_days_from_monday() as usize;
+        let yesterday = (today + 6) % 7;
+        let time = now.time();
+        if self.start < self.end {
+            self.days[today] && time >= self.start && time < self.end
+        } else {
+            (self.days[today] && time >= self.start) || (self.days[yesterday] && time < self.end)
+        }
+    }
+}
+
+fn parse_day(s: &str) -> Result<usize, String> {
+    s.parse::<Weekday>()
+        .map(|day| day.num_days_from_monday() as usize)
+        .map_err(|_| format!("Invalid day '{}'", s))
+}
+
+fn parse_days(s: &str) -> Result<[bool; 7], String> {
+    let mut days = [false; 7];
+    match s {
+        "daily" => days = [true; 7],
+        "weekdays" => days[..5].fill(true),
+        "weekends" => days[5..].fill(true),
+        _ => {
+            for part in s.split(',') {
+                match part.split_once('-') {
+                    // Ranges may wrap around the week (fri-mon)
+                    Some((first, last)) => {
+                        let (first, last) = (parse_day(first)?, parse_day(last)?);
+                        let mut day = first;
+                        loop {
+                            days[day] = true;
+                            if day == last {
+                                break;
+                            }
+                            day = (day + 1) % 7;
+                        }
+                    }
+                    None => days[parse_day(part)?] = true,
+                }
+            }
+        }
+    }
+    Ok(days)
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime, String> {
+    // 24:00 is a common way to write the end of the day
+    if s == "24:00" {
+        return Ok(NaiveTime::MIN);
+    }
+    NaiveTime::parse_from_str(s, "%H:%M").map_err(|_| format!("Invalid time '{}' (expected HH:MM)", s))
+}
+
+impl FromStr for Window {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let (days, times) = match s.rsplit_once(' ') {
+            Some((days, times)) => (parse_days(days.trim())?, times),
+            None => ([true; 7], s.as_str()),
+        };
+        let (start, end) = times
+            .split_once('-')
+            .ok_or_else(|| format!("Invalid window '{}' (expected e.g. \"mon-fri 19:00-07:00\")", s))?;
+        let (start_time, end_time) = (parse_time(start)?, parse_time(end)?);
+        // Equal bounds would be an empty window; only 00:00-24:00 (the whole day) ends where it
+        // starts
+        if start_time == end_time && (end != "24:00" || start == "24:00") {
+            return Err(format!("Invalid window '{}' (start and end are equal)", s));
+        }
+        Ok(Self {
+            days,
+            start: start_time,
+            end: end_time,
+        })
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days: Vec<&str> = DAY_NAMES
+            .iter()
+            .zip(self.days)
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect();
+        if days.len() < 7 {
+            write!(f, "{} ", days.join(","))?;
+        }
+        let end = if self.end == NaiveTime::MIN {
+            "24:00".to_string()
+        } else {
+            self.end.format("%H:%M").to_string()
+        };
+        write!(f, "{}-{}", self.start.format("%H:%M"), end)
+    }
+}
+
+// CPU used by other processes, so the search only runs on an otherwise idle machine.
+// The load average is no help here: it counts our own busy workers too.
+struct IdleCheck {
+    system: System,
+    pid: Option<Pid>,
+    // Fraction of the whole machine other processes may use
+    threshold: f64,
+    // How long the machine must stay idle before the workers resume
+    resume_delay: Duration,
+    busy_since: Option<Instant>,
+}
+
+impl IdleCheck {
+    fn new(threshold: f64, resume_delay: Duration) -> Self {
+        let pid = sysinfo::get_current_pid().ok();
+        let mut system = System::new();
+        system.refresh_cpu_usage();
+        if let Some(pid) = pid {
+            system.refresh_processes_specifics(ProcessesToUpdate::Some(&[pid]), ProcessRefreshKind::new().with_cpu());
+        }
+        // Usage is the difference between two samples; an earlier second sample reads as idle
+        std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        Self {
+            system,
+            pid,
+            threshold,
+            resume_delay,
+            busy_since: None,
+        }
+    }
+
+    // Fraction of the machine used by everything except this process
+    fn other_usage(&mut self) -> f64 {
+        self.system.refresh_cpu_usage();
+        let total = self.system.global_cpu_usage() as f64 / 100.0;
+        let own = match self.pid {
+            Some(pid) => {
+                self.system
+                    .refresh_processes_specifics(ProcessesToUpdate::Some(&[pid]), ProcessRefreshKind::new().with_cpu());
+                let cpus = self.system.cpus().len().max(1) as f64;
+                self.system
+                    .process(pid)
+                    .map_or(0.0, |process| process.cpu_usage() as f64 / 100.0 / cpus)
+            }
+            None => 0.0,
+        };
+        (total - own).max(0.0)
+    }
+
+    /// Whether the workers should stay paused
+    fn busy(&mut self) -> bool {
+        if self.other_usage() > self.threshold {
+            self.busy_since = Some(Instant::now());
+        }
+        self.busy_since.is_some_and(|since| since.elapsed() < self.resume_delay)
+    }
+}
+
+pub struct Scheduler {
+    windows: Vec<Window>,
+    idle: Option<IdleCheck>,
+    stats: Arc<RunStats>,
+    reporter: Arc<Reporter>,
+    stop_flag: Arc<AtomicBool>,
+}
+
+impl Scheduler {
+    /// `idle_threshold`: pause while other processes use more than this fraction of the CPU
+    pub fn new(
+        windows: Vec<Window>,
+        idle_threshold: Option<f64>,
+        resume_delay: Duration,
+        stats: Arc<RunStats>,
+        reporter: Arc<Reporter>,
+        stop_flag: Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            windows,
+            idle: idle_threshold.map(|threshold| IdleCheck::new(threshold, resume_delay)),
+            stats,
+            reporter,
+            stop_flag,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.windows.is_empty() || self.idle.is_some()
+    }
+
+    /// Evaluate the windows once (before the workers start, so they never run outside them)
+    pub fn update(&mut self) {
+        if !self.windows.is_empty() {
+            let now = Local::now().naive_local();
+            let inside = self.windows.iter().any(|window| window.contains(now));
+            self.set(PauseReason::Schedule, !inside);
+        }
+        if let Some(ref mut idle) = self.idle {
+            let busy = idle.busy();
+            self.set(PauseReason::Busy, busy);
+        }
+    }
+
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        std::thread::spawn(move || {
+            while !self.stop_flag.load(Ordering::Relaxed) {
+                std::thread::sleep(CHECK_INTERVAL);
+                self.update();
+            }
+        })
+    }
+
+    fn set(&self, reason: PauseReason, paused: bool) {
+        if self.stats.set_pause(reason, paused) == paused {
+            return;
+        }
+        let message = match (reason, paused) {
+            (PauseReason::Schedule, true) => "[Schedule] Paused: outside the run windows".to_string(),
+            (PauseReason::Schedule, false) => "[Schedule] Resumed: run window started".to_string(),
+            (_, true) => format!(
+                "[Schedule] Paused: other processes are using the CPU (over {:.0}%)",
+                self.idle.as_ref().map_or(0.0, |idle| idle.threshold * 100.0)
+            ),
+            (_, false) => "[Schedule] Resumed: machine is idle".to_string(),
+        };
+        self.reporter.notice(&message);
+        self.reporter.event(
+            if paused { "paused" } else { "resumed" },
+            serde_json::json!({ "reason": reason.name() }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    // 2026-10-19 is a Monday
+    fn at(day: u32, time: &str) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 19 + day)
+            .unwrap()
+            .and_time(NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+    }
+
+    #[test]
+    fn parses_windows() {
+        for (text, shown) in [
+            ("09:00-17:00", "09:00-17:00"),
+            (" Mon-Fri 19:00-07:00 ", "mon,tue,wed,thu,fri 19:00-07:00"),
+            ("fri-mon 22:00-24:00", "mon,fri,sat,sun 22:00-24:00"),
+            ("weekends 00:00-24:00", "sat,sun 00:00-24:00"),
+            ("sat,tue 08:00-09:30", "tue,sat 08:00-09:30"),
+        ] {
+            assert_eq!(text.parse::<Window>().unwrap().to_string(), shown);
+        }
+        for invalid in [
+            "",
+            "09:00",
+            "9-17",
+            "25:00-07:00",
+            "mon-fry 09:00-17:00",
+            "mon 09:00-17:00-18:00",
+            "08:00-08:00",
+            "00:00-00:00",
+            "24:00-24:00",
+            "24:00-00:00",
+        ] {
+            assert!(invalid.parse::<Window>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn matches_times() {
+        let office: Window = "mon-fri 09:00-17:00".parse().unwrap();
+        assert!(office.contains(at(0, "09:00")));
+        assert!(office.contains(at(4, "16:59")));
+        assert!(!office.contains(at(0, "17:00")));
+        assert!(!office.contains(at(0, "08:59")));
+        assert!(!office.contains(at(5, "12:00")));
+
+        // Belongs to the day it starts on
+        let night: Window = "fri 22:00-06:00".parse().unwrap();
+        assert!(night.contains(at(4, "22:00")));
+        assert!(night.contains(at(5, "05:59")));
+        assert!(!night.contains(at(5, "06:00")));
+        assert!(!night.contains(at(4, "05:00")));
+        assert!(!night.contains(at(5, "22:00")));
+
+        let evening: Window = "mon 20:00-24:00".parse().unwrap();
+        assert!(evening.contains(at(0, "23:59")));
+        assert!(!evening.contains(at(1, "00:00")));
+        assert!(!evening.contains(at(0, "19:59")));
+
+        let weekend: Window = "weekends 00:00-24:00".parse().unwrap();
+        assert!(weekend.contains(at(5, "00:00")));
+        assert!(weekend.contains(at(6, "23:59")));
+        assert!(!weekend.contains(at(0, "00:00")));
+        assert!(!weekend.contains(at(4, "23:59")));
+    }
+}
@@ -0,0 +1,251 @@
+// Candidate scoring as the GPU does it (kernels `score_random` / `score_entropies` in
+// kernels/gpuseed.cu)
+// The kernel computes the BIP39 checksum with its own single-block SHA-256, extracts the 12
+// word indices and sums their lengths from a table, so only candidates under the threshold
+// (plus the score histogram and the best candidate) are copied back to the host. This module
+// is the same algorithm on the CPU, step for step: `gpu-check` compares the kernel against it,
+// and it is checked itself against the bip39 crate, so it also works on machines without a GPU.
+use bip39::{Language, Mnemonic};
+use rand::RngCore;
+use std::sync::Arc;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::gpu::{GpuContext, GpuRng};
+use crate::philox;
+use crate::reporter::{ConsoleMode, Reporter};
+use crate::stats::MAX_SCORE;
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const SHA256_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Length of every English BIP39 word, indexed by word number (uploaded to the device)
+pub fn word_lengths() -> [u8; 2048] {
+    let mut lengths = [0u8; 2048];
+    for (length, word) in lengths.iter_mut().zip(Language::English.word_list()) {
+        *length = word.len() as u8;
+    }
+    lengths
+}
+
+// First word of the SHA-256 digest of a 16-byte message (one padded block), which holds the
+// 4 checksum bits of a 12-word mnemonic
+fn sha256_first_word(entropy: &[u8; 16]) -> u32 {
+    let mut w = [0u32; 64];
+    for (i, chunk) in entropy.chunks_exact(4).enumerate() {
+        w[i] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    w[4] = 0x8000_0000;
+    w[15] = 128;
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+    }
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = SHA256_IV;
+    for i in 0..64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(SHA256_K[i]).wrapping_add(w[i]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+        (h, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+    }
+    w.zeroize();
+    a.wrapping_add(SHA256_IV[0])
+}
+
+/// Total characters of the 12-word mnemonic of `entropy`
+pub fn score(entropy: &[u8; 16], lengths: &[u8; 2048]) -> usize {
+    // 132 bits: the entropy followed by the 4 checksum bits (one padding byte for the last window)
+    let mut bits = Zeroizing::new([0u8; 18]);
+    bits[..16].copy_from_slice(entropy);
+    bits[16] = ((sha256_first_word(entropy) >> 28) << 4) as u8;
+    let mut chars = 0;
+    for word in 0..12 {
+        let bit = word * 11;
+        // The 11 bits of a word lie within 3 bytes
+        let window = (bits[bit / 8] as u32) << 16 | (bits[bit / 8 + 1] as u32) << 8 | bits[bit / 8 + 2] as u32;
+        chars += lengths[((window >> (13 - bit % 8)) & 0x7ff) as usize] as usize;
+    }
+    chars
+}
+
+/// A candidate reported by the scoring kernel
+#[derive(Clone)]
+pub struct Hit {
+    // Position of the candidate in its batch
+    pub index: u32,
+    pub chars: usize,
+    pub entropy: [u8; 16],
+}
+
+impl Zeroize for Hit {
+    fn zeroize(&mut self) {
+        self.entropy.zeroize();
+    }
+}
+
+/// What a scoring kernel returns for one batch
+pub struct ScoredBatch {
+    pub candidates: usize,
+    // Candidates under the threshold, in batch order
+    pub hits: Zeroizing<Vec<Hit>>,
+    // Candidates per character count
+    pub histogram: [u64; MAX_SCORE + 1],
+    // Shortest candidate (the first one on ties), whether it is a hit or not
+    pub best: Option<Zeroizing<Hit>>,
+}
+
+/// The kernel's work on the CPU: score every candidate, keep the histogram and the best one,
+/// and compact the candidates under the threshold
+pub fn score_batch(entropies: &[[u8; 16]], threshold: usize, lengths: &[u8; 2048]) -> ScoredBatch {
+    let mut scored = ScoredBatch {
+        candidates: entropies.len(),
+        hits: Zeroizing::new(Vec::new()),
+        histogram: [0; MAX_SCORE + 1],
+        best: None,
+    };
+    for (index, entropy) in entropies.iter().enumerate() {
+        let chars = score(entropy, lengths);
+        let hit = || Hit {
+            index: index as u32,
+            chars,
+            entropy: *entropy,
+        };
+        scored.histogram[chars] += 1;
+        if scored.best.as_ref().is_none_or(|best| chars < best.chars) {
+            scored.best = Some(Zeroizing::new(hit()));
+        }
+        if chars < threshold {
+            scored.hits.push(hit());
+        }
+    }
+    scored
+}
+
+/// `gpu-check`: the CPU reference against the bip39 crate, then the GPU kernels against it and
+/// the host Philox on the same candidates (when a GPU is available)
+pub fn check(count: usize, threshold: usize) -> Result<(), String> {
+    let lengths = word_lengths();
+    let mut rng = rand::thread_rng();
+    let mut entropies = Zeroizing::new(vec![[0u8; 16]; count]);
+    for entropy in entropies.iter_mut() {
+        rng.fill_bytes(entropy);
+    }
+
+    for entropy in entropies.iter() {
+        let mnemonic = Mnemonic::from_entropy_in(Language::English, entropy)
+            .expect("16 bytes is always valid BIP39 entropy");
+        let expected: usize = mnemonic.words().map(str::len).sum();
+        let chars = score(entropy, &lengths);
+        if chars != expected {
+            return Err(format!(
+                "CPU reference scored a {}-character mnemonic as {} characters",
+                expected, chars
+            ));
+        }
+    }
+    let reference = score_batch(&entropies, threshold, &lengths);
+    println!(
+        "CPU reference: {} candidates match the bip39 crate ({} under {} characters)",
+        count,
+        reference.hits.len(),
+        threshold
+    );
+
+    // No dashboard or status stream here: notices go straight to stderr
+    let reporter = Arc::new(Reporter::new(ConsoleMode::Redacted, Zeroizing::default()));
+    let gpu = GpuContext::new(0, count, GpuRng::Philox, reporter);
+    if !gpu.is_available() {
+        println!("No GPU available: only the CPU reference was checked.");
+        return Ok(());
+    }
+    philox::check(&gpu, count)?;
+    let scored = gpu.score_entropies(&entropies, threshold);
+    gpu.cleanup();
+    let scored = scored?;
+    if scored.histogram != reference.histogram {
+        return Err("GPU score histogram differs from the CPU reference".to_string());
+    }
+    let same_hit = |a: &Hit, b: &Hit| (a.index, a.chars, a.entropy) == (b.index, b.chars, b.entropy);
+    if scored.hits.len() != reference.hits.len()
+        || !scored.hits.iter().zip(reference.hits.iter()).all(|(a, b)| same_hit(a, b))
+    {
+        return Err(format!(
+            "GPU hits differ from the CPU reference ({} vs {})",
+            scored.hits.len(),
+            reference.hits.len()
+        ));
+    }
+    match (&scored.best, &reference.best) {
+        (Some(a), Some(b)) if same_hit(a, b) => {}
+        (None, None) => {}
+        _ => return Err("GPU best candidate differs from the CPU reference".to_string()),
+    }
+    println!("GPU kernel: histogram, {} hits and best candidate match the CPU reference", scored.hits.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bip39_chars(entropy: &[u8; 16]) -> usize {
+        Mnemonic::from_entropy_in(Language::English, entropy).unwrap().words().map(str::len).sum()
+    }
+
+    #[test]
+    fn scores_like_the_bip39_crate() {
+        let lengths = word_lengths();
+        // "abandon" x11 + "about" and "zoo" x11 + "wrong"
+        assert_eq!(score(&[0; 16], &lengths), 82);
+        assert_eq!(score(&[0xff; 16], &lengths), 38);
+        let mut entropies = vec![[0u8; 16], [0xff; 16], [0x7f; 16], [0x80; 16]];
+        // Every byte value in every position
+        entropies.extend((0..=255u8).map(|byte| std::array::from_fn(|i| byte.wrapping_add(i as u8 * 7))));
+        for entropy in &entropies {
+            assert_eq!(score(entropy, &lengths), bip39_chars(entropy), "{:02x?}", entropy);
+        }
+    }
+
+    #[test]
+    fn compacts_hits_in_batch_order() {
+        let lengths = word_lengths();
+        let entropies = [[0u8; 16], [0xff; 16], [0u8; 16], [0xff; 16], [0x7f; 16]];
+        let other = score(&[0x7f; 16], &lengths);
+        assert!(other > 38 && other != 82);
+
+        let scored = score_batch(&entropies, other + 1, &lengths);
+        assert_eq!(scored.candidates, 5);
+        let hits: Vec<(u32, usize)> = scored.hits.iter().map(|hit| (hit.index, hit.chars)).collect();
+        assert_eq!(hits, [(1, 38), (3, 38), (4, other)]);
+        assert!(scored.hits.iter().all(|hit| hit.entropy == entropies[hit.index as usize]));
+
+        let mut histogram = [0u64; MAX_SCORE + 1];
+        histogram[82] = 2;
+        histogram[38] = 2;
+        histogram[other] += 1;
+        assert_eq!(scored.histogram, histogram);
+
+        // The first of the two shortest candidates, even with no hits at all
+        for threshold in [other + 1, 0] {
+            let best = score_batch(&entropies, threshold, &lengths).best.unwrap();
+            assert_eq!((best.index, best.chars, best.entropy), (1, 38, [0xff; 16]));
+        }
+        assert!(score_batch(&entropies, 0, &lengths).hits.is_empty());
+        assert!(score_batch(&[], 100, &lengths).best.is_none());
+    }
+}
@@ -0,0 +1,150 @@
+// Secret hygiene helpers
+// Everything we keep is real wallet entropy, so secret buffers are wiped on drop,
+// the results store can be pinned in RAM and the process never writes core dumps.
+use zeroize::{Zeroize, Zeroizing};
+
+/// Batch of raw 128-bit entropy values, wiped when dropped
+pub type EntropyBatch = Zeroizing<Vec<[u8; 16]>>;
+
+/// Disable core dumps so a crash never writes secrets to disk.
+/// On Linux this also marks the process non-dumpable (blocks ptrace from other users
+/// and hides /proc/<pid>/mem).
+pub fn disable_core_dumps() {
+    #[cfg(unix)]
+    {
+        let limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: setrlimit only reads the struct we pass in
+        if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &limit) } != 0 {
+            eprintln!(
+                "Warning: Could not disable core dumps: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: PR_SET_DUMPABLE takes a plain integer argument
+        if unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) } != 0 {
+            eprintln!(
+                "Warning: Could not mark process as non-dumpable: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+/// Vector for secret records. Contents are zeroized on drop and, when locking
+/// is requested, the backing buffer is kept mlock'ed (Linux only) so it is
+/// never swapped out. Records are expected to store their secrets inline.
+pub struct SecretVec<T: Zeroize> {
+    items: Vec<T>,
+    lock_memory: bool,
+    // Currently locked region (address, length in bytes)
+    locked: Option<(usize, usize)>,
+}
+
+impl<T: Zeroize> SecretVec<T> {
+    pub fn new(lock_memory: bool) -> Self {
+        // Reserve up front so the locked region rarely has to move
+        let mut vec = Self {
+            items: Vec::with_capacity(if lock_memory { 1024 } else { 0 }),
+            lock_memory,
+            locked: None,
+        };
+        vec.relock();
+        vec
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.items.len() == self.items.capacity() {
+            // Growing reallocates: wipe the old buffer before it is freed
+            let mut grown = Vec::with_capacity((self.items.capacity() * 2).max(16));
+            grown.append(&mut self.items);
+            let mut old = std::mem::replace(&mut self.items, grown);
+            old.zeroize();
+            self.relock();
+        }
+        self.items.push(item);
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.items.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    fn relock(&mut self) {
+        if !self.lock_memory {
+            return;
+        }
+        self.unlock();
+
+        let bytes = self.items.capacity() * std::mem::size_of::<T>();
+        if bytes == 0 {
+            return;
+        }
+        let addr = self.items.as_ptr() as usize;
+
+        #[cfg(target_os = "linux")]
+        {
+            // SAFETY: the range is the vector's own allocation
+            if unsafe { libc::mlock(addr as *const libc::c_void, bytes) } == 0 {
+                self.locked = Some((addr, bytes));
+            } else {
+                static MLOCK_WARNED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+                if !MLOCK_WARNED.swap(true, std::sync::atomic::Ordering::Relaxed) {
+                    eprintln!(
+                        "Warning: mlock failed ({}). Results may be swapped to disk; check `ulimit -l`.",
+                        std::io::Error::last_os_error()
+                    );
+                }
+            }
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = addr;
+            static MLOCK_WARNED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+            if !MLOCK_WARNED.swap(true, std::sync::atomic::Ordering::Relaxed) {
+                eprintln!("Warning: Memory locking is only supported on Linux. Results are not locked.");
+            }
+        }
+    }
+
+    fn unlock(&mut self) {
+        if let Some((_addr, _bytes)) = self.locked.take() {
+            #[cfg(target_os = "linux")]
+            // SAFETY: unlocking the exact region we locked earlier
+            unsafe {
+                libc::munlock(_addr as *const libc::c_void, _bytes);
+            }
+        }
+    }
+}
+
+impl<T: Zeroize> Default for SecretVec<T> {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+impl<T: Zeroize> Drop for SecretVec<T> {
+    fn drop(&mut self) {
+        self.items.zeroize();
+        self.unlock();
+    }
+}
@@ -0,0 +1,233 @@
+// SeedQR / CompactSeedQR encoding (SeedSigner specification)
+// Standard SeedQR: each BIP39 word index as 4 zero-padded digits, numeric mode.
+// CompactSeedQR: the raw entropy bytes, byte mode.
+// 12-word seeds fit QR version 2 (standard) or version 1 (compact), error correction L.
+use bip39::{Language, Mnemonic};
+use clap::ValueEnum;
+use qrcode::bits::Bits;
+use qrcode::render::svg;
+use qrcode::{Color, EcLevel, QrCode, Version};
+use zeroize::Zeroizing;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeedQrFormat {
+    /// Standard SeedQR (digit-encoded word indices)
+    Standard,
+    /// CompactSeedQR (raw entropy bytes)
+    Compact,
+}
+
+// Quiet zone around the symbol, in modules (QR spec minimum is 4)
+const QUIET_ZONE: usize = 4;
+
+/// Payload bytes for a 12-word mnemonic
+pub fn encode_payload(entropy: &[u8; 16], format: SeedQrFormat) -> Result<Zeroizing<Vec<u8>>, String> {
+    match format {
+        SeedQrFormat::Standard => {
+            let mnemonic = Mnemonic::from_entropy_in(Language::English, entropy)
+                .map_err(|e| format!("Invalid entropy: {}", e))?;
+            let mut digits = Zeroizing::new(Vec::with_capacity(48));
+            for index in mnemonic.word_indices() {
+                digits.extend_from_slice(format!("{:04}", index).as_bytes());
+            }
+            Ok(digits)
+        }
+        SeedQrFormat::Compact => Ok(Zeroizing::new(entropy.to_vec())),
+    }
+}
+
+/// Decode a SeedQR (48/96 digits) or CompactSeedQR (16/32 bytes) payload into a mnemonic
+pub fn decode_payload(payload: &[u8]) -> Result<Mnemonic, String> {
+    let is_digits = !payload.is_empty() && payload.iter().all(u8::is_ascii_digit);
+
+    if is_digits && (payload.len() == 48 || payload.len() == 96) {
+        let words = Language::English.word_list();
+        let mut phrase = Zeroizing::new(String::new());
+        for chunk in payload.chunks(4) {
+            // Chunks are ASCII digits, so this cannot fail
+            let index: usize = std::str::from_utf8(chunk).unwrap().parse().unwrap();
+            let word = words
+                .get(index)
+                .ok_or_else(|| format!("Word index {} out of range", index))?;
+            if !phrase.is_empty() {
+                phrase.push(' ');
+            }
+            phrase.push_str(word);
+        }
+        Mnemonic::parse_in_normalized(Language::English, &phrase)
+            .map_err(|e| format!("Invalid SeedQR payload: {}", e))
+    } else if payload.len() == 16 || payload.len() == 32 {
+        Mnemonic::from_entropy_in(Language::English, payload)
+            .map_err(|e| format!("Invalid CompactSeedQR payload: {}", e))
+    } else {
+        Err(format!(
+            "Unrecognized payload ({} bytes): expected 48/96 digits or 16/32 entropy bytes",
+            payload.len()
+        ))
+    }
+}
+
+/// Build the QR symbol, using the mode and version mandated by the spec
+pub fn build_code(entropy: &[u8; 16], format: SeedQrFormat) -> Result<QrCode, String> {
+    let payload = encode_payload(entropy, format)?;
+
+    let (version, pushed) = match format {
+        SeedQrFormat::Standard => {
+            let mut bits = Bits::new(Version::Normal(2));
+            let pushed = bits.push_numeric_data(&payload).map(|_| bits);
+            (2, pushed)
+        }
+        SeedQrFormat::Compact => {
+            let mut bits = Bits::new(Version::Normal(1));
+            let pushed = bits.push_byte_data(&payload).map(|_| bits);
+            (1, pushed)
+        }
+    };
+
+    let mut bits = pushed.map_err(|e| format!("Failed to encode QR version {}: {:?}", version, e))?;
+    bits.push_terminator(EcLevel::L)
+        .map_err(|e| format!("Failed to encode QR version {}: {:?}", version, e))?;
+    QrCode::with_bits(bits, EcLevel::L).map_err(|e| format!("Failed to build QR code: {:?}", e))
+}
+
+/// Render as an SVG document
+pub fn render_svg(code: &QrCode) -> String {
+    code.render::<svg::Color>()
+        .quiet_zone(true)
+        .min_dimensions(300, 300)
+        .build()
+}
+
+/// Render as terminal blocks (two spaces per module, ANSI background colors)
+pub fn render_ansi(code: &QrCode) -> String {
+    const LIGHT: &str = "\x1b[47m  ";
+    const DARK: &str = "\x1b[40m  ";
+    const RESET: &str = "\x1b[0m";
+
+    let width = code.width();
+    let colors = code.to_colors();
+    let full = width + 2 * QUIET_ZONE;
+    let mut out = String::new();
+
+    for y in 0..full {
+        for x in 0..full {
+            let inside = (QUIET_ZONE..QUIET_ZONE + width).contains(&x)
+                && (QUIET_ZONE..QUIET_ZONE + width).contains(&y);
+            let dark = inside && colors[(y - QUIET_ZONE) * width + (x - QUIET_ZONE)] == Color::Dark;
+            out.push_str(if dark { DARK } else { LIGHT });
+        }
+        out.push_str(RESET);
+        out.push('\n');
+    }
+    out
+}
+
+/// Encode as an 8-bit grayscale PNG with `scale` pixels per module
+pub fn render_png(code: &QrCode, scale: usize) -> Result<Vec<u8>, String> {
+    let width = code.width();
+    let colors = code.to_colors();
+    let size = (width + 2 * QUIET_ZONE) * scale;
+
+    let mut pixels = vec![255u8; size * size];
+    for y in 0..width {
+        for x in 0..width {
+            if colors[y * width + x] != Color::Dark {
+                continue;
+            }
+            for py in 0..scale {
+                let row = (y + QUIET_ZONE) * scale + py;
+                let start = row * size + (x + QUIET_ZONE) * scale;
+                pixels[start..start + scale].fill(0);
+            }
+        }
+    }
+
+    let mut png_bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png_bytes, size as u32, size as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| format!("Failed to write PNG header: {}", e))?;
+        writer
+            .write_image_data(&pixels)
+            .map_err(|e| format!("Failed to write PNG data: {}", e))?;
+    }
+    Ok(png_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hex::FromHex;
+
+    // Examples of the SeedSigner SeedQR specification: phrase, SeedQR digit stream and
+    // CompactSeedQR bytes (hex)
+    const VECTORS: [(&str, &str, &str); 2] = [
+        (
+            "forum undo fragile fade shy sign arrest garment culture tube off merit",
+            "073318950739065415961602009907670428187212261116",
+            "5bbd9d71a8ec7990831aff359d426545",
+        ),
+        (
+            "attack pizza motion avocado network gather crop fresh patrol unusual wild holiday candy pony ranch \
+            winter theme error hybrid van cereal salon goddess expire",
+            "011513251154012711900771041507421289190620080870026613431420201617920614089619290300152408010643",
+            "0e74b64107f94cc0ccfae6a13dcbec3662154fec67e0e00999c07892597d190a",
+        ),
+    ];
+
+    fn entropy_12() -> [u8; 16] {
+        <[u8; 16]>::from_hex(VECTORS[0].2).unwrap()
+    }
+
+    #[test]
+    fn encodes_reference_payloads() {
+        let standard = encode_payload(&entropy_12(), SeedQrFormat::Standard).unwrap();
+        assert_eq!(standard.as_slice(), VECTORS[0].1.as_bytes());
+        let compact = encode_payload(&entropy_12(), SeedQrFormat::Compact).unwrap();
+        assert_eq!(compact.as_slice(), entropy_12());
+    }
+
+    #[test]
+    fn decodes_reference_payloads() {
+        for (phrase, digits, compact) in VECTORS {
+            assert_eq!(decode_payload(digits.as_bytes()).unwrap().to_string(), phrase);
+            let bytes = Vec::<u8>::from_hex(compact).unwrap();
+            assert_eq!(decode_payload(&bytes).unwrap().to_string(), phrase);
+        }
+    }
+
+    #[test]
+    fn exports_seeds_read_from_results_files() {
+        // Output file line and log file line of the same seed
+        let (phrase, digits, _) = VECTORS[0];
+        for line in [format!("{} 61", phrase), format!("Mnemonic: {}", phrase)] {
+            let entropy = crate::results::parse_line(&line).unwrap();
+            assert_eq!(*entropy, entropy_12());
+            let payload = encode_payload(&entropy, SeedQrFormat::Standard).unwrap();
+            assert_eq!(payload.as_slice(), digits.as_bytes());
+        }
+    }
+
+    #[test]
+    fn builds_symbols_of_the_specified_version() {
+        let standard = build_code(&entropy_12(), SeedQrFormat::Standard).unwrap();
+        assert_eq!(standard.version(), Version::Normal(2));
+        let compact = build_code(&entropy_12(), SeedQrFormat::Compact).unwrap();
+        assert_eq!(compact.version(), Version::Normal(1));
+    }
+
+    #[test]
+    fn rejects_invalid_payloads() {
+        let digits = VECTORS[0].1;
+        // Last word "merit" (1116) replaced by its neighbour: the checksum no longer matches
+        let bad_checksum = format!("{}1117", &digits[..44]);
+        assert!(decode_payload(bad_checksum.as_bytes()).is_err());
+        let out_of_range = format!("{}2048", &digits[..44]);
+        assert!(decode_payload(out_of_range.as_bytes()).is_err());
+        assert!(decode_payload(&digits.as_bytes()[..47]).is_err());
+        assert!(decode_payload(&[0u8; 20]).is_err());
+    }
+}
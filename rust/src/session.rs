@@ -0,0 +1,360 @@
+// Long-lived GPU session of the feeder thread (feeder.rs)
+// The backend (CUDA, or the simulated one of --simulate-gpu) creates its random states, device
+// buffers and streams once, sized to the largest batch, in two slots. The session keeps one
+// batch in flight in the other slot while the current one is handed to the feeder, so the GPU
+// generates the next batch while the host queues this one.
+use rand::RngCore;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+
+use crate::gpu::GpuRng;
+use crate::philox::Philox;
+use crate::scoring::{self, ScoredBatch};
+use crate::secure::EntropyBatch;
+
+/// What a batch launch computes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Job {
+    // Raw entropy, copied back in full (user entropy is mixed in on the CPU)
+    Entropy,
+    // Scored on the device, only the hits are copied back
+    Scored { threshold: usize },
+}
+
+/// What the GPU returned for a batch
+pub enum GpuBatch {
+    Entropy(EntropyBatch),
+    Scored(Box<ScoredBatch>),
+}
+
+/// A device that runs batches asynchronously in one of two slots
+pub trait SessionBackend {
+    /// Start `job` for `size` candidates in `slot` and return without waiting for it
+    fn launch(&mut self, slot: usize, job: Job, size: usize) -> Result<(), String>;
+    /// Wait for the launch in `slot` and copy its output to the host
+    fn collect(&mut self, slot: usize) -> Result<GpuBatch, String>;
+}
+
+/// Batch counts of every session of a run
+#[derive(Default)]
+pub struct SessionCounters {
+    pub sessions: AtomicU64,
+    pub batches: AtomicU64,
+    // Batches that were already running when the worker asked for them
+    pub prefetched: AtomicU64,
+    // Prefetched batches thrown away because the job changed (new threshold) or was too large
+    pub discarded: AtomicU64,
+}
+
+impl SessionCounters {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} session(s), {} batches ({} prefetched, {} discarded)",
+            self.sessions.load(Ordering::Relaxed),
+            self.batches.load(Ordering::Relaxed),
+            self.prefetched.load(Ordering::Relaxed),
+            self.discarded.load(Ordering::Relaxed),
+        )
+    }
+}
+
+pub struct GpuSession {
+    backend: Box<dyn SessionBackend>,
+    capacity: usize,
+    // Job and size running in each slot
+    pending: [Option<(Job, usize)>; 2],
+    current: usize,
+    counters: Arc<SessionCounters>,
+}
+
+impl GpuSession {
+    pub fn new(backend: Box<dyn SessionBackend>, capacity: usize, counters: Arc<SessionCounters>) -> Self {
+        counters.sessions.fetch_add(1, Ordering::Relaxed);
+        Self {
+            backend,
+            capacity: capacity.max(1),
+            pending: [None, None],
+            current: 0,
+            counters,
+        }
+    }
+
+    /// Next batch of `job`. It was usually launched by the previous call; the batch after it
+    /// is launched before waiting, so it runs while the caller handles this one. A prefetched
+    /// batch keeps the size it was launched with, which is never more than `size`.
+    pub fn next(&mut self, job: Job, size: usize) -> Result<GpuBatch, String> {
+        let size = size.clamp(1, self.capacity);
+        let result = self.advance(job, size);
+        if result.is_err() {
+            // Nothing in flight is trusted after an error
+            self.pending = [None, None];
+        }
+        result
+    }
+
+    fn advance(&mut self, job: Job, size: usize) -> Result<GpuBatch, String> {
+        let slot = self.current;
+        match self.pending[slot] {
+            Some((pending, pending_size)) if pending == job && pending_size <= size => {
+                self.counters.prefetched.fetch_add(1, Ordering::Relaxed);
+            }
+            Some(_) => {
+                // Launched for other rules, or larger than the caller may take now (throttling,
+                // the last iterations of --max-iterations): wait for it so the slot is free,
+                // and drop it
+                self.pending[slot] = None;
+                drop(self.backend.collect(slot)?);
+                self.counters.discarded.fetch_add(1, Ordering::Relaxed);
+                self.launch(slot, job, size)?;
+            }
+            None => self.launch(slot, job, size)?,
+        }
+
+        let other = 1 - slot;
+        if self.pending[other].is_none() {
+            self.launch(other, job, size)?;
+        }
+
+        self.pending[slot] = None;
+        self.current = other;
+        let batch = self.backend.collect(slot)?;
+        self.counters.batches.fetch_add(1, Ordering::Relaxed);
+        Ok(batch)
+    }
+
+    fn launch(&mut self, slot: usize, job: Job, size: usize) -> Result<(), String> {
+        self.backend.launch(slot, job, size)?;
+        self.pending[slot] = Some((job, size));
+        Ok(())
+    }
+}
+
+/// `--simulate-gpu`: the session logic without CUDA. A launch computes the batch on the CPU
+/// (the same scoring as the kernel, see scoring.rs) and keeps it until it is collected. With
+/// Philox every slot runs the same streams as a device slot would (one per candidate index,
+/// seeded once); XORWOW has no host implementation, so it is stood in for by the thread RNG.
+/// A batch is ready `delay` after its launch, which models a slow device.
+pub struct SimulatedBackend {
+    lengths: [u8; 2048],
+    delay: Duration,
+    streams: [Vec<Philox>; 2],
+    slots: [Option<(Instant, GpuBatch)>; 2],
+}
+
+impl SimulatedBackend {
+    pub fn new(rng: GpuRng, capacity: usize, delay: Duration) -> Self {
+        let streams = |_| match rng {
+            GpuRng::Xorwow => Vec::new(),
+            GpuRng::Philox => {
+                let mut os_rng = rand::rngs::OsRng;
+                let (seed, subsequence) = (os_rng.next_u64(), os_rng.next_u64());
+                (0..capacity as u64)
+                    .map(|index| Philox::new(seed, subsequence.wrapping_add(index)))
+                    .collect()
+            }
+        };
+        Self {
+            lengths: scoring::word_lengths(),
+            delay,
+            streams: [0, 1].map(streams),
+            slots: [None, None],
+        }
+    }
+}
+
+impl SessionBackend for SimulatedBackend {
+    fn launch(&mut self, slot: usize, job: Job, size: usize) -> Result<(), String> {
+        let mut entropies = Zeroizing::new(vec![[0u8; 16]; size]);
+        if self.streams[slot].is_empty() {
+            let mut rng = rand::thread_rng();
+            for entropy in entropies.iter_mut() {
+                rng.fill_bytes(entropy);
+            }
+        } else {
+            for (entropy, stream) in entropies.iter_mut().zip(self.streams[slot].iter_mut()) {
+                stream.fill_entropy(entropy);
+            }
+        }
+        // A batch nobody collected (after an error) is replaced
+        let batch = match job {
+            Job::Entropy => GpuBatch::Entropy(entropies),
+            Job::Scored { threshold } => {
+                GpuBatch::Scored(Box::new(scoring::score_batch(&entropies, threshold, &self.lengths)))
+            }
+        };
+        self.slots[slot] = Some((Instant::now() + self.delay, batch));
+        Ok(())
+    }
+
+    fn collect(&mut self, slot: usize) -> Result<GpuBatch, String> {
+        let (ready, batch) = self.slots[slot]
+            .take()
+            .ok_or_else(|| format!("Slot {} collected without a launch", slot))?;
+        std::thread::sleep(ready.saturating_duration_since(Instant::now()));
+        Ok(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::MAX_SCORE;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Mutex;
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Call {
+        Launch(usize, Job, usize),
+        Collect(usize),
+    }
+
+    // Records the calls of the session; every launch is numbered (the first byte of its
+    // candidates), and a collect fails while `fail` is set
+    #[derive(Clone, Default)]
+    struct Recording {
+        calls: Arc<Mutex<Vec<Call>>>,
+        fail: Arc<AtomicBool>,
+    }
+
+    struct RecordingBackend {
+        log: Recording,
+        launches: u8,
+        slots: [Option<(u8, Job, usize)>; 2],
+    }
+
+    impl SessionBackend for RecordingBackend {
+        fn launch(&mut self, slot: usize, job: Job, size: usize) -> Result<(), String> {
+            self.log.calls.lock().unwrap().push(Call::Launch(slot, job, size));
+            self.launches += 1;
+            self.slots[slot] = Some((self.launches, job, size));
+            Ok(())
+        }
+
+        fn collect(&mut self, slot: usize) -> Result<GpuBatch, String> {
+            self.log.calls.lock().unwrap().push(Call::Collect(slot));
+            let (launch, job, size) = self.slots[slot].take().ok_or("collected without a launch")?;
+            if self.log.fail.load(Ordering::Relaxed) {
+                return Err("device lost".to_string());
+            }
+            let entropies = Zeroizing::new(vec![[launch; 16]; size]);
+            Ok(match job {
+                Job::Entropy => GpuBatch::Entropy(entropies),
+                Job::Scored { .. } => GpuBatch::Scored(Box::new(ScoredBatch {
+                    candidates: size,
+                    hits: Zeroizing::new(Vec::new()),
+                    histogram: [0; MAX_SCORE + 1],
+                    best: None,
+                })),
+            })
+        }
+    }
+
+    fn session() -> (GpuSession, Recording, Arc<SessionCounters>) {
+        let log = Recording::default();
+        let backend = RecordingBackend {
+            log: log.clone(),
+            launches: 0,
+            slots: [None, None],
+        };
+        let counters = Arc::new(SessionCounters::default());
+        (GpuSession::new(Box::new(backend), 8, counters.clone()), log, counters)
+    }
+
+    // Launch number and size of an entropy batch
+    fn entropy(batch: GpuBatch) -> (u8, usize) {
+        match batch {
+            GpuBatch::Entropy(entropies) => (entropies[0][0], entropies.len()),
+            GpuBatch::Scored(_) => panic!("scored batch for an entropy job"),
+        }
+    }
+
+    fn calls(log: &Recording) -> Vec<Call> {
+        std::mem::take(&mut *log.calls.lock().unwrap())
+    }
+
+    #[test]
+    fn hands_out_the_prefetched_batch() {
+        let (mut session, log, counters) = session();
+        assert_eq!(entropy(session.next(Job::Entropy, 4).unwrap()), (1, 4));
+        assert_eq!(
+            calls(&log),
+            [Call::Launch(0, Job::Entropy, 4), Call::Launch(1, Job::Entropy, 4), Call::Collect(0)]
+        );
+        // The batch launched by the first call, with the next one launched before waiting
+        assert_eq!(entropy(session.next(Job::Entropy, 4).unwrap()), (2, 4));
+        assert_eq!(calls(&log), [Call::Launch(0, Job::Entropy, 4), Call::Collect(1)]);
+        // A smaller prefetched batch is still taken when more may be
+        assert_eq!(entropy(session.next(Job::Entropy, 8).unwrap()), (3, 4));
+        assert_eq!(calls(&log), [Call::Launch(1, Job::Entropy, 8), Call::Collect(0)]);
+        assert_eq!(counters.prefetched.load(Ordering::Relaxed), 2);
+        assert_eq!(counters.discarded.load(Ordering::Relaxed), 0);
+        assert_eq!(counters.batches.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn discards_the_batch_in_flight_when_the_job_changes() {
+        let (mut session, log, counters) = session();
+        session.next(Job::Scored { threshold: 46 }, 4).unwrap();
+        calls(&log);
+
+        let job = Job::Scored { threshold: 44 };
+        match session.next(job, 4).unwrap() {
+            GpuBatch::Scored(scored) => assert_eq!(scored.candidates, 4),
+            GpuBatch::Entropy(_) => panic!("entropy batch for a scored job"),
+        }
+        assert_eq!(
+            calls(&log),
+            [Call::Collect(1), Call::Launch(1, job, 4), Call::Launch(0, job, 4), Call::Collect(1)]
+        );
+        assert_eq!(counters.discarded.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.prefetched.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn discards_a_prefetched_batch_larger_than_requested() {
+        let (mut session, log, counters) = session();
+        // Sizes are clamped to the capacity
+        assert_eq!(entropy(session.next(Job::Entropy, 100).unwrap()), (1, 8));
+        calls(&log);
+
+        // The relaunched batch, not the one prefetched at the old size
+        assert_eq!(entropy(session.next(Job::Entropy, 3).unwrap()), (3, 3));
+        assert_eq!(
+            calls(&log),
+            [
+                Call::Collect(1),
+                Call::Launch(1, Job::Entropy, 3),
+                Call::Launch(0, Job::Entropy, 3),
+                Call::Collect(1)
+            ]
+        );
+        assert_eq!(counters.discarded.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn clears_the_slots_after_a_backend_error() {
+        let (mut session, log, counters) = session();
+        session.next(Job::Entropy, 4).unwrap();
+        log.fail.store(true, Ordering::Relaxed);
+        assert_eq!(session.next(Job::Entropy, 4).err().unwrap(), "device lost");
+        assert_eq!(session.pending, [None, None]);
+        calls(&log);
+
+        // Nothing launched before the error is collected: both slots start over
+        log.fail.store(false, Ordering::Relaxed);
+        let (launch, size) = entropy(session.next(Job::Entropy, 4).unwrap());
+        assert_eq!((launch, size), (4, 4));
+        let slot = session.current;
+        assert_eq!(
+            calls(&log),
+            [
+                Call::Launch(1 - slot, Job::Entropy, 4),
+                Call::Launch(slot, Job::Entropy, 4),
+                Call::Collect(1 - slot)
+            ]
+        );
+        assert_eq!(counters.batches.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,138 @@
+// `split` / `combine` subcommands: SLIP-39 Shamir shares of stored seeds
+use bip39::{Language, Mnemonic};
+use clap::Args;
+use std::fs;
+use std::io::{self, BufRead};
+use zeroize::Zeroizing;
+
+use crate::results;
+use crate::slip39::{self, Scheme};
+
+#[derive(Args, Debug)]
+pub struct SplitArgs {
+    /// Results file to read (output or log file)
+    #[arg(long, default_value = "seeds_output.txt")]
+    input: String,
+
+    /// Which seed to split (1-based, in file order)
+    #[arg(long)]
+    index: usize,
+
+    /// Group threshold and groups as GT:M/N[,M/N...], e.g. 1:2/3 or 2:2/3,3/5,1/1
+    #[arg(long, default_value = "1:2/3")]
+    scheme: Scheme,
+
+    /// PBKDF2 iteration exponent (10000 << E iterations in total)
+    #[arg(long, default_value_t = slip39::DEFAULT_ITERATION_EXPONENT)]
+    iteration_exponent: u8,
+
+    /// Random splits to try; the one with the shortest longest share is printed
+    #[arg(long, default_value_t = 1)]
+    attempts: usize,
+
+    /// Prompt for a SLIP-39 passphrase (needed again to combine the shares)
+    #[arg(long)]
+    passphrase: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct CombineArgs {
+    /// File with one share per line (reads standard input if omitted)
+    #[arg(long)]
+    input: Option<String>,
+
+    /// Prompt for the SLIP-39 passphrase used when splitting
+    #[arg(long)]
+    passphrase: bool,
+}
+
+pub fn split(args: &SplitArgs) -> Result<(), String> {
+    if args.attempts == 0 || args.attempts > 10_000 {
+        return Err("Attempts must be between 1 and 10,000".to_string());
+    }
+
+    let results = results::load_results(&args.input)?;
+    let found = results
+        .iter()
+        .nth(args.index.wrapping_sub(1))
+        .ok_or_else(|| format!("Index {} out of range (1-{})", args.index, results.len()))?;
+    let passphrase = read_passphrase(args.passphrase)?;
+
+    let split = slip39::split(
+        &found.entropy,
+        &passphrase,
+        &args.scheme,
+        args.iteration_exponent,
+        args.attempts,
+        &mut rand::thread_rng(),
+    )?;
+
+    // Everything except the shares is a '#' comment, so the output can be fed to `combine`
+    println!(
+        "# Seed #{} ({} characters) split as {}",
+        args.index, found.chars, split.scheme
+    );
+    println!(
+        "# Any {} of {} groups recover the seed",
+        split.scheme.group_threshold,
+        split.scheme.groups.len()
+    );
+    for (i, (spec, shares)) in split.scheme.groups.iter().zip(&split.groups).enumerate() {
+        println!(
+            "\n# Group {} ({} of {} shares required)",
+            i + 1,
+            spec.threshold,
+            spec.count
+        );
+        for (j, share) in shares.iter().enumerate() {
+            println!("# Share {}.{} ({} characters)", i + 1, j + 1, slip39::share_chars(share));
+            println!("{}", **share);
+        }
+    }
+    println!("\n# Longest share: {} characters", split.longest_share_chars());
+    Ok(())
+}
+
+pub fn combine(args: &CombineArgs) -> Result<(), String> {
+    let content = match args.input {
+        Some(ref path) => Zeroizing::new(
+            fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))?,
+        ),
+        None => {
+            eprintln!("Enter shares, one per line (end with EOF):");
+            let mut content = Zeroizing::new(String::new());
+            for line in io::stdin().lock().lines() {
+                let line = Zeroizing::new(line.map_err(|e| format!("Could not read shares: {}", e))?);
+                content.push_str(&line);
+                content.push('\n');
+            }
+            content
+        }
+    };
+
+    // Blank lines and '#' comments are skipped
+    let shares: Vec<Zeroizing<String>> = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| Zeroizing::new(line.to_string()))
+        .collect();
+    let passphrase = read_passphrase(args.passphrase)?;
+
+    let secret = slip39::combine(&shares, &passphrase)?;
+    let mnemonic = Mnemonic::from_entropy_in(Language::English, &secret)
+        .map_err(|e| format!("Recovered secret is not BIP39 entropy: {}", e))?;
+    let phrase = Zeroizing::new(mnemonic.to_string());
+    println!("Mnemonic: {}", *phrase);
+    println!("Total characters: {}", mnemonic.words().map(str::len).sum::<usize>());
+    Ok(())
+}
+
+fn read_passphrase(prompt: bool) -> Result<Zeroizing<String>, String> {
+    if !prompt {
+        return Ok(Zeroizing::new(String::new()));
+    }
+    rpassword::prompt_password("SLIP-39 passphrase: ")
+        .map(Zeroizing::new)
+        .map_err(|e| format!("Could not read passphrase: {}", e))
+}
@@ -0,0 +1,95 @@
+// Unix signal control, for running as a daemon (systemd, supervisors)
+// SIGUSR1 dumps a status snapshot, SIGUSR2 toggles the pause, SIGHUP reloads the collection
+// rules and resource limit from the config file, SIGTERM stops like Ctrl+C.
+use signal_hook::consts::{SIGHUP, SIGTERM, SIGUSR1, SIGUSR2};
+use signal_hook::iterator::{Handle, Signals};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use crate::config::Config;
+use crate::monitor;
+use crate::reporter::Reporter;
+use crate::stats::{PauseReason, RunStats};
+use crate::worker::CollectionRules;
+
+pub struct SignalHandler {
+    handle: Handle,
+    thread: JoinHandle<()>,
+}
+
+impl SignalHandler {
+    pub fn install(
+        stats: Arc<RunStats>,
+        throttle_data: Arc<Mutex<HashMap<String, f64>>>,
+        rules: Arc<CollectionRules>,
+        // Under --join the coordinator owns the collection rules
+        remote_rules: bool,
+        reporter: Arc<Reporter>,
+        stop_flag: Arc<AtomicBool>,
+    ) -> Result<Self, String> {
+        let mut signals = Signals::new([SIGUSR1, SIGUSR2, SIGHUP, SIGTERM])
+            .map_err(|e| format!("Could not install signal handlers: {}", e))?;
+        let handle = signals.handle();
+        let thread = std::thread::spawn(move || {
+            for signal in signals.forever() {
+                match signal {
+                    SIGUSR1 => reporter.notice(&stats.snapshot(&throttle_data, &rules)),
+                    SIGUSR2 => {
+                        let paused = !stats.is_paused_by(PauseReason::Manual);
+                        stats.set_pause(PauseReason::Manual, paused);
+                        reporter.notice(if paused { "[SIGUSR2] Paused" } else { "[SIGUSR2] Resumed" });
+                        reporter.event(
+                            if paused { "paused" } else { "resumed" },
+                            serde_json::json!({ "reason": PauseReason::Manual.name() }),
+                        );
+                    }
+                    SIGHUP => match reload((!remote_rules).then_some(&*rules), &throttle_data) {
+                        Ok(summary) => reporter.notice(&format!("[SIGHUP] Reloaded config: {}", summary)),
+                        Err(e) => reporter.notice(&format!("[SIGHUP] Config not reloaded: {}", e)),
+                    },
+                    SIGTERM => {
+                        reporter.notice("\n\nTerminated. Shutting down gracefully...");
+                        stop_flag.store(true, Ordering::Relaxed);
+                    }
+                    _ => {}
+                }
+            }
+        });
+        Ok(Self { handle, thread })
+    }
+
+    pub fn close(self) {
+        self.handle.close();
+        self.thread.join().ok();
+    }
+}
+
+/// Apply the collection rules (unless `rules` is None) and resource limit from the config
+/// file. Settings missing from the file keep their current values; nothing changes if any
+/// value is invalid.
+pub fn reload(rules: Option<&CollectionRules>, throttle_data: &Mutex<HashMap<String, f64>>) -> Result<String, String> {
+    let config = Config::reload()?;
+    let max_usage = config
+        .max_usage_percent
+        .unwrap_or(monitor::max_usage(throttle_data) * 100.0);
+    let Some(rules) = rules else {
+        monitor::set_max_usage(throttle_data, max_usage)?;
+        return Ok(format!(
+            "resource limit {:.0}% (collection rules come from the coordinator)",
+            max_usage
+        ));
+    };
+    let (threshold, count) = rules.get();
+    let threshold = config.threshold.unwrap_or(threshold);
+    let count = config.count.unwrap_or(count);
+
+    crate::worker::check_rules(threshold, count)?;
+    monitor::set_max_usage(throttle_data, max_usage)?;
+    rules.set(threshold, count)?;
+    Ok(format!(
+        "threshold {}, count {}, resource limit {:.0}%",
+        threshold, count, max_usage
+    ))
+}
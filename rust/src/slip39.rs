@@ -0,0 +1,785 @@
+// SLIP-39 Shamir's Secret-Sharing for Mnemonic Codes
+// https://github.com/satoshilabs/slips/blob/master/slip-0039.md
+//
+// The master secret is encrypted with a 4-round Feistel network (PBKDF2-HMAC-SHA256),
+// split into group shares, and each group share is split again into member shares.
+// Shares are encoded as words from the SLIP-39 wordlist with an RS1024 checksum.
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+const WORDLIST: &str = include_str!("slip39_wordlist.txt");
+
+const RADIX_BITS: usize = 10;
+const ID_LENGTH_BITS: usize = 15;
+const ITERATION_EXP_LENGTH_BITS: usize = 4;
+const CHECKSUM_LENGTH_WORDS: usize = 3;
+const DIGEST_LENGTH_BYTES: usize = 4;
+const MAX_SHARE_COUNT: usize = 16;
+const MIN_STRENGTH_BYTES: usize = 16;
+const BASE_ITERATION_COUNT: u32 = 10000;
+const ROUND_COUNT: u8 = 4;
+const SECRET_INDEX: u8 = 255;
+const DIGEST_INDEX: u8 = 254;
+const CUSTOMIZATION: &[u8] = b"shamir";
+const CUSTOMIZATION_EXTENDABLE: &[u8] = b"shamir_extendable";
+// id + extendable flag + exponent (2 words), share parameters (2 words), checksum (3 words)
+const METADATA_LENGTH_WORDS: usize = 2 + 2 + CHECKSUM_LENGTH_WORDS;
+// Iteration exponent used by the reference implementation (PBKDF2 rounds of 2500 << e)
+pub const DEFAULT_ITERATION_EXPONENT: u8 = 1;
+
+fn words() -> Vec<&'static str> {
+    WORDLIST.lines().collect()
+}
+
+/// Member threshold and count of one group, e.g. `2/3`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GroupSpec {
+    pub threshold: u8,
+    pub count: u8,
+}
+
+/// Group threshold and groups, written as `GT:M/N,M/N,...` (e.g. `1:2/3` or `2:2/3,3/5,1/1`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scheme {
+    pub group_threshold: u8,
+    pub groups: Vec<GroupSpec>,
+}
+
+impl FromStr for Scheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (threshold, groups) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid scheme '{}': expected GT:M/N[,M/N...]", s))?;
+        let group_threshold: u8 = threshold
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid group threshold '{}'", threshold))?;
+
+        let mut specs = Vec::new();
+        for group in groups.split(',') {
+            let (m, n) = group
+                .trim()
+                .split_once('/')
+                .ok_or_else(|| format!("Invalid group '{}': expected M/N", group))?;
+            let threshold: u8 = m.parse().map_err(|_| format!("Invalid member threshold '{}'", m))?;
+            let count: u8 = n.parse().map_err(|_| format!("Invalid member count '{}'", n))?;
+            specs.push(GroupSpec { threshold, count });
+        }
+
+        let scheme = Scheme {
+            group_threshold,
+            groups: specs,
+        };
+        scheme.validate()?;
+        Ok(scheme)
+    }
+}
+
+impl std::fmt::Display for Scheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let groups: Vec<String> = self
+            .groups
+            .iter()
+            .map(|g| format!("{}/{}", g.threshold, g.count))
+            .collect();
+        write!(f, "{}:{}", self.group_threshold, groups.join(","))
+    }
+}
+
+impl Scheme {
+    fn validate(&self) -> Result<(), String> {
+        if self.groups.is_empty() || self.groups.len() > MAX_SHARE_COUNT {
+            return Err(format!("Group count must be between 1 and {}", MAX_SHARE_COUNT));
+        }
+        if self.group_threshold == 0 || self.group_threshold as usize > self.groups.len() {
+            return Err("Group threshold must be between 1 and the number of groups".to_string());
+        }
+        for group in &self.groups {
+            if group.threshold == 0 || group.threshold > group.count || group.count as usize > MAX_SHARE_COUNT {
+                return Err(format!(
+                    "Invalid group {}/{}: need 1 <= threshold <= count <= {}",
+                    group.threshold, group.count, MAX_SHARE_COUNT
+                ));
+            }
+            if group.threshold == 1 && group.count > 1 {
+                return Err("Member threshold 1 with several shares is not allowed, use 1/1".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Shares of one split, by group
+#[derive(Clone)]
+pub struct Split {
+    pub scheme: Scheme,
+    pub groups: Vec<Vec<Zeroizing<String>>>,
+}
+
+impl Split {
+    /// Characters of the longest share (spaces excluded)
+    pub fn longest_share_chars(&self) -> usize {
+        self.groups
+            .iter()
+            .flatten()
+            .map(|share| share_chars(share))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Search-time share scoring: every collected seed is split a few times (empty
+/// SLIP-39 passphrase) and the split with the shortest longest share is kept
+#[derive(Clone, Debug)]
+pub struct ShareScoring {
+    pub scheme: Scheme,
+    pub attempts: usize,
+    // Only collect seeds whose longest share stays within this many characters
+    pub max_chars: Option<usize>,
+}
+
+impl ShareScoring {
+    pub fn score(&self, entropy: &[u8; 16]) -> Result<Split, String> {
+        split(
+            entropy,
+            "",
+            &self.scheme,
+            DEFAULT_ITERATION_EXPONENT,
+            self.attempts,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    pub fn accepts(&self, split: &Split) -> bool {
+        self.max_chars
+            .is_none_or(|max| split.longest_share_chars() <= max)
+    }
+}
+
+/// Letters in a share mnemonic, spaces excluded
+pub fn share_chars(share: &str) -> usize {
+    share.split_whitespace().map(str::len).sum()
+}
+
+struct Share {
+    identifier: u16,
+    extendable: bool,
+    iteration_exponent: u8,
+    group_index: u8,
+    group_threshold: u8,
+    group_count: u8,
+    member_index: u8,
+    member_threshold: u8,
+    value: Zeroizing<Vec<u8>>,
+}
+
+impl Share {
+    fn to_mnemonic(&self) -> Zeroizing<String> {
+        let wordlist = words();
+        let id_exp = ((self.identifier as u32) << (ITERATION_EXP_LENGTH_BITS + 1))
+            | ((self.extendable as u32) << ITERATION_EXP_LENGTH_BITS)
+            | self.iteration_exponent as u32;
+        let params = ((self.group_index as u32) << 16)
+            | (((self.group_threshold - 1) as u32) << 12)
+            | (((self.group_count - 1) as u32) << 8)
+            | ((self.member_index as u32) << 4)
+            | (self.member_threshold - 1) as u32;
+
+        let mut indices: Vec<u16> = vec![
+            (id_exp >> 10) as u16,
+            (id_exp & 0x3ff) as u16,
+            (params >> 10) as u16,
+            (params & 0x3ff) as u16,
+        ];
+        indices.extend(bytes_to_indices(&self.value));
+        let checksum = create_checksum(customization(self.extendable), &indices);
+        indices.extend(checksum);
+
+        let mut mnemonic = Zeroizing::new(String::new());
+        for (i, index) in indices.iter().enumerate() {
+            if i > 0 {
+                mnemonic.push(' ');
+            }
+            mnemonic.push_str(wordlist[*index as usize]);
+        }
+        mnemonic
+    }
+
+    fn parse(mnemonic: &str) -> Result<Share, String> {
+        let wordlist = words();
+        let mut indices = Vec::new();
+        for word in mnemonic.split_whitespace() {
+            let word = word.to_lowercase();
+            let index = wordlist
+                .binary_search(&word.as_str())
+                .map_err(|_| format!("Invalid SLIP-39 word '{}'", word))?;
+            indices.push(index as u16);
+        }
+
+        let min_words = METADATA_LENGTH_WORDS + (MIN_STRENGTH_BYTES * 8).div_ceil(RADIX_BITS);
+        if indices.len() < min_words {
+            return Err(format!("Invalid share: at least {} words required", min_words));
+        }
+        let padding_bits = (RADIX_BITS * (indices.len() - METADATA_LENGTH_WORDS)) % 16;
+        if padding_bits > 8 {
+            return Err("Invalid share length".to_string());
+        }
+
+        let id_exp = ((indices[0] as u32) << 10) | indices[1] as u32;
+        let extendable = (id_exp >> ITERATION_EXP_LENGTH_BITS) & 1 == 1;
+        if !verify_checksum(customization(extendable), &indices) {
+            return Err(format!(
+                "Invalid share checksum: {}...",
+                mnemonic.split_whitespace().take(3).collect::<Vec<_>>().join(" ")
+            ));
+        }
+
+        let params = ((indices[2] as u32) << 10) | indices[3] as u32;
+        let value_indices = &indices[4..indices.len() - CHECKSUM_LENGTH_WORDS];
+        let value_bytes = (RADIX_BITS * value_indices.len() - padding_bits) / 8;
+        let value = indices_to_bytes(value_indices, value_bytes)?;
+
+        let share = Share {
+            identifier: (id_exp >> (ITERATION_EXP_LENGTH_BITS + 1)) as u16,
+            extendable,
+            iteration_exponent: (id_exp & 0xf) as u8,
+            group_index: (params >> 16) as u8,
+            group_threshold: ((params >> 12) & 0xf) as u8 + 1,
+            group_count: ((params >> 8) & 0xf) as u8 + 1,
+            member_index: ((params >> 4) & 0xf) as u8,
+            member_threshold: (params & 0xf) as u8 + 1,
+            value,
+        };
+        if share.group_count < share.group_threshold {
+            return Err("Invalid share: group threshold exceeds group count".to_string());
+        }
+        Ok(share)
+    }
+}
+
+/// Split a master secret into (extendable) SLIP-39 share mnemonics.
+/// With several attempts, the split whose longest share is shortest is kept;
+/// the encryption does not depend on the identifier for extendable shares, so
+/// the (slow) PBKDF2 rounds run once for all attempts.
+pub fn split<R: RngCore>(
+    master_secret: &[u8],
+    passphrase: &str,
+    scheme: &Scheme,
+    iteration_exponent: u8,
+    attempts: usize,
+    rng: &mut R,
+) -> Result<Split, String> {
+    check_secret(master_secret)?;
+    check_passphrase(passphrase)?;
+    scheme.validate()?;
+    if iteration_exponent as usize >= 1 << ITERATION_EXP_LENGTH_BITS {
+        return Err("Iteration exponent must be between 0 and 15".to_string());
+    }
+
+    let ems = encrypt(master_secret, passphrase, iteration_exponent, 0, true);
+    let mut best: Option<Split> = None;
+    for _ in 0..attempts.max(1) {
+        let identifier = (rng.next_u32() & ((1 << ID_LENGTH_BITS) - 1)) as u16;
+        let split = split_ems(&ems, scheme, identifier, iteration_exponent, true, rng)?;
+        if best
+            .as_ref()
+            .is_none_or(|b| split.longest_share_chars() < b.longest_share_chars())
+        {
+            best = Some(split);
+        }
+    }
+    Ok(best.expect("at least one attempt"))
+}
+
+fn split_ems<R: RngCore>(
+    ems: &[u8],
+    scheme: &Scheme,
+    identifier: u16,
+    iteration_exponent: u8,
+    extendable: bool,
+    rng: &mut R,
+) -> Result<Split, String> {
+    let group_shares = split_secret(scheme.group_threshold, scheme.groups.len() as u8, ems, rng)?;
+
+    let mut groups = Vec::with_capacity(scheme.groups.len());
+    for (group_index, group_secret) in group_shares {
+        let spec = scheme.groups[group_index as usize];
+        let member_shares = split_secret(spec.threshold, spec.count, &group_secret, rng)?;
+        let mnemonics = member_shares
+            .into_iter()
+            .map(|(member_index, value)| {
+                Share {
+                    identifier,
+                    extendable,
+                    iteration_exponent,
+                    group_index,
+                    group_threshold: scheme.group_threshold,
+                    group_count: scheme.groups.len() as u8,
+                    member_index,
+                    member_threshold: spec.threshold,
+                    value,
+                }
+                .to_mnemonic()
+            })
+            .collect();
+        groups.push(mnemonics);
+    }
+    Ok(Split {
+        scheme: scheme.clone(),
+        groups,
+    })
+}
+
+/// Recover the master secret from enough share mnemonics
+pub fn combine(mnemonics: &[Zeroizing<String>], passphrase: &str) -> Result<Zeroizing<Vec<u8>>, String> {
+    if mnemonics.is_empty() {
+        return Err("No shares provided".to_string());
+    }
+    check_passphrase(passphrase)?;
+
+    let shares = mnemonics
+        .iter()
+        .map(|m| Share::parse(m))
+        .collect::<Result<Vec<_>, _>>()?;
+    let first = &shares[0];
+    for share in &shares {
+        if share.identifier != first.identifier
+            || share.extendable != first.extendable
+            || share.iteration_exponent != first.iteration_exponent
+        {
+            return Err("Shares belong to different secrets (identifier mismatch)".to_string());
+        }
+        if share.group_threshold != first.group_threshold || share.group_count != first.group_count {
+            return Err("Shares have inconsistent group parameters".to_string());
+        }
+        if share.value.len() != first.value.len() {
+            return Err("Shares have different lengths".to_string());
+        }
+    }
+
+    // Members by group index
+    let mut groups: BTreeMap<u8, Vec<&Share>> = BTreeMap::new();
+    for share in &shares {
+        let members = groups.entry(share.group_index).or_default();
+        if members.iter().any(|m| m.member_index == share.member_index) {
+            return Err(format!(
+                "Duplicate member {} in group {}",
+                share.member_index + 1,
+                share.group_index + 1
+            ));
+        }
+        members.push(share);
+    }
+    if groups.len() < first.group_threshold as usize {
+        return Err(format!(
+            "Insufficient groups: {} of {} required",
+            groups.len(),
+            first.group_threshold
+        ));
+    }
+
+    let mut group_secrets = Vec::new();
+    for (group_index, members) in groups.iter().take(first.group_threshold as usize) {
+        let threshold = members[0].member_threshold;
+        if members.iter().any(|m| m.member_threshold != threshold) {
+            return Err(format!("Group {} has inconsistent member thresholds", group_index + 1));
+        }
+        if members.len() < threshold as usize {
+            return Err(format!(
+                "Group {}: {} of {} shares required",
+                group_index + 1,
+                members.len(),
+                threshold
+            ));
+        }
+        let points: Points = members
+            .iter()
+            .take(threshold as usize)
+            .map(|m| (m.member_index, m.value.clone()))
+            .collect();
+        group_secrets.push((*group_index, recover_secret(threshold, &points)?));
+    }
+
+    let ems = recover_secret(first.group_threshold, &group_secrets)?;
+    Ok(decrypt(&ems, passphrase, first.iteration_exponent, first.identifier, first.extendable))
+}
+
+fn check_secret(secret: &[u8]) -> Result<(), String> {
+    if secret.len() < MIN_STRENGTH_BYTES || !secret.len().is_multiple_of(2) {
+        return Err(format!(
+            "Master secret must be at least {} bytes and an even length",
+            MIN_STRENGTH_BYTES
+        ));
+    }
+    Ok(())
+}
+
+fn check_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.bytes().all(|b| (32..=126).contains(&b)) {
+        Ok(())
+    } else {
+        Err("SLIP-39 passphrases may only contain printable ASCII characters".to_string())
+    }
+}
+
+fn customization(extendable: bool) -> &'static [u8] {
+    if extendable {
+        CUSTOMIZATION_EXTENDABLE
+    } else {
+        CUSTOMIZATION
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Word index encoding and RS1024 checksum
+
+fn bytes_to_indices(bytes: &[u8]) -> Vec<u16> {
+    let word_count = (bytes.len() * 8).div_ceil(RADIX_BITS);
+    let padding = word_count * RADIX_BITS - bytes.len() * 8;
+    let mut indices = Vec::with_capacity(word_count);
+    let mut acc: u32 = 0;
+    let mut bits = padding; // leading zero padding bits
+    for &byte in bytes {
+        acc = (acc << 8) | byte as u32;
+        bits += 8;
+        while bits >= RADIX_BITS {
+            bits -= RADIX_BITS;
+            indices.push(((acc >> bits) & 0x3ff) as u16);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    indices
+}
+
+fn indices_to_bytes(indices: &[u16], byte_count: usize) -> Result<Zeroizing<Vec<u8>>, String> {
+    let padding = indices.len() * RADIX_BITS - byte_count * 8;
+    let mut bytes = Zeroizing::new(Vec::with_capacity(byte_count));
+    let mut acc: u32 = 0;
+    let mut bits = 0usize;
+    let mut skip = padding;
+    for &index in indices {
+        acc = (acc << RADIX_BITS) | index as u32;
+        bits += RADIX_BITS;
+        if skip > 0 {
+            // Padding bits must be zero
+            let take = skip.min(bits);
+            if (acc >> (bits - take)) != 0 {
+                return Err("Invalid share: non-zero padding".to_string());
+            }
+            bits -= take;
+            skip -= take;
+            acc &= (1 << bits) - 1;
+        }
+        while bits >= 8 {
+            bits -= 8;
+            bytes.push(((acc >> bits) & 0xff) as u8);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    Ok(bytes)
+}
+
+fn rs1024_polymod(values: impl Iterator<Item = u32>) -> u32 {
+    const GEN: [u32; 10] = [
+        0xE0E040, 0x1C1C080, 0x3838100, 0x7070200, 0xE0E0009, 0x1C0C2412, 0x38086C24, 0x3090FC48,
+        0x21B1F890, 0x3F3F120,
+    ];
+    let mut chk: u32 = 1;
+    for v in values {
+        let b = chk >> 20;
+        chk = ((chk & 0xFFFFF) << 10) ^ v;
+        for (i, g) in GEN.iter().enumerate() {
+            if (b >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+fn create_checksum(customization: &[u8], data: &[u16]) -> [u16; CHECKSUM_LENGTH_WORDS] {
+    let values = customization
+        .iter()
+        .map(|&c| c as u32)
+        .chain(data.iter().map(|&d| d as u32))
+        .chain(std::iter::repeat_n(0, CHECKSUM_LENGTH_WORDS));
+    let polymod = rs1024_polymod(values) ^ 1;
+    [
+        ((polymod >> 20) & 0x3ff) as u16,
+        ((polymod >> 10) & 0x3ff) as u16,
+        (polymod & 0x3ff) as u16,
+    ]
+}
+
+fn verify_checksum(customization: &[u8], data: &[u16]) -> bool {
+    let values = customization
+        .iter()
+        .map(|&c| c as u32)
+        .chain(data.iter().map(|&d| d as u32));
+    rs1024_polymod(values) == 1
+}
+
+// ---------------------------------------------------------------------------
+// Feistel encryption of the master secret
+
+fn round_function(round: u8, passphrase: &str, exponent: u8, salt: &[u8], r: &[u8]) -> Zeroizing<Vec<u8>> {
+    let mut password = Zeroizing::new(vec![round]);
+    password.extend_from_slice(passphrase.as_bytes());
+    let mut full_salt = salt.to_vec();
+    full_salt.extend_from_slice(r);
+    let iterations = (BASE_ITERATION_COUNT << exponent) / ROUND_COUNT as u32;
+
+    let mut out = Zeroizing::new(vec![0u8; r.len()]);
+    pbkdf2::pbkdf2_hmac::<Sha256>(&password, &full_salt, iterations, &mut out);
+    out
+}
+
+fn feistel_salt(identifier: u16, extendable: bool) -> Vec<u8> {
+    if extendable {
+        Vec::new()
+    } else {
+        let mut salt = CUSTOMIZATION.to_vec();
+        salt.extend_from_slice(&identifier.to_be_bytes());
+        salt
+    }
+}
+
+fn feistel(
+    input: &[u8],
+    passphrase: &str,
+    exponent: u8,
+    salt: &[u8],
+    rounds: impl Iterator<Item = u8>,
+) -> Zeroizing<Vec<u8>> {
+    let half = input.len() / 2;
+    let mut l = Zeroizing::new(input[..half].to_vec());
+    let mut r = Zeroizing::new(input[half..].to_vec());
+    for round in rounds {
+        let f = round_function(round, passphrase, exponent, salt, &r);
+        let new_r: Vec<u8> = l.iter().zip(f.iter()).map(|(a, b)| a ^ b).collect();
+        l = std::mem::replace(&mut r, Zeroizing::new(new_r));
+    }
+    let mut out = Zeroizing::new(Vec::with_capacity(input.len()));
+    out.extend_from_slice(&r);
+    out.extend_from_slice(&l);
+    out
+}
+
+fn encrypt(secret: &[u8], passphrase: &str, exponent: u8, identifier: u16, extendable: bool) -> Zeroizing<Vec<u8>> {
+    let salt = feistel_salt(identifier, extendable);
+    feistel(secret, passphrase, exponent, &salt, 0..ROUND_COUNT)
+}
+
+fn decrypt(ems: &[u8], passphrase: &str, exponent: u8, identifier: u16, extendable: bool) -> Zeroizing<Vec<u8>> {
+    let salt = feistel_salt(identifier, extendable);
+    feistel(ems, passphrase, exponent, &salt, (0..ROUND_COUNT).rev())
+}
+
+// ---------------------------------------------------------------------------
+// Shamir's secret sharing over GF(256) (Rijndael polynomial)
+
+struct Gf256 {
+    exp: [u8; 255],
+    log: [u8; 256],
+}
+
+impl Gf256 {
+    fn new() -> Self {
+        let mut exp = [0u8; 255];
+        let mut log = [0u8; 256];
+        let mut poly: u16 = 1;
+        for (i, e) in exp.iter_mut().enumerate() {
+            *e = poly as u8;
+            log[poly as usize] = i as u8;
+            // Multiply by the generator 3 = x + 1
+            poly = (poly << 1) ^ poly;
+            if poly & 0x100 != 0 {
+                poly ^= 0x11B;
+            }
+        }
+        Self { exp, log }
+    }
+
+    /// Value at `x` of the polynomial through the given points
+    fn interpolate(&self, points: &[(u8, Zeroizing<Vec<u8>>)], x: u8) -> Result<Zeroizing<Vec<u8>>, String> {
+        if let Some((_, value)) = points.iter().find(|(px, _)| *px == x) {
+            return Ok(value.clone());
+        }
+        let len = points[0].1.len();
+        let log_prod: u32 = points.iter().map(|(px, _)| self.log[(px ^ x) as usize] as u32).sum();
+
+        let mut result = Zeroizing::new(vec![0u8; len]);
+        for (px, value) in points {
+            let others: u32 = points
+                .iter()
+                .map(|(ox, _)| self.log[(px ^ ox) as usize] as u32)
+                .sum();
+            // log of the Lagrange basis polynomial at x (points are distinct)
+            let log_basis = (log_prod + 255 * 16 - self.log[(px ^ x) as usize] as u32 - others) % 255;
+            for (out, &v) in result.iter_mut().zip(value.iter()) {
+                if v != 0 {
+                    *out ^= self.exp[((self.log[v as usize] as u32 + log_basis) % 255) as usize];
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+fn share_digest(random_part: &[u8], secret: &[u8]) -> [u8; DIGEST_LENGTH_BYTES] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(random_part).expect("HMAC accepts any key length");
+    mac.update(secret);
+    let digest = mac.finalize().into_bytes();
+    let mut out = [0u8; DIGEST_LENGTH_BYTES];
+    out.copy_from_slice(&digest[..DIGEST_LENGTH_BYTES]);
+    out
+}
+
+// (x coordinate, share value) pairs of one Shamir split
+type Points = Vec<(u8, Zeroizing<Vec<u8>>)>;
+
+fn split_secret<R: RngCore>(
+    threshold: u8,
+    share_count: u8,
+    secret: &[u8],
+    rng: &mut R,
+) -> Result<Points, String> {
+    if threshold == 1 {
+        return Ok((0..share_count).map(|i| (i, Zeroizing::new(secret.to_vec()))).collect());
+    }
+
+    let random_share_count = threshold - 2;
+    let mut shares: Points = (0..random_share_count)
+        .map(|i| {
+            let mut value = Zeroizing::new(vec![0u8; secret.len()]);
+            rng.fill_bytes(&mut value);
+            (i, value)
+        })
+        .collect();
+
+    let mut random_part = Zeroizing::new(vec![0u8; secret.len() - DIGEST_LENGTH_BYTES]);
+    rng.fill_bytes(&mut random_part);
+    let mut digest_share = Zeroizing::new(share_digest(&random_part, secret).to_vec());
+    digest_share.extend_from_slice(&random_part);
+
+    let mut base = shares.clone();
+    base.push((DIGEST_INDEX, digest_share));
+    base.push((SECRET_INDEX, Zeroizing::new(secret.to_vec())));
+
+    let gf = Gf256::new();
+    for i in random_share_count..share_count {
+        shares.push((i, gf.interpolate(&base, i)?));
+    }
+    Ok(shares)
+}
+
+fn recover_secret(threshold: u8, points: &[(u8, Zeroizing<Vec<u8>>)]) -> Result<Zeroizing<Vec<u8>>, String> {
+    if threshold == 1 {
+        return Ok(points[0].1.clone());
+    }
+    let gf = Gf256::new();
+    let secret = gf.interpolate(points, SECRET_INDEX)?;
+    let digest_share = gf.interpolate(points, DIGEST_INDEX)?;
+    if digest_share[..DIGEST_LENGTH_BYTES] != share_digest(&digest_share[DIGEST_LENGTH_BYTES..], &secret) {
+        return Err("Invalid digest of the shared secret (wrong or mismatched shares)".to_string());
+    }
+    Ok(secret)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hex::FromHex;
+
+    // Official SLIP-39 test vectors (trezor/python-shamir-mnemonic vectors.json), passphrase "TREZOR"
+    const SINGLE_SHARE: &str = "duckling enlarge academic academic agency result length solution fridge kidney coal \
+        piece deal husband erode duke ajar critical decision keyboard";
+    const SINGLE_SHARE_SECRET: &str = "bb54aac4b89dc868ba37d9cc21b2cece";
+    const INVALID_CHECKSUM: &str = "duckling enlarge academic academic agency result length solution fridge kidney \
+        coal piece deal husband erode duke ajar critical decision kidney";
+    const TWO_OF_THREE: [&str; 2] = [
+        "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short \
+        owner flip making coding armed",
+        "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft \
+        early superior advocate guest smoking",
+    ];
+    const TWO_OF_THREE_SECRET: &str = "b43ceb7e57a0ea8766221624d01b0864";
+
+    fn shares(mnemonics: &[&str]) -> Vec<Zeroizing<String>> {
+        mnemonics.iter().map(|m| Zeroizing::new(m.to_string())).collect()
+    }
+
+    fn secret(hex: &str) -> Vec<u8> {
+        Vec::<u8>::from_hex(hex).unwrap()
+    }
+
+    #[test]
+    fn combines_official_vectors() {
+        let recovered = combine(&shares(&[SINGLE_SHARE]), "TREZOR").unwrap();
+        assert_eq!(*recovered, secret(SINGLE_SHARE_SECRET));
+        let recovered = combine(&shares(&TWO_OF_THREE), "TREZOR").unwrap();
+        assert_eq!(*recovered, secret(TWO_OF_THREE_SECRET));
+        // Any order of the shares
+        let recovered = combine(&shares(&[TWO_OF_THREE[1], TWO_OF_THREE[0]]), "TREZOR").unwrap();
+        assert_eq!(*recovered, secret(TWO_OF_THREE_SECRET));
+    }
+
+    #[test]
+    fn rejects_invalid_checksum() {
+        let error = combine(&shares(&[INVALID_CHECKSUM]), "TREZOR").unwrap_err();
+        assert!(error.contains("checksum"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_insufficient_shares() {
+        let error = combine(&shares(&TWO_OF_THREE[..1]), "TREZOR").unwrap_err();
+        assert!(error.contains("1 of 2 shares required"), "{}", error);
+        let error = combine(&shares(&[TWO_OF_THREE[0], TWO_OF_THREE[0]]), "TREZOR").unwrap_err();
+        assert!(error.contains("Duplicate member"), "{}", error);
+    }
+
+    #[test]
+    fn wrong_passphrase_recovers_another_secret() {
+        // SLIP-39 cannot tell a wrong passphrase: it decrypts to a different, valid-looking secret
+        let recovered = combine(&shares(&[SINGLE_SHARE]), "").unwrap();
+        assert_ne!(*recovered, secret(SINGLE_SHARE_SECRET));
+        // Passphrases must be printable ASCII
+        let error = combine(&shares(&[SINGLE_SHARE]), "TRÉZOR").unwrap_err();
+        assert!(error.contains("assphrase"), "{}", error);
+    }
+
+    #[test]
+    fn split_and_combine_round_trip() {
+        let master = secret(TWO_OF_THREE_SECRET);
+        let scheme: Scheme = "2:2/3,3/5,1/1".parse().unwrap();
+        let split = split(&master, "TREZOR", &scheme, 0, 3, &mut rand::thread_rng()).unwrap();
+        assert_eq!(split.groups.iter().map(Vec::len).collect::<Vec<_>>(), [3, 5, 1]);
+
+        // Two members of the first group and the single share of the last one
+        let mut subset = vec![split.groups[0][2].clone(), split.groups[0][0].clone(), split.groups[2][0].clone()];
+        assert_eq!(*combine(&subset, "TREZOR").unwrap(), master);
+
+        // Three members of the second group with the first group complete
+        subset = split.groups[1][1..4].to_vec();
+        subset.extend(split.groups[0][..2].iter().cloned());
+        assert_eq!(*combine(&subset, "TREZOR").unwrap(), master);
+
+        // One complete group is not enough for a group threshold of 2
+        let error = combine(&split.groups[1], "TREZOR").unwrap_err();
+        assert!(error.contains("Insufficient groups"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_invalid_schemes() {
+        for scheme in ["0:1/1", "2:2/3", "1:3/2", "1:1/3", "1:2/17", "2/3"] {
+            assert!(scheme.parse::<Scheme>().is_err(), "{}", scheme);
+        }
+    }
+}
@@ -0,0 +1,461 @@
+// Shared run statistics
+// Workers update these counters; the dashboard and status output only read them.
+use bip39::Language;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+
+use crate::format_number;
+use crate::gpu::GpuDevice;
+use crate::monitor::{device_key, MAX_USAGE_PERCENT};
+use crate::worker::{CollectionRules, FoundSeed};
+
+// 12 words, at most 8 letters each
+pub const MAX_SCORE: usize = 96;
+
+/// Where a batch of candidates came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Cpu,
+    Gpu,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 2] = [Backend::Cpu, Backend::Gpu];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Cpu => "cpu",
+            Backend::Gpu => "gpu",
+        }
+    }
+}
+
+/// Why the workers are paused; they only run while no reason is set
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseReason {
+    // Dashboard key
+    Manual,
+    // Outside the run windows
+    Schedule,
+    // Other processes are using the machine (--idle-only)
+    Busy,
+}
+
+impl PauseReason {
+    pub const ALL: [PauseReason; 3] = [PauseReason::Manual, PauseReason::Schedule, PauseReason::Busy];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PauseReason::Manual => "manual",
+            PauseReason::Schedule => "schedule",
+            PauseReason::Busy => "busy",
+        }
+    }
+}
+
+/// Batches generated by the feeder of one GPU
+pub struct DeviceStats {
+    pub index: u32,
+    pub name: String,
+    pub batches: AtomicU64,
+    pub candidates: AtomicU64,
+}
+
+pub struct RunStats {
+    pub start: Instant,
+    // Candidates processed in total, per worker and per backend
+    pub iterations: AtomicU64,
+    pub worker_iterations: Vec<AtomicU64>,
+    backend_iterations: [AtomicU64; 2],
+    // GPU batches that failed and fell back to the CPU
+    pub gpu_failures: AtomicU64,
+    // One entry per GPU of the run (--devices)
+    pub devices: Vec<DeviceStats>,
+    // Workers idle while any of these is set (indexed by PauseReason)
+    pauses: [AtomicBool; 3],
+    // Collected seeds per character count
+    pub found_counts: Mutex<HashMap<usize, usize>>,
+    // Character counts of every candidate, merged from the workers' local histograms
+    score_counts: Mutex<Vec<u64>>,
+    // Fewest characters of any candidate (usize::MAX before the first) and that candidate,
+    // whether or not it met the threshold
+    best_chars: AtomicUsize,
+    best: Mutex<Option<Zeroizing<FoundSeed>>>,
+}
+
+impl RunStats {
+    pub fn new(num_workers: usize, devices: &[GpuDevice]) -> Self {
+        Self {
+            start: Instant::now(),
+            iterations: AtomicU64::new(0),
+            worker_iterations: (0..num_workers).map(|_| AtomicU64::new(0)).collect(),
+            backend_iterations: [AtomicU64::new(0), AtomicU64::new(0)],
+            gpu_failures: AtomicU64::new(0),
+            devices: devices
+                .iter()
+                .map(|device| DeviceStats {
+                    index: device.index,
+                    name: device.name.clone(),
+                    batches: AtomicU64::new(0),
+                    candidates: AtomicU64::new(0),
+                })
+                .collect(),
+            pauses: [AtomicBool::new(false), AtomicBool::new(false), AtomicBool::new(false)],
+            found_counts: Mutex::new(HashMap::new()),
+            score_counts: Mutex::new(vec![0; MAX_SCORE + 1]),
+            best_chars: AtomicUsize::new(usize::MAX),
+            best: Mutex::new(None),
+        }
+    }
+
+    /// Count up to `count` candidates as processed, never past `max` (--max-iterations), and
+    /// return how many were counted. Readers never see the counter above `max`.
+    pub fn claim_iterations(&self, count: u64, max: Option<u64>) -> u64 {
+        let max = max.unwrap_or(u64::MAX);
+        match self.iterations.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |done| {
+            (done < max).then(|| done + count.min(max - done))
+        }) {
+            Ok(done) => count.min(max - done),
+            Err(_) => 0,
+        }
+    }
+
+    /// Record a processed batch
+    pub fn add_batch(&self, worker: usize, backend: Backend, candidates: u64) {
+        self.worker_iterations[worker].fetch_add(candidates, Ordering::Relaxed);
+        self.backend_iterations[backend as usize].fetch_add(candidates, Ordering::Relaxed);
+    }
+
+    /// Record a GPU batch handed to a worker
+    pub fn add_device_batch(&self, device: u32, candidates: u64) {
+        if let Some(stats) = self.devices.iter().find(|stats| stats.index == device) {
+            stats.batches.fetch_add(1, Ordering::Relaxed);
+            stats.candidates.fetch_add(candidates, Ordering::Relaxed);
+        }
+    }
+
+    /// Candidates per second a GPU delivered to the workers since the start of the run
+    pub fn device_rate(&self, device: &DeviceStats) -> f64 {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            device.candidates.load(Ordering::Relaxed) as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    pub fn backend_iterations(&self, backend: Backend) -> u64 {
+        self.backend_iterations[backend as usize].load(Ordering::Relaxed)
+    }
+
+    /// Set or clear a pause reason, returning the previous state
+    pub fn set_pause(&self, reason: PauseReason, paused: bool) -> bool {
+        self.pauses[reason as usize].swap(paused, Ordering::Relaxed)
+    }
+
+    pub fn is_paused_by(&self, reason: PauseReason) -> bool {
+        self.pauses[reason as usize].load(Ordering::Relaxed)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause_reasons().next().is_some()
+    }
+
+    pub fn pause_reasons(&self) -> impl Iterator<Item = PauseReason> + '_ {
+        PauseReason::ALL.into_iter().filter(|&reason| self.is_paused_by(reason))
+    }
+
+    /// Candidates per second since the start of the run
+    pub fn average_rate(&self) -> f64 {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.iterations.load(Ordering::Relaxed) as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    pub fn found_snapshot(&self) -> HashMap<usize, usize> {
+        self.found_counts.lock().unwrap().clone()
+    }
+
+    /// Add a worker's local score histogram and reset it
+    pub fn merge_scores(&self, local: &mut [u64]) {
+        let mut counts = self.score_counts.lock().unwrap();
+        for (total, count) in counts.iter_mut().zip(local.iter_mut()) {
+            *total += std::mem::take(count);
+        }
+    }
+
+    /// Candidates seen per character count (index), as of the last merge
+    pub fn score_snapshot(&self) -> Vec<u64> {
+        self.score_counts.lock().unwrap().clone()
+    }
+
+    /// Fewest characters of any candidate so far
+    pub fn best_chars(&self) -> Option<usize> {
+        Some(self.best_chars.load(Ordering::Relaxed)).filter(|&chars| chars != usize::MAX)
+    }
+
+    /// Keep the candidate if it is shorter than the best so far (ties keep the first)
+    pub fn offer_best(&self, candidate: FoundSeed) {
+        let mut best = self.best.lock().unwrap();
+        if best.as_ref().is_none_or(|best| candidate.chars < best.chars) {
+            self.best_chars.store(candidate.chars, Ordering::Relaxed);
+            *best = Some(Zeroizing::new(candidate));
+        }
+    }
+
+    pub fn best(&self) -> Option<Zeroizing<FoundSeed>> {
+        self.best.lock().unwrap().clone()
+    }
+
+    /// Multi-line human-readable snapshot of the whole run state (SIGUSR1, control socket).
+    /// Contains no secrets.
+    pub fn snapshot(&self, throttle_data: &Mutex<HashMap<String, f64>>, rules: &CollectionRules) -> String {
+        let mut out = String::new();
+        let elapsed = Duration::from_secs(self.start.elapsed().as_secs());
+        writeln!(out, "=== Status snapshot ===").unwrap();
+        writeln!(out, "Elapsed: {}", humantime::format_duration(elapsed)).unwrap();
+        writeln!(
+            out,
+            "Processed: {} ({:.0}/s average)",
+            format_number(self.iterations.load(Ordering::Relaxed)),
+            self.average_rate()
+        )
+        .unwrap();
+        let backends: Vec<String> = Backend::ALL
+            .iter()
+            .map(|&backend| format!("{} {}", backend.name(), format_number(self.backend_iterations(backend))))
+            .collect();
+        writeln!(
+            out,
+            "Backends: {} | GPU batch failures: {}",
+            backends.join(" | "),
+            self.gpu_failures.load(Ordering::Relaxed)
+        )
+        .unwrap();
+        let workers: Vec<String> = self
+            .worker_iterations
+            .iter()
+            .map(|counter| format_number(counter.load(Ordering::Relaxed)))
+            .collect();
+        writeln!(out, "Workers: {}", workers.join(" | ")).unwrap();
+        let reasons: Vec<&str> = self.pause_reasons().map(PauseReason::name).collect();
+        if reasons.is_empty() {
+            writeln!(out, "State: running").unwrap();
+        } else {
+            writeln!(out, "State: paused ({})", reasons.join(", ")).unwrap();
+        }
+
+        let (threshold, count_per_threshold) = rules.get();
+        writeln!(
+            out,
+            "Rules: fewer than {} characters, at most {} per count above 42",
+            threshold, count_per_threshold
+        )
+        .unwrap();
+        let data = throttle_data.lock().unwrap().clone();
+        let get = |key: &str, default: f64| data.get(key).copied().unwrap_or(default);
+        writeln!(
+            out,
+            "Resources: limit {:.0}% | CPU {:.1}% (throttle {:.2}) | GPU {:.1}% (throttle {:.2})",
+            get("max_usage", MAX_USAGE_PERCENT) * 100.0,
+            get("cpu_usage", 0.0) * 100.0,
+            get("cpu_throttle", 1.0),
+            get("gpu_usage", 0.0) * 100.0,
+            get("gpu_throttle", 1.0)
+        )
+        .unwrap();
+        for device in &self.devices {
+            writeln!(
+                out,
+                "  GPU {} ({}): {} generated ({:.0}/s average) | usage {:.1}% (throttle {:.2})",
+                device.index,
+                device.name,
+                format_number(device.candidates.load(Ordering::Relaxed)),
+                self.device_rate(device),
+                get(&device_key("gpu_usage", device.index), 0.0) * 100.0,
+                get(&device_key("gpu_throttle", device.index), 1.0)
+            )
+            .unwrap();
+        }
+
+        match self.best_chars() {
+            Some(best) => writeln!(out, "Best seen: {} characters", best).unwrap(),
+            None => writeln!(out, "Best seen: -").unwrap(),
+        }
+        let mut found: Vec<(usize, usize)> = self.found_snapshot().into_iter().collect();
+        found.sort();
+        writeln!(out, "Found: {}", found.iter().map(|(_, count)| count).sum::<usize>()).unwrap();
+        for (chars, count) in found {
+            writeln!(out, "  {} characters: {}", chars, count).unwrap();
+        }
+        out
+    }
+
+    /// Observed character counts next to the theoretical distribution, with a chi-square
+    /// goodness-of-fit test. Contains no secrets.
+    pub fn score_report(&self) -> String {
+        let observed = self.score_snapshot();
+        let total: u64 = observed.iter().sum();
+        let mut out = String::new();
+        writeln!(out, "Candidates scored: {}", total).unwrap();
+        if total == 0 {
+            return out;
+        }
+
+        let expected: Vec<f64> = score_distribution().iter().map(|p| p * total as f64).collect();
+        writeln!(out, "{:>5} {:>14} {:>10} {:>10} {:>8}", "Chars", "Observed", "Observed%", "Expected%", "Ratio").unwrap();
+        for chars in 0..=MAX_SCORE {
+            // Skip counts that are neither seen nor expected at least once
+            if observed[chars] == 0 && expected[chars] < 1.0 {
+                continue;
+            }
+            writeln!(
+                out,
+                "{:>5} {:>14} {:>9.4}% {:>9.4}% {:>8}",
+                chars,
+                observed[chars],
+                observed[chars] as f64 / total as f64 * 100.0,
+                expected[chars] / total as f64 * 100.0,
+                if expected[chars] > 0.0 {
+                    format!("{:.3}", observed[chars] as f64 / expected[chars])
+                } else {
+                    "-".to_string()
+                },
+            )
+            .unwrap();
+        }
+
+        match chi_square(&observed, &expected) {
+            Some((statistic, dof)) => {
+                let z = wilson_hilferty(statistic, dof);
+                writeln!(
+                    out,
+                    "Chi-square: {:.2} on {} degrees of freedom (z = {:.2}, p ~ {:.4})",
+                    statistic,
+                    dof,
+                    z,
+                    0.5 * erfc(z / std::f64::consts::SQRT_2)
+                )
+                .unwrap();
+            }
+            None => writeln!(out, "Chi-square: not enough candidates for a meaningful test").unwrap(),
+        }
+        out
+    }
+}
+
+// Pearson chi-square over bins pooled until each expects at least 5 candidates
+fn chi_square(observed: &[u64], expected: &[f64]) -> Option<(f64, usize)> {
+    let mut bins: Vec<(f64, f64)> = Vec::new();
+    let mut pending = (0.0, 0.0);
+    for (&o, &e) in observed.iter().zip(expected) {
+        pending.0 += o as f64;
+        pending.1 += e;
+        if pending.1 >= 5.0 {
+            bins.push(std::mem::take(&mut pending));
+        }
+    }
+    // The sparse upper tail joins the last full bin
+    if let Some(last) = bins.last_mut() {
+        last.0 += pending.0;
+        last.1 += pending.1;
+    }
+    if bins.len() < 2 {
+        return None;
+    }
+    let statistic = bins.iter().map(|(o, e)| (o - e) * (o - e) / e).sum();
+    Some((statistic, bins.len() - 1))
+}
+
+// Normal approximation of a chi-square statistic (upper tail z-score)
+fn wilson_hilferty(statistic: f64, dof: usize) -> f64 {
+    let k = dof as f64;
+    let variance = 2.0 / (9.0 * k);
+    ((statistic / k).cbrt() - (1.0 - variance)) / variance.sqrt()
+}
+
+// Complementary error function (Numerical Recipes erfcc, relative error < 1.2e-7)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807
+                            + t * (-1.13520398 + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    let result = t * poly.exp();
+    if x >= 0.0 {
+        result
+    } else {
+        2.0 - result
+    }
+}
+
+/// Probability of each character count (index) for a uniformly random 12-word mnemonic.
+/// Words are treated as independent and uniform; the checksum word is close enough.
+pub fn score_distribution() -> Vec<f64> {
+    let mut word_lengths = [0f64; 9];
+    let words = Language::English.word_list();
+    for word in words {
+        word_lengths[word.len()] += 1.0 / words.len() as f64;
+    }
+
+    let mut distribution = vec![0f64; MAX_SCORE + 1];
+    distribution[0] = 1.0;
+    for _ in 0..12 {
+        let mut next = vec![0f64; MAX_SCORE + 1];
+        for (total, &p) in distribution.iter().enumerate() {
+            if p == 0.0 {
+                continue;
+            }
+            for (length, &q) in word_lengths.iter().enumerate() {
+                if q > 0.0 && total + length <= MAX_SCORE {
+                    next[total + length] += p * q;
+                }
+            }
+        }
+        distribution = next;
+    }
+    distribution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn claimed_iterations_never_pass_the_maximum() {
+        let stats = Arc::new(RunStats::new(4, &[]));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let stats = stats.clone();
+                std::thread::spawn(move || {
+                    let mut claimed = 0;
+                    loop {
+                        let count = stats.claim_iterations(7, Some(1000));
+                        assert!(stats.iterations.load(Ordering::Relaxed) <= 1000);
+                        if count == 0 {
+                            return claimed;
+                        }
+                        claimed += count;
+                    }
+                })
+            })
+            .collect();
+        let claimed: u64 = handles.into_iter().map(|handle| handle.join().unwrap()).sum();
+        assert_eq!(claimed, 1000);
+        assert_eq!(stats.iterations.load(Ordering::Relaxed), 1000);
+        // Without a maximum every candidate counts
+        assert_eq!(stats.claim_iterations(5, None), 5);
+    }
+}
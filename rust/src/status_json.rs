@@ -0,0 +1,244 @@
+// Machine-readable status stream (`--status-json`)
+// One JSON object per line: periodic {"type":"status",...} records plus {"type":"event",...}
+// records for found seeds, backend fallback, throttle changes and shutdown.
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::monitor::device_key;
+use crate::reporter::{short_hash, Reporter};
+use crate::stats::{Backend, PauseReason, RunStats};
+
+// How often throttle factors are checked for changes
+const THROTTLE_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct StatusStream {
+    // None once a write failed (reader went away)
+    out: Mutex<Option<File>>,
+}
+
+impl StatusStream {
+    /// Open a file (or FIFO) path, or an inherited file descriptor given as a number
+    pub fn open(target: &str) -> Result<Self, String> {
+        let file = match target.parse::<i32>() {
+            Ok(fd) => from_fd(fd)?,
+            Err(_) => OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(target)
+                .map_err(|e| format!("Could not open {}: {}", target, e))?,
+        };
+        Ok(Self {
+            out: Mutex::new(Some(file)),
+        })
+    }
+
+    /// Emit an event record; the error (for the reporter to show) comes once, when the stream
+    /// closes
+    pub fn event(&self, event: &str, fields: Value) -> Result<(), String> {
+        let mut record = json!({ "type": "event", "time": unix_time(), "event": event });
+        merge(&mut record, fields);
+        self.write(&record)
+    }
+
+    fn write(&self, record: &Value) -> Result<(), String> {
+        let mut out = self.out.lock().unwrap();
+        let Some(ref mut file) = *out else {
+            return Ok(());
+        };
+        let line = format!("{}\n", record);
+        file.write_all(line.as_bytes()).and_then(|_| file.flush()).map_err(|e| {
+            *out = None;
+            format!("Warning: Status stream closed: {}", e)
+        })
+    }
+}
+
+#[cfg(unix)]
+fn from_fd(fd: i32) -> Result<File, String> {
+    use std::os::unix::io::FromRawFd;
+    if fd < 0 {
+        return Err(format!("Invalid file descriptor {}", fd));
+    }
+    // The File closes its descriptor on drop, which must never be stdin, stdout or stderr
+    if fd <= 2 {
+        return Err(format!(
+            "File descriptor {} is a standard stream, redirect it to 3 or above (e.g. 3>&1)",
+            fd
+        ));
+    }
+    // SAFETY: fcntl(F_GETFD) only checks that the descriptor is open
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(format!("File descriptor {} is not open", fd));
+    }
+    // SAFETY: the descriptor is open and handed to us by the parent process for this stream
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+#[cfg(not(unix))]
+fn from_fd(fd: i32) -> Result<File, String> {
+    Err(format!("File descriptors are not supported on this platform ({}), use a path", fd))
+}
+
+/// Periodic status records and throttle change events, until the stop flag is set
+pub fn spawn(
+    stream: Arc<StatusStream>,
+    stats: Arc<RunStats>,
+    throttle_data: Arc<Mutex<HashMap<String, f64>>>,
+    reporter: Arc<Reporter>,
+    stop_flag: Arc<AtomicBool>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut last_status = Instant::now();
+        let mut last_iterations = 0;
+        let mut last_backends = [0u64; 2];
+        let mut throttle = throttle_factors(&throttle_data);
+
+        loop {
+            let stopping = stop_flag.load(Ordering::Relaxed);
+
+            let current = throttle_factors(&throttle_data);
+            if current != throttle {
+                reporter.event(
+                    "throttle",
+                    json!({
+                        "cpu": current.0, "gpu": current.1,
+                        "previous": { "cpu": throttle.0, "gpu": throttle.1 },
+                    }),
+                );
+                throttle = current;
+            }
+
+            // One last record on the way out, so consumers see the final counts
+            if stopping || last_status.elapsed() >= interval {
+                let seconds = last_status.elapsed().as_secs_f64();
+                last_status = Instant::now();
+                let iterations = stats.iterations.load(Ordering::Relaxed);
+                let backends = Backend::ALL.map(|backend| stats.backend_iterations(backend));
+                let record = status_record(
+                    &stats,
+                    &throttle_data,
+                    iterations.saturating_sub(last_iterations) as f64 / seconds.max(1e-3),
+                    [backends[0].saturating_sub(last_backends[0]), backends[1].saturating_sub(last_backends[1])],
+                );
+                if let Err(e) = stream.write(&record) {
+                    reporter.notice(&e);
+                }
+                last_iterations = iterations;
+                last_backends = backends;
+            }
+
+            if stopping {
+                break;
+            }
+            std::thread::sleep(THROTTLE_CHECK_INTERVAL.min(interval));
+        }
+    })
+}
+
+fn status_record(
+    stats: &RunStats,
+    throttle_data: &Mutex<HashMap<String, f64>>,
+    rate: f64,
+    backend_deltas: [u64; 2],
+) -> Value {
+    let found: Map<String, Value> = stats
+        .found_snapshot()
+        .into_iter()
+        .map(|(chars, count)| (chars.to_string(), json!(count)))
+        .collect();
+    let data = throttle_data.lock().unwrap().clone();
+    let get = |key: &str, default: f64| data.get(key).copied().unwrap_or(default);
+    // Backend that produced most candidates since the last record
+    let backend = match backend_deltas {
+        [0, 0] => "idle",
+        [cpu, gpu] if gpu > cpu => Backend::Gpu.name(),
+        _ => Backend::Cpu.name(),
+    };
+
+    let devices: Vec<Value> = stats
+        .devices
+        .iter()
+        .map(|device| {
+            json!({
+                "index": device.index,
+                "name": device.name,
+                "candidates": device.candidates.load(Ordering::Relaxed),
+                "average_rate": round(stats.device_rate(device)),
+                "usage": round(get(&device_key("gpu_usage", device.index), 0.0)),
+                "throttle": get(&device_key("gpu_throttle", device.index), 1.0),
+            })
+        })
+        .collect();
+
+    json!({
+        "type": "status",
+        "time": unix_time(),
+        "elapsed": round(stats.start.elapsed().as_secs_f64()),
+        "iterations": stats.iterations.load(Ordering::Relaxed),
+        "rate": round(rate),
+        "average_rate": round(stats.average_rate()),
+        "found_total": found.values().filter_map(Value::as_u64).sum::<u64>(),
+        "found": found,
+        // Fewest characters of any candidate, collected or not
+        "best": stats.best().map(|best| json!({ "chars": best.chars, "id": short_hash(&best.entropy) })),
+        "backend": backend,
+        "backends": {
+            "cpu": stats.backend_iterations(Backend::Cpu),
+            "gpu": stats.backend_iterations(Backend::Gpu),
+        },
+        "gpu_failures": stats.gpu_failures.load(Ordering::Relaxed),
+        "paused": stats.is_paused(),
+        "pause_reasons": stats.pause_reasons().map(PauseReason::name).collect::<Vec<_>>(),
+        "throttle": { "cpu": get("cpu_throttle", 1.0), "gpu": get("gpu_throttle", 1.0) },
+        "usage": { "cpu": round(get("cpu_usage", 0.0)), "gpu": round(get("gpu_usage", 0.0)) },
+        "devices": devices,
+    })
+}
+
+fn throttle_factors(throttle_data: &Mutex<HashMap<String, f64>>) -> (f64, f64) {
+    let data = throttle_data.lock().unwrap();
+    (
+        data.get("cpu_throttle").copied().unwrap_or(1.0),
+        data.get("gpu_throttle").copied().unwrap_or(1.0),
+    )
+}
+
+fn merge(record: &mut Value, fields: Value) {
+    if let (Value::Object(record), Value::Object(fields)) = (record, fields) {
+        record.extend(fields);
+    }
+}
+
+fn unix_time() -> f64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    round(now.as_secs_f64())
+}
+
+// Three decimals are plenty and keep the lines short
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_standard_streams() {
+        for fd in ["0", "1", "2", "-1"] {
+            assert!(StatusStream::open(fd).is_err(), "{}", fd);
+        }
+        // Still open after the refusal
+        // SAFETY: fcntl(F_GETFD) only checks that the descriptor is open
+        assert_ne!(unsafe { libc::fcntl(1, libc::F_GETFD) }, -1);
+    }
+}
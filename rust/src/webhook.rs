@@ -0,0 +1,509 @@
+// Webhook sink (--webhook)
+// Every collected seed is POSTed as a JSON record to an HTTP endpoint, so results from many
+// machines end up in one place. Records are appended to an on-disk queue first and a sender
+// thread delivers them in order, retrying with exponential backoff while the endpoint is
+// unreachable; whatever is still queued at exit is sent on the next run. Records the endpoint
+// rejects (4xx other than 408 and 429) are moved to a dead-letter file instead of being retried.
+// Each request is signed with HMAC-SHA256 over timestamp and body, and the payload can be
+// redacted or encrypted.
+use bitcoin::hex::DisplayHex;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::ChaCha20Poly1305;
+use clap::ValueEnum;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+use crate::keys;
+use crate::reporter::{short_hash, Reporter};
+use crate::worker::FoundSeed;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const FIRST_RETRY: Duration = Duration::from_secs(1);
+const MAX_RETRY: Duration = Duration::from_secs(300);
+// How long queued records may still be delivered when the run ends
+const STOP_GRACE: Duration = Duration::from_secs(5);
+const MIN_SECRET_LEN: usize = 16;
+
+/// What a webhook record reveals about a seed
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebhookPayload {
+    /// Character count and a short hash of the entropy
+    Redacted,
+    /// Character count and BIP32 master key fingerprint
+    Fingerprint,
+    /// Full mnemonic phrase in plain text
+    Full,
+    /// Full record encrypted with ChaCha20-Poly1305 (key derived from the webhook secret)
+    Encrypted,
+}
+
+// Why a delivery failed
+enum Failure {
+    // Network error or an answer worth retrying (5xx, 408, 429)
+    Retry(String),
+    // The endpoint refused the record itself; sending it again would fail the same way
+    Rejected(u16),
+}
+
+// The delivery id of a record body (the other fields, mnemonic included, are skipped unparsed)
+#[derive(Deserialize)]
+struct DeliveryId {
+    delivery: String,
+}
+
+#[derive(Default)]
+struct Queue {
+    // Record bodies not delivered yet, oldest first (mirrors the queue file)
+    pending: VecDeque<Zeroizing<String>>,
+    // Consecutive failed deliveries and when to try again
+    failures: u32,
+    retry_at: Option<Instant>,
+    // Set when the run ends: deliver what is possible until then
+    closing: Option<Instant>,
+}
+
+pub struct Webhook {
+    url: String,
+    key: Zeroizing<[u8; 32]>,
+    payload: WebhookPayload,
+    // BIP39 passphrase, so fingerprints match what a hardware wallet shows
+    passphrase: Zeroizing<String>,
+    host: String,
+    queue_path: String,
+    agent: ureq::Agent,
+    queue: Mutex<Queue>,
+    wake: Condvar,
+    delivered: AtomicU64,
+    rejected: AtomicU64,
+    // Shown by the sender thread when it starts
+    warning: Option<String>,
+}
+
+impl Webhook {
+    /// Check the settings and load records left in the queue file by an earlier run
+    pub fn open(
+        url: &str,
+        secret_path: &str,
+        payload: WebhookPayload,
+        queue_path: &str,
+        passphrase: Zeroizing<String>,
+    ) -> Result<Self, String> {
+        let Some(rest) = url.strip_prefix("http://").or_else(|| url.strip_prefix("https://")) else {
+            return Err(format!("Webhook URL must start with http:// or https:// ({})", url));
+        };
+        let host = rest.split('/').next().unwrap_or_default();
+        let local = ["localhost", "127.0.0.1", "[::1]"]
+            .iter()
+            .any(|name| host == *name || host.starts_with(&format!("{}:", name)));
+        let warning = (payload == WebhookPayload::Full && url.starts_with("http://") && !local).then(|| {
+            format!("Warning: --webhook-payload full sends mnemonics unencrypted over plain HTTP to {}", host)
+        });
+
+        let content = Zeroizing::new(
+            fs::read_to_string(secret_path).map_err(|e| format!("Could not read webhook secret {}: {}", secret_path, e))?,
+        );
+        let secret = content.trim();
+        if secret.len() < MIN_SECRET_LEN {
+            return Err(format!(
+                "Webhook secret in {} must be at least {} characters",
+                secret_path, MIN_SECRET_LEN
+            ));
+        }
+
+        let pending: VecDeque<Zeroizing<String>> = match fs::read_to_string(queue_path) {
+            Ok(content) => {
+                let content = Zeroizing::new(content);
+                content
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| Zeroizing::new(line.to_string()))
+                    .collect()
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => VecDeque::new(),
+            Err(e) => return Err(format!("Could not read webhook queue {}: {}", queue_path, e)),
+        };
+
+        Ok(Self {
+            url: url.to_string(),
+            key: Zeroizing::new(Sha256::digest(secret.as_bytes()).into()),
+            payload,
+            passphrase,
+            host: sysinfo::System::host_name().unwrap_or_default(),
+            queue_path: queue_path.to_string(),
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+            queue: Mutex::new(Queue {
+                pending,
+                ..Queue::default()
+            }),
+            wake: Condvar::new(),
+            delivered: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            warning,
+        })
+    }
+
+    fn mac(&self, label: &[u8], data: &[u8]) -> Hmac<Sha256> {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(self.key.as_slice()).expect("HMAC accepts any key length");
+        mac.update(label);
+        mac.update(data);
+        mac
+    }
+
+    /// Queue the record of a newly collected seed (it is still sent if the queue file cannot be
+    /// written)
+    pub fn found(&self, found: &FoundSeed) -> Result<(), String> {
+        let delivery: String = rand::random::<[u8; 8]>().to_lower_hex_string();
+        let mut record = json!({
+            "event": "found",
+            "delivery": delivery,
+            "time": SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            "host": self.host,
+        });
+        let mut details = json!({
+            "chars": found.chars,
+            "id": short_hash(&found.entropy),
+            "bip85_index": found.bip85_index,
+            "longest_share": found.shares.as_ref().map(|split| split.longest_share_chars()),
+            "unit": found.unit,
+        });
+        if self.payload != WebhookPayload::Redacted {
+            details["fingerprint"] = match keys::master_fingerprint(&found.entropy, &self.passphrase) {
+                Ok(fingerprint) => json!(fingerprint.to_string()),
+                Err(_) => Value::Null,
+            };
+        }
+        let body = match self.payload {
+            WebhookPayload::Redacted | WebhookPayload::Fingerprint => {
+                merge(&mut record, details);
+                Zeroizing::new(record.to_string())
+            }
+            WebhookPayload::Full => {
+                merge(&mut record, details);
+                with_mnemonic(&record, &found.mnemonic())
+            }
+            WebhookPayload::Encrypted => {
+                let plaintext = with_mnemonic(&details, &found.mnemonic());
+                let key: Zeroizing<[u8; 32]> =
+                    Zeroizing::new(self.mac(b"gpuseed webhook encryption", &[]).finalize().into_bytes().into());
+                let nonce: [u8; 12] = rand::random();
+                let ciphertext = ChaCha20Poly1305::new(key.as_slice().into())
+                    .encrypt(&nonce.into(), plaintext.as_bytes())
+                    .expect("ChaCha20-Poly1305 encryption of a short record cannot fail");
+                record["encrypted"] = json!({
+                    "nonce": nonce.to_lower_hex_string(),
+                    "ciphertext": ciphertext.to_lower_hex_string(),
+                });
+                Zeroizing::new(record.to_string())
+            }
+        };
+
+        let mut queue = self.queue.lock().unwrap();
+        let written = self
+            .append(&self.queue_path, &body)
+            .map_err(|e| format!("Warning: Could not write webhook queue {}: {}", self.queue_path, e));
+        queue.pending.push_back(body);
+        self.wake.notify_one();
+        written
+    }
+
+    // Add one record to a queue or dead-letter file
+    fn append(&self, path: &str, body: &str) -> std::io::Result<()> {
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        file.write_all(body.as_bytes())?;
+        file.write_all(b"\n")
+    }
+
+    // Records the endpoint rejected, kept next to the queue file
+    fn rejected_path(&self) -> String {
+        format!("{}.rejected", self.queue_path)
+    }
+
+    // Rewrite the queue file with the records still pending
+    fn persist(&self, pending: &VecDeque<Zeroizing<String>>) -> std::io::Result<()> {
+        if pending.is_empty() {
+            return match fs::remove_file(&self.queue_path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+        let mut content = Zeroizing::new(String::with_capacity(pending.iter().map(|body| body.len() + 1).sum()));
+        for body in pending {
+            content.push_str(body);
+            content.push('\n');
+        }
+        let temp = format!("{}.tmp", self.queue_path);
+        let mut options = OpenOptions::new();
+        options.create(true).write(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(&temp)?.write_all(content.as_bytes())?;
+        fs::rename(&temp, &self.queue_path)
+    }
+
+    // One signed POST; non-2xx answers count as failures
+    fn post(&self, body: &str) -> Result<(), Failure> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs().to_string();
+        let mut mac = self.mac(timestamp.as_bytes(), b".");
+        mac.update(body.as_bytes());
+        let signature = mac.finalize().into_bytes();
+        let delivery = serde_json::from_str::<DeliveryId>(body)
+            .map(|id| id.delivery)
+            .unwrap_or_default();
+        match self
+            .agent
+            .post(&self.url)
+            .set("Content-Type", "application/json")
+            .set("X-Gpuseed-Timestamp", &timestamp)
+            .set("X-Gpuseed-Signature", &format!("sha256={}", signature.to_lower_hex_string()))
+            .set("X-Gpuseed-Delivery", &delivery)
+            .send_string(body)
+        {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(code, _)) if (400..500).contains(&code) && code != 408 && code != 429 => {
+                Err(Failure::Rejected(code))
+            }
+            Err(ureq::Error::Status(code, _)) => Err(Failure::Retry(format!("HTTP {}", code))),
+            Err(e) => Err(Failure::Retry(e.to_string())),
+        }
+    }
+
+    /// Sender thread: delivers queued records until `close` and the queue is empty (or the
+    /// endpoint keeps failing)
+    pub fn spawn(self: &Arc<Self>, reporter: Arc<Reporter>) -> JoinHandle<()> {
+        let webhook = self.clone();
+        if let Some(ref warning) = webhook.warning {
+            reporter.notice(warning);
+        }
+        let pending = webhook.queue.lock().unwrap().pending.len();
+        if pending > 0 {
+            reporter.notice(&format!("Webhook: {} records queued by an earlier run", pending));
+        }
+        std::thread::spawn(move || loop {
+            let body = {
+                let mut queue = webhook.queue.lock().unwrap();
+                loop {
+                    let now = Instant::now();
+                    if let Some(deadline) = queue.closing {
+                        if queue.pending.is_empty() || queue.failures > 0 || now >= deadline {
+                            return;
+                        }
+                    }
+                    let due = queue.retry_at.is_none_or(|at| now >= at);
+                    if let (Some(body), true) = (queue.pending.front(), due) {
+                        break body.clone();
+                    }
+                    queue = match queue.retry_at {
+                        Some(at) if !queue.pending.is_empty() => {
+                            webhook.wake.wait_timeout(queue, at.saturating_duration_since(now)).unwrap().0
+                        }
+                        _ => webhook.wake.wait(queue).unwrap(),
+                    };
+                }
+            };
+
+            let result = webhook.post(&body);
+            let mut queue = webhook.queue.lock().unwrap();
+            match result {
+                Ok(()) => {
+                    queue.pending.pop_front();
+                    if let Err(e) = webhook.persist(&queue.pending) {
+                        reporter.notice(&format!("Warning: Could not write webhook queue {}: {}", webhook.queue_path, e));
+                    }
+                    webhook.delivered.fetch_add(1, Ordering::Relaxed);
+                    if queue.failures > 0 {
+                        reporter.notice(&format!("[Webhook] Endpoint reachable again, {} records queued", queue.pending.len()));
+                    }
+                    queue.failures = 0;
+                    queue.retry_at = None;
+                }
+                Err(Failure::Rejected(code)) => {
+                    // Dropped from the queue either way, so one bad record does not hold up the rest
+                    queue.pending.pop_front();
+                    if let Err(e) = webhook.persist(&queue.pending) {
+                        reporter.notice(&format!("Warning: Could not write webhook queue {}: {}", webhook.queue_path, e));
+                    }
+                    webhook.rejected.fetch_add(1, Ordering::Relaxed);
+                    let kept = match webhook.append(&webhook.rejected_path(), &body) {
+                        Ok(()) => format!("moved to {}", webhook.rejected_path()),
+                        Err(e) => format!("dropped (could not write {}: {})", webhook.rejected_path(), e),
+                    };
+                    reporter.notice(&format!("[Webhook] Endpoint rejected a record (HTTP {}), {}", code, kept));
+                    queue.failures = 0;
+                    queue.retry_at = None;
+                }
+                Err(Failure::Retry(e)) => {
+                    queue.failures += 1;
+                    let delay = FIRST_RETRY.saturating_mul(1 << (queue.failures - 1).min(16)).min(MAX_RETRY);
+                    queue.retry_at = Some(Instant::now() + delay);
+                    if queue.failures == 1 {
+                        reporter.notice(&format!(
+                            "[Webhook] Delivery failed ({}), {} records queued, retrying with backoff",
+                            e,
+                            queue.pending.len()
+                        ));
+                    }
+                }
+            }
+        })
+    }
+
+    /// End of the run: deliver what is left for a few seconds, then let the sender exit
+    pub fn close(&self) {
+        self.queue.lock().unwrap().closing = Some(Instant::now() + STOP_GRACE);
+        self.wake.notify_one();
+    }
+
+    pub fn summary(&self) -> String {
+        let pending = self.queue.lock().unwrap().pending.len();
+        let delivered = self.delivered.load(Ordering::Relaxed);
+        let mut summary = format!("{} records delivered", delivered);
+        let rejected = self.rejected.load(Ordering::Relaxed);
+        if rejected > 0 {
+            write!(summary, ", {} rejected (kept in {})", rejected, self.rejected_path()).unwrap();
+        }
+        if pending > 0 {
+            write!(summary, ", {} still queued in {} (sent on the next run)", pending, self.queue_path).unwrap();
+        }
+        summary
+    }
+}
+
+// A JSON object with the mnemonic added as its last field. The phrase goes straight into a
+// presized zeroizing buffer; a JSON value would keep copies of it that are never wiped
+fn with_mnemonic(fields: &Value, mnemonic: &str) -> Zeroizing<String> {
+    let json = fields.to_string();
+    let mut out = Zeroizing::new(String::with_capacity(json.len() + mnemonic.len() + 16));
+    out.push_str(&json[..json.len() - 1]);
+    if json.len() > 2 {
+        out.push(',');
+    }
+    // Mnemonic words are plain lowercase ASCII, nothing to escape
+    write!(out, "\"mnemonic\":\"{}\"}}", mnemonic).unwrap();
+    out
+}
+
+fn merge(record: &mut Value, fields: Value) {
+    if let (Value::Object(record), Value::Object(fields)) = (record, fields) {
+        record.extend(fields);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reporter::ConsoleMode;
+    use std::path::Path;
+    use std::sync::mpsc;
+
+    const SECRET: &str = "webhook test secret";
+
+    // What the endpoint received: status it answered, timestamp, signature and body
+    type Received = (u16, String, String, String);
+
+    fn header(request: &tiny_http::Request, name: &str) -> String {
+        request
+            .headers()
+            .iter()
+            .find(|header| header.field.as_str().as_str().eq_ignore_ascii_case(name))
+            .map(|header| header.value.to_string())
+            .unwrap_or_default()
+    }
+
+    // Endpoint answering with the given statuses in turn
+    fn endpoint(statuses: Vec<u16>) -> (String, mpsc::Receiver<Received>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", server.server_addr().to_ip().unwrap());
+        let (sender, received) = mpsc::channel();
+        std::thread::spawn(move || {
+            for status in statuses {
+                let mut request = server.recv().unwrap();
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let (timestamp, signature) =
+                    (header(&request, "X-Gpuseed-Timestamp"), header(&request, "X-Gpuseed-Signature"));
+                request.respond(tiny_http::Response::empty(status)).unwrap();
+                sender.send((status, timestamp, signature, body)).unwrap();
+            }
+        });
+        (url, received)
+    }
+
+    fn signature(timestamp: &str, body: &str) -> String {
+        let key = Sha256::digest(SECRET.as_bytes());
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&key).unwrap();
+        mac.update(timestamp.as_bytes());
+        mac.update(b".");
+        mac.update(body.as_bytes());
+        format!("sha256={}", mac.finalize().into_bytes().to_lower_hex_string())
+    }
+
+    #[test]
+    fn queues_offline_and_delivers_signed_records() {
+        let dir = std::env::temp_dir().join(format!("gpuseed-webhook-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let secret_path = dir.join("webhook.key");
+        fs::write(&secret_path, format!("{}\n", SECRET)).unwrap();
+        let queue_path = dir.join("queue.ndjson").to_str().unwrap().to_string();
+        let rejected_path = format!("{}.rejected", queue_path);
+        fs::remove_file(&queue_path).ok();
+        fs::remove_file(&rejected_path).ok();
+        let open = |url: &str, payload| {
+            Webhook::open(url, secret_path.to_str().unwrap(), payload, &queue_path, Zeroizing::default()).unwrap()
+        };
+
+        // Offline (nothing listens on the port): the record waits in the queue file
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let offline = open(&format!("http://{}/hook", closed), WebhookPayload::Redacted);
+        offline.found(&FoundSeed::from_entropy([0x11; 16])).unwrap();
+        let queued = fs::read_to_string(&queue_path).unwrap();
+        assert_eq!(queued.lines().count(), 1);
+        drop(offline);
+
+        // The next run sends it: retried after a 500, then delivered and removed from the queue
+        let (url, received) = endpoint(vec![500, 200, 400]);
+        let webhook = Arc::new(open(&url, WebhookPayload::Full));
+        assert_eq!(webhook.queue.lock().unwrap().pending.len(), 1);
+        let handle = webhook.spawn(Arc::new(Reporter::new(ConsoleMode::Redacted, Zeroizing::default())));
+        let timeout = Duration::from_secs(10);
+        for status in [500, 200] {
+            let (answered, timestamp, signed, body) = received.recv_timeout(timeout).unwrap();
+            assert_eq!(answered, status);
+            assert_eq!(body, queued.trim_end());
+            assert_eq!(signed, signature(&timestamp, &body));
+        }
+
+        // A rejected record is not retried but moved to the dead-letter file
+        let found = FoundSeed::from_entropy([0x22; 16]);
+        webhook.found(&found).unwrap();
+        let (answered, timestamp, signed, body) = received.recv_timeout(timeout).unwrap();
+        assert_eq!(answered, 400);
+        assert_eq!(signed, signature(&timestamp, &body));
+        let record: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(record["mnemonic"], found.mnemonic().as_str());
+        assert_eq!(record["chars"], found.chars);
+
+        webhook.close();
+        handle.join().unwrap();
+        assert!(!Path::new(&queue_path).exists());
+        assert_eq!(fs::read_to_string(&rejected_path).unwrap(), format!("{}\n", body));
+        assert_eq!(
+            webhook.summary(),
+            format!("1 records delivered, 1 rejected (kept in {})", rejected_path)
+        );
+    }
+}
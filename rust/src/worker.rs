@@ -1,91 +1,247 @@
 use bip39::{Language, Mnemonic};
 use rand::RngCore;
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use zeroize::{Zeroize, Zeroizing};
 
-use crate::gpu::GpuContext;
+use crate::bip85::Bip85Source;
+use crate::cluster::UnitSource;
+use crate::feeder::{GpuFeeder, GpuFeeders};
+use crate::gpu::{GpuContext, GpuDevice, GpuRng};
+use crate::keys::SeedKeys;
+use crate::limits::RunLimits;
+use crate::mixing::{MixRecord, UserEntropy};
+use crate::reporter::Reporter;
+use crate::session::GpuBatch;
+use crate::secure::{EntropyBatch, SecretVec};
+use crate::slip39::{ShareScoring, Split};
+use crate::stats::{Backend, RunStats, MAX_SCORE};
+
+// How often workers add their score histograms to the shared one
+const SCORE_MERGE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A mnemonic that passed the collection rules.
+/// Only the entropy is stored (inline, so a locked results store covers it);
+/// the phrase is rebuilt on demand and wiped again after use.
+#[derive(Clone)]
+pub struct FoundSeed {
+    pub entropy: [u8; 16],
+    pub chars: usize,
+    // Fingerprint and account xpubs, filled in by the post-processing stage
+    pub keys: Option<SeedKeys>,
+    // Best SLIP-39 split found by search-time share scoring
+    pub shares: Option<Split>,
+    // Child index when the seed was derived from a BIP85 master
+    pub bip85_index: Option<u32>,
+    // Machine entropy and counter when user entropy was mixed in
+    pub mix: Option<MixRecord>,
+    // Work unit and candidate offset in a distributed or partitioned search
+    pub unit: Option<(u64, u64)>,
+}
+
+impl FoundSeed {
+    pub fn from_entropy(entropy: [u8; 16]) -> Self {
+        let mnemonic = Mnemonic::from_entropy_in(Language::English, &entropy)
+            .expect("16 bytes is always valid BIP39 entropy");
+        Self {
+            entropy,
+            chars: mnemonic.words().map(str::len).sum(),
+            keys: None,
+            shares: None,
+            bip85_index: None,
+            mix: None,
+            unit: None,
+        }
+    }
+
+    pub fn mnemonic(&self) -> Zeroizing<String> {
+        let mnemonic = Mnemonic::from_entropy_in(Language::English, &self.entropy)
+            .expect("16 bytes is always valid BIP39 entropy");
+        Zeroizing::new(mnemonic.to_string())
+    }
+}
+
+impl Zeroize for FoundSeed {
+    fn zeroize(&mut self) {
+        self.entropy.zeroize();
+        self.chars = 0;
+        self.keys = None;
+        // Share strings are zeroizing, dropping them wipes them
+        self.shares = None;
+        self.bip85_index = None;
+        if let Some(ref mut mix) = self.mix {
+            mix.machine_entropy.zeroize();
+        }
+        self.mix = None;
+        self.unit = None;
+    }
+}
 
 pub struct WorkerPool {
     num_workers: usize,
     batch_size: usize,
-    threshold: usize,
-    count_per_threshold: usize,
-    use_gpu: bool,
+    rules: Arc<CollectionRules>,
     stop_flag: Arc<AtomicBool>,
     throttle_data: Arc<Mutex<HashMap<String, f64>>>,
-    iterations: Arc<AtomicU64>,
-    gpu_context: Option<Arc<GpuContext>>,
+    stats: Arc<RunStats>,
+    feeders: Option<Arc<GpuFeeders>>,
+    share_scoring: Option<ShareScoring>,
+    bip85: Option<Arc<Bip85Source>>,
+    user_entropy: Option<Arc<UserEntropy>>,
+    units: Option<Arc<UnitSource>>,
+    limits: Arc<RunLimits>,
+    reporter: Arc<Reporter>,
+    // Collected seeds, shared with the control socket (flush-results)
+    results: Arc<Mutex<SecretVec<FoundSeed>>>,
 }
 
 impl WorkerPool {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         num_workers: usize,
         batch_size: usize,
-        threshold: usize,
-        count_per_threshold: usize,
+        rules: Arc<CollectionRules>,
         use_gpu: bool,
+        gpu_devices: &[GpuDevice],
+        simulate_gpu: Option<u64>,
+        gpu_rng: GpuRng,
+        gpu_queue: usize,
+        lock_memory: bool,
+        share_scoring: Option<ShareScoring>,
+        bip85: Option<Arc<Bip85Source>>,
+        user_entropy: Option<Arc<UserEntropy>>,
+        units: Option<Arc<UnitSource>>,
+        limits: Arc<RunLimits>,
         stop_flag: Arc<AtomicBool>,
         throttle_data: Arc<Mutex<HashMap<String, f64>>>,
+        stats: Arc<RunStats>,
+        reporter: Arc<Reporter>,
     ) -> Self {
-        // Initialize GPU context if GPU mode is enabled
-        // Note: Each worker thread will need to create its own GPU context
-        // because CUDA contexts are not thread-safe
-        let gpu_context = if use_gpu {
-            let ctx = GpuContext::new();
-            if ctx.is_available() {
-                Some(Arc::new(ctx))
-            } else {
-                None
-            }
+        // Initialize a GPU context per device if GPU mode is enabled
+        // Note: Only the device's feeder thread uses it (CUDA contexts are not thread-safe)
+        let feeders: Vec<Arc<GpuFeeder>> = if use_gpu {
+            gpu_devices
+                .iter()
+                .filter_map(|device| {
+                    // The session is sized for the full batch (throttling only makes batches smaller)
+                    let ctx = match simulate_gpu {
+                        Some(delay_ms) => GpuContext::simulated(
+                            device.index,
+                            batch_size,
+                            gpu_rng,
+                            Duration::from_millis(delay_ms),
+                            reporter.clone(),
+                        ),
+                        None => GpuContext::new(device.index, batch_size, gpu_rng, reporter.clone()),
+                    };
+                    ctx.is_available().then(|| {
+                        Arc::new(GpuFeeder::new(
+                            ctx,
+                            user_entropy.is_none(),
+                            batch_size,
+                            gpu_queue,
+                            rules.clone(),
+                            limits.clone(),
+                            throttle_data.clone(),
+                            stats.clone(),
+                            reporter.clone(),
+                        ))
+                    })
+                })
+                .collect()
         } else {
+            Vec::new()
+        };
+        let feeders = if !feeders.is_empty() {
+            Some(Arc::new(GpuFeeders::new(feeders)))
+        } else {
+            if use_gpu {
+                reporter.event("backend_fallback", serde_json::json!({
+                    "from": "gpu", "to": "cpu", "error": "GPU not available",
+                }));
+            }
             None
         };
         
         Self {
             num_workers,
             batch_size,
-            threshold,
-            count_per_threshold,
-            use_gpu,
+            rules,
             stop_flag,
             throttle_data,
-            iterations: Arc::new(AtomicU64::new(0)),
-            gpu_context,
+            stats,
+            feeders,
+            share_scoring,
+            bip85,
+            user_entropy,
+            units,
+            limits,
+            reporter,
+            results: Arc::new(Mutex::new(SecretVec::new(lock_memory))),
         }
     }
     
-    pub fn run(&self) -> Vec<(String, usize)> {
-        let results = Arc::new(Mutex::new(Vec::new()));
-        let found_counts: Arc<Mutex<HashMap<usize, usize>>> = Arc::new(Mutex::new(HashMap::new()));
+    /// The GPU feeders (None when running on the CPU)
+    pub fn gpu_feeders(&self) -> Option<Arc<GpuFeeders>> {
+        self.feeders.clone()
+    }
+    
+    /// Seeds collected so far (workers keep adding to them while running)
+    pub fn results(&self) -> Arc<Mutex<SecretVec<FoundSeed>>> {
+        self.results.clone()
+    }
+    
+    pub fn run(&self) -> SecretVec<FoundSeed> {
+        let results = self.results.clone();
+        
+        // One thread per device drives it for all workers
+        let feeder_stop = Arc::new(AtomicBool::new(false));
+        let feeder_handles: Vec<_> = self
+            .feeders
+            .iter()
+            .flat_map(|feeders| feeders.feeders())
+            .map(|feeder| {
+                let feeder = feeder.clone();
+                let feeder_stop = feeder_stop.clone();
+                thread::spawn(move || feeder.run(&feeder_stop))
+            })
+            .collect();
         
         let handles: Vec<_> = (0..self.num_workers)
-            .map(|_| {
+            .map(|worker| {
                 let stop_flag = self.stop_flag.clone();
                 let throttle_data = self.throttle_data.clone();
                 let results = results.clone();
-                let found_counts = found_counts.clone();
-                let iterations = self.iterations.clone();
+                let stats = self.stats.clone();
                 let batch_size = self.batch_size;
-                let threshold = self.threshold;
-                let count_per_threshold = self.count_per_threshold;
-                let use_gpu = self.use_gpu;
-                let gpu_context = self.gpu_context.clone();
+                let rules = self.rules.clone();
+                let feeders = self.feeders.clone();
+                let share_scoring = self.share_scoring.clone();
+                let bip85 = self.bip85.clone();
+                let user_entropy = self.user_entropy.clone();
+                let units = self.units.clone();
+                let limits = self.limits.clone();
+                let reporter = self.reporter.clone();
                 
                 thread::spawn(move || {
                     Self::worker_loop(
+                        worker,
                         stop_flag,
                         throttle_data,
                         results,
-                        found_counts,
-                        iterations,
+                        stats,
                         batch_size,
-                        threshold,
-                        count_per_threshold,
-                        use_gpu,
-                        gpu_context,
+                        rules,
+                        feeders,
+                        share_scoring,
+                        bip85,
+                        user_entropy,
+                        units,
+                        limits,
+                        reporter,
                     );
                 })
             })
@@ -95,136 +251,181 @@ impl WorkerPool {
         for handle in handles {
             handle.join().ok();
         }
+        feeder_stop.store(true, Ordering::Relaxed);
+        for handle in feeder_handles {
+            handle.join().ok();
+        }
         
-        // Safely unwrap results
-        Arc::try_unwrap(results)
-            .ok()
-            .and_then(|m| m.into_inner().ok())
-            .unwrap_or_default()
+        // Take the results out rather than cloning secrets
+        std::mem::take(&mut *self.results.lock().unwrap())
     }
     
+    #[allow(clippy::too_many_arguments)]
     fn worker_loop(
+        worker: usize,
         stop_flag: Arc<AtomicBool>,
         throttle_data: Arc<Mutex<HashMap<String, f64>>>,
-        results: Arc<Mutex<Vec<(String, usize)>>>,
-        found_counts: Arc<Mutex<HashMap<usize, usize>>>,
-        iterations: Arc<AtomicU64>,
+        results: Arc<Mutex<SecretVec<FoundSeed>>>,
+        stats: Arc<RunStats>,
         batch_size: usize,
-        threshold: usize,
-        count_per_threshold: usize,
-        use_gpu: bool,
-        gpu_context: Option<Arc<GpuContext>>,
+        rules: Arc<CollectionRules>,
+        feeders: Option<Arc<GpuFeeders>>,
+        share_scoring: Option<ShareScoring>,
+        bip85: Option<Arc<Bip85Source>>,
+        user_entropy: Option<Arc<UserEntropy>>,
+        units: Option<Arc<UnitSource>>,
+        limits: Arc<RunLimits>,
+        reporter: Arc<Reporter>,
     ) {
         let mut rng = rand::thread_rng();
+        // Score histogram of this thread, merged into the shared one periodically
+        let mut scores = [0u64; MAX_SCORE + 1];
+        let mut last_merge = Instant::now();
         
         loop {
-            // Check if we should stop
-            if stop_flag.load(Ordering::Relaxed) {
+            // Check if we should stop (also while paused, so time limits still end the run)
+            if stop_flag.load(Ordering::Relaxed) || limits.check_time(&stop_flag) {
                 break;
             }
             
-            // Check throttle factor
-            let throttle_factor = {
-                let data = throttle_data.lock().unwrap();
-                let cpu_throttle = data.get("cpu_throttle").copied().unwrap_or(1.0);
-                let gpu_throttle = data.get("gpu_throttle").copied().unwrap_or(1.0);
-                cpu_throttle.min(gpu_throttle)
-            };
+            // Idle while the run is paused
+            if stats.is_paused() {
+                thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+            
+            // Check throttle factor (CPU batches only; each GPU feeder applies its own device's)
+            let throttle_factor = throttle_data.lock().unwrap().get("cpu_throttle").copied().unwrap_or(1.0);
+            
+            // Rules can change while running (SIGHUP); each batch uses one consistent pair
+            let (threshold, count_per_threshold) = rules.get();
             
             // Adjust batch size based on throttle
             let adjusted_batch_size = std::cmp::max(1, (batch_size as f64 * throttle_factor) as usize);
             
-            // Try to use GPU if available, otherwise fallback to CPU
-            let entropy_batch = if use_gpu {
-                if let Some(ref gpu_ctx) = gpu_context {
-                    // Try GPU generation - log attempt (first time only)
-                    static GPU_ATTEMPT_LOGGED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
-                    if !GPU_ATTEMPT_LOGGED.swap(true, std::sync::atomic::Ordering::Relaxed) {
-                        eprintln!("[GPU] Attempting to generate entropy on GPU (batch size: {})", adjusted_batch_size);
+            // Take a batch from a GPU feeder if one is queued, otherwise fall back to the CPU
+            // The GPU scores its candidates itself and returns only the hits, unless user entropy
+            // is mixed in (that changes every candidate, so the GPU then only generates entropy)
+            let gpu_batch = feeders.as_ref().and_then(|feeders| feeders.take(worker, threshold));
+            let (entropy_batch, scored) = match gpu_batch {
+                Some(GpuBatch::Entropy(batch)) => (Some(batch), None),
+                Some(GpuBatch::Scored(scored)) => (None, Some(scored)),
+                None => (None, None),
+            };
+            
+            // Generate entropy batch (BIP85, GPU or CPU)
+            // Batches are zeroizing, so the entropy is wiped once the batch is processed
+            let mut bip85_start = None;
+            let mut unit_slice = None;
+            let backend = if entropy_batch.is_some() || scored.is_some() { Backend::Gpu } else { Backend::Cpu };
+            let entropies: EntropyBatch = if let Some(ref source) = bip85 {
+                // Children at consecutive indices, claimed per batch so workers never overlap
+                match source.next_batch(adjusted_batch_size) {
+                    Some((start, batch)) => {
+                        bip85_start = Some(start);
+                        batch
                     }
-                    
-                    match gpu_ctx.generate_entropy_batch(adjusted_batch_size) {
-                        Ok(batch) => {
-                            // GPU generation succeeded - log first time only
-                            static GPU_SUCCESS_LOGGED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
-                            if !GPU_SUCCESS_LOGGED.swap(true, std::sync::atomic::Ordering::Relaxed) {
-                                eprintln!("[GPU] Successfully generating entropy on GPU (batch size: {})", adjusted_batch_size);
-                            }
-                            Some(batch)
-                        }
-                        Err(e) => {
-                            // GPU generation failed, fallback to CPU
-                            // Only log once to avoid spam
-                            static GPU_ERROR_LOGGED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
-                            if !GPU_ERROR_LOGGED.swap(true, std::sync::atomic::Ordering::Relaxed) {
-                                eprintln!("[GPU] ERROR: GPU generation failed: {}. Using CPU fallback.", e);
-                            }
-                            None
+                    None => {
+                        static BIP85_DONE_LOGGED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+                        if !BIP85_DONE_LOGGED.swap(true, std::sync::atomic::Ordering::Relaxed) {
+                            reporter.notice("[BIP85] All child indices searched.");
                         }
+                        break;
                     }
-                } else {
-                    None
                 }
-            } else {
-                None
-            };
-            
-            // Generate entropy batch (GPU or CPU)
-            let entropies: Vec<[u8; 16]> = if let Some(gpu_batch) = entropy_batch {
+            } else if let Some(ref source) = units {
+                // Work units of a distributed search (--coordinator / --join)
+                match source.next_batch(adjusted_batch_size) {
+                    Some((slice, batch)) => {
+                        unit_slice = Some(slice);
+                        batch
+                    }
+                    None => break,
+                }
+            } else if let Some(gpu_batch) = entropy_batch {
                 gpu_batch
+            } else if scored.is_some() {
+                // Scored on the GPU: only its hits are handled below
+                Zeroizing::new(Vec::new())
             } else {
                 // CPU fallback
-                let mut batch = Vec::with_capacity(adjusted_batch_size);
+                let mut batch = Zeroizing::new(Vec::with_capacity(adjusted_batch_size));
                 for _ in 0..adjusted_batch_size {
                     let mut entropy = [0u8; 16];
                     rng.fill_bytes(&mut entropy);
                     batch.push(entropy);
+                    entropy.zeroize();
                 }
                 batch
             };
             
             // Process each entropy in the batch
-            for entropy in entropies {
+            // User entropy: every candidate gets its own counter, claimed per batch
+            let mix_start = user_entropy.as_ref().map_or(0, |user| user.claim(entropies.len()));
+            let mut processed = 0;
+            
+            for (offset, machine_entropy) in entropies.iter().enumerate() {
                 if stop_flag.load(Ordering::Relaxed) {
-                    return;
+                    break;
                 }
                 
-                // Increment iteration counter
-                iterations.fetch_add(1, Ordering::Relaxed);
+                let counter = mix_start + offset as u64;
+                let mixed = user_entropy
+                    .as_ref()
+                    .map(|user| Zeroizing::new(user.mix(machine_entropy, counter)));
+                let entropy = mixed.as_deref().unwrap_or(machine_entropy);
+                
+                // Increment iteration counter (candidates past --max-iterations are not processed)
+                if stats.claim_iterations(1, limits.max_iterations()) == 0 {
+                    limits.stop("max_iterations", &stop_flag);
+                    break;
+                }
+                processed += 1;
                 
-                // Convert to mnemonic
-                match Mnemonic::from_entropy_in(Language::English, &entropy) {
+                // Convert to mnemonic (Mnemonic wipes its word indices on drop)
+                match Mnemonic::from_entropy_in(Language::English, entropy) {
                     Ok(mnemonic) => {
-                        let mnemonic_str = mnemonic.to_string();
-                        let total_chars = mnemonic_str.replace(' ', "").len();
-
-                        // Only collect seeds with less than 46 characters
-                        // <= 42 chars: no limit (collect all unique)
-                        // 43-45 chars: limit of 5 per character count
-                        if total_chars < 46 {
-                            // Lock both structures together to avoid race conditions
-                            let mut counts = found_counts.lock().unwrap();
-                            let count = counts.entry(total_chars).or_insert(0);
+                        // Count characters without building the phrase string
+                        let total_chars: usize = mnemonic.words().map(str::len).sum();
+                        let record = |shares| FoundSeed {
+                            entropy: *entropy,
+                            chars: total_chars,
+                            keys: None,
+                            shares,
+                            bip85_index: bip85_start.map(|start| start + offset as u32),
+                            mix: mixed.as_ref().map(|_| MixRecord {
+                                machine_entropy: *machine_entropy,
+                                counter,
+                            }),
+                            unit: unit_slice.map(|slice| slice.position(offset as u64)),
+                        };
 
-                            // Determine if we should add based on character count
-                            let should_add = if total_chars <= 42 {
-                                // No limit for 42 or less
-                                true
-                            } else {
-                                // Limit of 5 for 43-45 characters
-                                *count < 5
-                            };
-
-                            if should_add {
-                                // Check if we already have this mnemonic
-                                let mut results_guard = results.lock().unwrap();
-                                if !results_guard.iter().any(|(m, _)| m == &mnemonic_str) {
-                                    results_guard.push((mnemonic_str.clone(), total_chars));
-                                    *count += 1;
+                        scores[total_chars] += 1;
+                        if stats.best_chars().is_none_or(|best| total_chars < best) {
+                            stats.offer_best(record(None));
+                            if let (Some(ref units), Some(slice)) = (&units, unit_slice) {
+                                units.best(slice, offset as u64);
+                            }
+                        }
 
-                                    println!("\nMnemonic: {}", mnemonic_str);
-                                    println!("Total characters: {}", total_chars);
-                                }
+                        // Only collect seeds below the character threshold
+                        // <= 42 chars: no limit (collect all unique)
+                        // 43 chars up to the threshold: limited per character count
+                        if total_chars < threshold {
+                            let collected = consider(
+                                entropy,
+                                total_chars,
+                                count_per_threshold,
+                                share_scoring.as_ref(),
+                                record,
+                                &results,
+                                &stats,
+                                &reporter,
+                                &limits,
+                                &stop_flag,
+                            );
+                            if let (true, Some(ref units), Some(slice)) = (collected, &units, unit_slice) {
+                                units.found(slice, offset as u64);
                             }
                         }
                     }
@@ -232,25 +433,189 @@ impl WorkerPool {
                 }
             }
             
+            // GPU-scored batch: count, histogram and best candidate come from the device, and
+            // only the hits go through the collection rules
+            if let Some(ref scored) = scored {
+                let candidates = scored.candidates as u64;
+                // Batches are sized to what is left of --max-iterations, so the run stops as soon
+                // as it is reached; another GPU worker may also have used it up meanwhile
+                let max_iterations = limits.max_iterations();
+                processed = stats.claim_iterations(candidates, max_iterations);
+                if max_iterations.is_some_and(|max| stats.iterations.load(Ordering::Relaxed) >= max) {
+                    limits.stop("max_iterations", &stop_flag);
+                }
+                // The histogram covers the whole batch, so a batch cut short is left out of it
+                if processed == candidates {
+                    for (total, count) in scores.iter_mut().zip(scored.histogram) {
+                        *total += count;
+                    }
+                }
+                if let Some(ref best) = scored.best {
+                    if (best.index as u64) < processed && stats.best_chars().is_none_or(|chars| best.chars < chars) {
+                        stats.offer_best(FoundSeed::from_entropy(best.entropy));
+                    }
+                }
+                for hit in scored.hits.iter().filter(|hit| (hit.index as u64) < processed) {
+                    if stop_flag.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    consider(
+                        &hit.entropy,
+                        hit.chars,
+                        count_per_threshold,
+                        share_scoring.as_ref(),
+                        |shares| FoundSeed {
+                            shares,
+                            ..FoundSeed::from_entropy(hit.entropy)
+                        },
+                        &results,
+                        &stats,
+                        &reporter,
+                        &limits,
+                        &stop_flag,
+                    );
+                }
+            }
+            stats.add_batch(worker, backend, processed);
+            if let (Some(ref units), Some(slice)) = (&units, unit_slice) {
+                units.complete(slice, processed);
+            }
+            if last_merge.elapsed() >= SCORE_MERGE_INTERVAL {
+                stats.merge_scores(&mut scores);
+                last_merge = Instant::now();
+            }
+
+            if stop_flag.load(Ordering::Relaxed) {
+                break;
+            }
+            
             // Small delay to prevent CPU spinning
             if throttle_factor < 1.0 {
                 thread::sleep(Duration::from_millis(1));
             }
         }
+
+        stats.merge_scores(&mut scores);
     }
-    
-    pub fn get_iterations(&self) -> u64 {
-        self.iterations.load(Ordering::Relaxed)
+}
+
+/// Collect a candidate below the threshold. SLIP-39 share scoring runs PBKDF2, so it is done
+/// first, outside the locks, and only for seeds that would still be collected.
+#[allow(clippy::too_many_arguments)]
+fn consider(
+    entropy: &[u8; 16],
+    chars: usize,
+    count_per_threshold: usize,
+    share_scoring: Option<&ShareScoring>,
+    record: impl FnOnce(Option<Split>) -> FoundSeed,
+    results: &Mutex<SecretVec<FoundSeed>>,
+    stats: &RunStats,
+    reporter: &Reporter,
+    limits: &RunLimits,
+    stop_flag: &AtomicBool,
+) -> bool {
+    let shares = match share_scoring {
+        Some(scoring) => {
+            let has_room = {
+                let counts = stats.found_counts.lock().unwrap();
+                let count = counts.get(&chars).copied().unwrap_or(0);
+                should_add(chars, count, count_per_threshold)
+                    && !results.lock().unwrap().iter().any(|found| &found.entropy == entropy)
+            };
+            if !has_room {
+                return false;
+            }
+            match scoring.score(entropy) {
+                Ok(split) if scoring.accepts(&split) => Some(split),
+                Ok(_) => return false,
+                Err(e) => {
+                    reporter.notice(&format!("[SLIP-39] Share scoring failed: {}", e));
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+    collect(entropy, chars, count_per_threshold, || record(shares), results, stats, reporter, limits, stop_flag)
+}
+
+/// Collect a seed below the threshold if its bucket still has room and it is new.
+/// The record is only built (copying the entropy) when the seed is added.
+#[allow(clippy::too_many_arguments)]
+pub fn collect(
+    entropy: &[u8; 16],
+    chars: usize,
+    count_per_threshold: usize,
+    record: impl FnOnce() -> FoundSeed,
+    results: &Mutex<SecretVec<FoundSeed>>,
+    stats: &RunStats,
+    reporter: &Reporter,
+    limits: &RunLimits,
+    stop_flag: &AtomicBool,
+) -> bool {
+    let found = {
+        // Lock both structures together to avoid race conditions
+        let mut counts = stats.found_counts.lock().unwrap();
+        let count = counts.entry(chars).or_insert(0);
+        if !should_add(chars, *count, count_per_threshold) {
+            return false;
+        }
+
+        // Check if we already have this mnemonic
+        let mut results_guard = results.lock().unwrap();
+        if results_guard.iter().any(|found| &found.entropy == entropy) {
+            return false;
+        }
+        let found = Zeroizing::new(record());
+        results_guard.push((*found).clone());
+        *count += 1;
+        limits.check_goals(&counts, stop_flag);
+        found
+    };
+    // Reporting derives fingerprints and sends webhook records, so the other workers are not
+    // kept waiting on the locks meanwhile
+    reporter.found(&found);
+    true
+}
+
+/// Collection rules (threshold and per-count limit), adjustable while the search runs
+pub struct CollectionRules {
+    // Both in one lock, so workers never see a half-applied change
+    rules: Mutex<(usize, usize)>,
+}
+
+impl CollectionRules {
+    pub fn new(threshold: usize, count_per_threshold: usize) -> Result<Self, String> {
+        check_rules(threshold, count_per_threshold)?;
+        Ok(Self {
+            rules: Mutex::new((threshold, count_per_threshold)),
+        })
     }
-    
-    pub fn get_iterations_counter(&self) -> Arc<AtomicU64> {
-        self.iterations.clone()
+
+    /// (threshold, count per threshold)
+    pub fn get(&self) -> (usize, usize) {
+        *self.rules.lock().unwrap()
     }
-    
-    pub fn cleanup(&self) {
-        // Cleanup GPU contexts if GPU was used
-        if let Some(ref gpu_ctx) = self.gpu_context {
-            gpu_ctx.cleanup();
-        }
+
+    pub fn set(&self, threshold: usize, count_per_threshold: usize) -> Result<(), String> {
+        check_rules(threshold, count_per_threshold)?;
+        *self.rules.lock().unwrap() = (threshold, count_per_threshold);
+        Ok(())
     }
 }
+
+pub fn check_rules(threshold: usize, count_per_threshold: usize) -> Result<(), String> {
+    if threshold == 0 || threshold > 200 {
+        return Err("Threshold must be between 1 and 200".to_string());
+    }
+    if count_per_threshold == 0 || count_per_threshold > 1000 {
+        return Err("Count must be between 1 and 1000".to_string());
+    }
+    Ok(())
+}
+
+// Collection rule: seeds of 42 characters or less are unlimited,
+// 43 characters and above are limited per character count
+fn should_add(total_chars: usize, count: usize, count_per_threshold: usize) -> bool {
+    total_chars <= 42 || count < count_per_threshold
+}